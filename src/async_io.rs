@@ -1,4 +1,5 @@
 use crate::{
+    backend::{Backend, Visa},
     enums::{
         attribute::{self, SpecAttr},
         event,
@@ -22,13 +23,16 @@ use visa_sys as vs;
 
 const CANCELED_CAP: usize = 32;
 
-pub struct AsyncInstrument {
-    pub(super) instr: Instrument,
+/// Bytes transferred and the completion code of an asynchronous job
+pub(crate) type JobResult = Result<(usize, CompletionCode)>;
+
+pub struct AsyncInstrument<B: Backend = Visa> {
+    pub(super) instr: Instrument<B>,
     callback: Box<AsyncIoCallbackPack>,
 }
 
-impl From<AsyncInstrument> for Instrument {
-    fn from(async_instr: AsyncInstrument) -> Self {
+impl<B: Backend> From<AsyncInstrument<B>> for Instrument<B> {
+    fn from(async_instr: AsyncInstrument<B>) -> Self {
         let async_instr = std::mem::ManuallyDrop::new(async_instr);
         // SAFETY: We intentionally prevent drop of `async_instr` and take ownership of `instr`.
         // `instr` is not used afterward, and `async_instr` is never dropped.
@@ -36,16 +40,16 @@ impl From<AsyncInstrument> for Instrument {
     }
 }
 
-impl AsyncInstrument {
-    pub fn new(instr: Instrument) -> Result<Self> {
+impl<B: Backend> AsyncInstrument<B> {
+    pub fn new(instr: Instrument<B>) -> Result<Self> {
         use crate::enums::attribute::HasAttribute;
         instr.set_attr(attribute::AttrTermcharEn::VI_TRUE)?;
         instr.set_attr(attribute::AttrSuppressEndEn::VI_FALSE)?;
         let mut callback = Box::new(AsyncIoCallbackPack::new());
-        wrap_raw_error_in_unsafe!(vs::viInstallHandler(
+        wrap_raw_error_in_unsafe!(B::install_handler(
             instr.as_raw_ss(),
             event::EventKind::EventIoCompletion as _,
-            Some(AsyncIoCallbackPack::call_in_c),
+            Some(AsyncIoCallbackPack::call_in_c::<B>),
             &mut *callback as *mut _ as _,
         ))?;
         instr.enable_event(
@@ -55,15 +59,15 @@ impl AsyncInstrument {
         Ok(Self { instr, callback })
     }
 
-    pub fn instrument(&self) -> &Instrument {
+    pub fn instrument(&self) -> &Instrument<B> {
         &self.instr
     }
 
-    pub fn async_read<'a>(&'a self, buf: &'a mut [u8]) -> AsyncRead<'a> {
+    pub fn async_read<'a>(&'a self, buf: &'a mut [u8]) -> AsyncRead<'a, B> {
         AsyncRead::new(self, buf)
     }
 
-    pub fn async_write<'a>(&'a self, buf: &'a [u8]) -> AsyncWrite<'a> {
+    pub fn async_write<'a>(&'a self, buf: &'a [u8]) -> AsyncWrite<'a, B> {
         AsyncWrite::new(self, buf)
     }
 
@@ -84,7 +88,7 @@ impl AsyncInstrument {
     }
 
     pub(crate) fn cancel_job(&self, job_id: JobID) {
-        if let Err(e) = wrap_raw_error_in_unsafe!(vs::viTerminate(
+        if let Err(e) = wrap_raw_error_in_unsafe!(B::terminate(
             self.instr.as_raw_ss(),
             vs::VI_NULL as _,
             job_id.0
@@ -95,12 +99,12 @@ impl AsyncInstrument {
     }
 }
 
-impl Drop for AsyncInstrument {
+impl<B: Backend> Drop for AsyncInstrument<B> {
     fn drop(&mut self) {
-        if let Err(e) = wrap_raw_error_in_unsafe!(vs::viUninstallHandler(
+        if let Err(e) = wrap_raw_error_in_unsafe!(B::uninstall_handler(
             self.instr.as_raw_ss(),
             event::EventKind::EventIoCompletion as _,
-            Some(AsyncIoCallbackPack::call_in_c),
+            Some(AsyncIoCallbackPack::call_in_c::<B>),
             &mut *self.callback as *mut _ as _,
         )) {
             log::warn!("error uninstalling handler: {}", e)
@@ -111,14 +115,14 @@ impl Drop for AsyncInstrument {
 // Entry for a job that sends results back to a Future
 #[derive(Clone)]
 struct JobEntry {
-    sender: Sender<Result<usize>>,
+    sender: Sender<JobResult>,
     waker: Weak<Mutex<Waker>>,
 }
 
 // Entry for a pending job that listener is not ready so that
 // the receiver is not created yet
 struct JobPending {
-    status: Result<usize>,
+    status: JobResult,
 }
 
 struct AsyncIoCallbackPack {
@@ -162,11 +166,14 @@ impl AsyncIoCallbackPack {
             canceled: Mutex::new(IndexMap::with_capacity(CANCELED_CAP)),
         }
     }
-    fn add_pending(&self, job_id: JobID, status: Result<usize>) {
+    fn add_pending(&self, job_id: JobID, status: JobResult) {
         if let Some(mut pending_status) = self.pending.get_mut(&job_id) {
             let pending_status = pending_status.value_mut();
             let _ = match status {
-                Ok(count) => pending_status.status.as_mut().map(|x| x.add_assign(count)),
+                Ok((count, code)) => pending_status.status.as_mut().map(|(x, c)| {
+                    x.add_assign(count);
+                    *c = code;
+                }),
                 Err(e) => {
                     pending_status.status = Err(e);
                     Ok(())
@@ -178,7 +185,7 @@ impl AsyncIoCallbackPack {
         // try merge in case of race, which happens when job added after callback called but before add_pending
         self.try_merge_pending(job_id);
     }
-    fn add_job(&self, job_id: JobID, sender: Sender<Result<usize>>, waker: &Arc<Mutex<Waker>>) {
+    fn add_job(&self, job_id: JobID, sender: Sender<JobResult>, waker: &Arc<Mutex<Waker>>) {
         if self
             .canceled
             .lock()
@@ -209,7 +216,11 @@ impl AsyncIoCallbackPack {
             let _ = canceled.shift_remove_index(0);
         }
     }
-    fn call(&mut self, _instr: &Instrument, event: &event::Event) -> vs::ViStatus {
+    fn call<B: Backend>(
+        &mut self,
+        _instr: &Instrument<B>,
+        event: &event::Event<B>,
+    ) -> vs::ViStatus {
         log::trace!("calling user data method");
 
         debug_assert_eq!(
@@ -252,9 +263,9 @@ impl AsyncIoCallbackPack {
                 if ret == Ok(CompletionCode::WarnQueueOverflow) {
                     log::warn!("warning: queue overflow in async io");
                 }
-                let ret = ret
-                    .and_then(|_| attribute::AttrRetCount::get_from(event))
-                    .map(|x| x.into_inner() as _);
+                let ret = ret.and_then(|code| {
+                    attribute::AttrRetCount::get_from(event).map(|x| (x.into_inner() as _, code))
+                });
                 if let Err(ref e) = ret {
                     log::error!("async io completion error: job_id={}, err={}", job_id.0, e);
                 }
@@ -286,7 +297,7 @@ impl AsyncIoCallbackPack {
         vs::VI_SUCCESS_NCHAIN as _
         //Normally, an application should always return VI_SUCCESS from all callback handlers. If a specific handler does not want other handlers to be invoked for the given event for the given session, it should return VI_SUCCESS_NCHAIN. No return value from a handler on one session will affect callbacks on other sessions. Future versions of VISA (or specific implementations of VISA) may take actions based on other return values, so a user should return VI_SUCCESS from handlers unless there is a specific reason to do otherwise.
     }
-    unsafe extern "system" fn call_in_c(
+    unsafe extern "system" fn call_in_c<B: Backend>(
         instr: vs::ViSession,
         event_type: vs::ViEventType,
         event: vs::ViEvent,
@@ -294,8 +305,8 @@ impl AsyncIoCallbackPack {
     ) -> vs::ViStatus {
         log::trace!("calling in c");
         let pack: &mut Self = &mut *(user_data as *mut Self);
-        let instr = Instrument::from_raw_ss(instr);
-        let event = event::Event::new(event, event_type);
+        let instr = Instrument::<B>::from_raw_ss(instr);
+        let event = event::Event::<B>::new(event, event_type);
         let ret = pack.call(&instr, &event);
         std::mem::forget(event); // The VISA system automatically invokes the viClose() operation on the event context when a user handler returns. Because the event context must still be valid after the user handler returns (so that VISA can free it up), an application should not invoke the viClose() operation on an event context passed to a user handler.
         std::mem::forget(instr); // ? no sure yet, in official example session not closed
@@ -304,26 +315,26 @@ impl AsyncIoCallbackPack {
 }
 
 pub(crate) struct AsyncId {
-    pub(crate) rec: Receiver<Result<usize>>,
+    pub(crate) rec: Receiver<JobResult>,
     pub(crate) waker: Arc<Mutex<Waker>>,
     pub(crate) job_id: JobID,
 }
 
-pub struct AsyncRead<'a> {
-    ss: &'a AsyncInstrument,
+pub struct AsyncRead<'a, B: Backend = Visa> {
+    ss: &'a AsyncInstrument<B>,
     buf: &'a mut [u8],
     id: Option<AsyncId>,
 }
 
-impl<'a> AsyncRead<'a> {
-    pub(crate) fn new(ss: &'a AsyncInstrument, buf: &'a mut [u8]) -> Self {
+impl<'a, B: Backend> AsyncRead<'a, B> {
+    pub(crate) fn new(ss: &'a AsyncInstrument<B>, buf: &'a mut [u8]) -> Self {
         AsyncRead { ss, buf, id: None }
     }
 }
 
-fn get_or_try_init_id<'a>(
+fn get_or_try_init_id<'a, B: Backend>(
     id: &'a mut Option<AsyncId>,
-    ss: &AsyncInstrument,
+    ss: &AsyncInstrument<B>,
     cx: &mut std::task::Context<'_>,
     f: impl FnOnce() -> Result<JobID>,
 ) -> Result<&'a mut AsyncId> {
@@ -339,14 +350,36 @@ fn get_or_try_init_id<'a>(
     Ok(id.as_mut().expect("just initialized"))
 }
 
-impl<'a> Future for AsyncRead<'a> {
+impl<'a, B: Backend> Future for AsyncRead<'a, B> {
     type Output = Result<usize>;
 
     fn poll(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        let self_mut = self.get_mut();
+        self.get_mut()
+            .poll_status(cx)
+            .map(|r| r.map(|(count, _)| count))
+    }
+}
+
+/// [`AsyncRead`] also returning the completion code, to tell END from a full buffer or the termination character
+pub(crate) struct AsyncReadStatus<'a, B: Backend = Visa>(pub(crate) AsyncRead<'a, B>);
+
+impl<'a, B: Backend> Future for AsyncReadStatus<'a, B> {
+    type Output = JobResult;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        self.get_mut().0.poll_status(cx)
+    }
+}
+
+impl<'a, B: Backend> AsyncRead<'a, B> {
+    fn poll_status(&mut self, cx: &mut std::task::Context<'_>) -> Poll<JobResult> {
+        let self_mut = self;
         log::trace!("polling async read");
         let id = get_or_try_init_id(&mut self_mut.id, self_mut.ss, cx, || unsafe {
             self_mut.ss.instr.visa_read_async(self_mut.buf)
@@ -374,11 +407,11 @@ impl<'a> Future for AsyncRead<'a> {
     }
 }
 
-impl<'a> Drop for AsyncRead<'a> {
+impl<'a, B: Backend> Drop for AsyncRead<'a, B> {
     fn drop(&mut self) {
         if let Some(id) = self.id.take() {
             self.ss.callback.as_ref().remove_job(id.job_id);
-            if let Err(e) = wrap_raw_error_in_unsafe!(vs::viTerminate(
+            if let Err(e) = wrap_raw_error_in_unsafe!(B::terminate(
                 self.ss.instr.as_raw_ss(),
                 vs::VI_NULL as _,
                 id.job_id.0
@@ -389,19 +422,19 @@ impl<'a> Drop for AsyncRead<'a> {
     }
 }
 
-pub struct AsyncWrite<'a> {
-    ss: &'a AsyncInstrument,
+pub struct AsyncWrite<'a, B: Backend = Visa> {
+    ss: &'a AsyncInstrument<B>,
     buf: &'a [u8],
     id: Option<AsyncId>,
 }
 
-impl<'a> AsyncWrite<'a> {
-    pub(crate) fn new(ss: &'a AsyncInstrument, buf: &'a [u8]) -> Self {
+impl<'a, B: Backend> AsyncWrite<'a, B> {
+    pub(crate) fn new(ss: &'a AsyncInstrument<B>, buf: &'a [u8]) -> Self {
         Self { ss, buf, id: None }
     }
 }
 
-impl<'a> Future for AsyncWrite<'a> {
+impl<'a, B: Backend> Future for AsyncWrite<'a, B> {
     type Output = Result<usize>;
 
     fn poll(
@@ -417,7 +450,7 @@ impl<'a> Future for AsyncWrite<'a> {
             Ok(o) => {
                 log::trace!("results returned");
                 self_mut.id = None;
-                Poll::Ready(o)
+                Poll::Ready(o.map(|(count, _)| count))
             }
             Err(TryRecvError::Empty) => {
                 log::trace!("empty results, future pending");
@@ -435,11 +468,11 @@ impl<'a> Future for AsyncWrite<'a> {
     }
 }
 
-impl<'a> Drop for AsyncWrite<'a> {
+impl<'a, B: Backend> Drop for AsyncWrite<'a, B> {
     fn drop(&mut self) {
         if let Some(id) = self.id.take() {
             self.ss.callback.as_ref().remove_job(id.job_id);
-            if let Err(e) = wrap_raw_error_in_unsafe!(vs::viTerminate(
+            if let Err(e) = wrap_raw_error_in_unsafe!(B::terminate(
                 self.ss.instr.as_raw_ss(),
                 vs::VI_NULL as _,
                 id.job_id.0
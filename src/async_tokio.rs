@@ -1,5 +1,6 @@
 use crate::{
     async_io::{AsyncId, AsyncInstrument},
+    backend::{Backend, Visa},
     enums::status::ErrorCode,
     Error, Instrument,
 };
@@ -11,29 +12,29 @@ use std::{
 };
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
-pub struct InstrumentTokioAdapter {
-    instr: AsyncInstrument,
+pub struct InstrumentTokioAdapter<B: Backend = Visa> {
+    instr: AsyncInstrument<B>,
     read_current: Option<AsyncId>,
     write_current: Option<AsyncId>,
     read_buf: BytesMut,
     write_buf: BytesMut,
 }
 
-impl TryFrom<Instrument> for InstrumentTokioAdapter {
+impl<B: Backend> TryFrom<Instrument<B>> for InstrumentTokioAdapter<B> {
     type Error = Error;
-    fn try_from(value: Instrument) -> Result<Self, Self::Error> {
+    fn try_from(value: Instrument<B>) -> Result<Self, Self::Error> {
         Ok(Self::new(AsyncInstrument::new(value)?))
     }
 }
 
-impl From<AsyncInstrument> for InstrumentTokioAdapter {
-    fn from(value: AsyncInstrument) -> Self {
+impl<B: Backend> From<AsyncInstrument<B>> for InstrumentTokioAdapter<B> {
+    fn from(value: AsyncInstrument<B>) -> Self {
         Self::new(value)
     }
 }
 
-impl From<InstrumentTokioAdapter> for AsyncInstrument {
-    fn from(mut value: InstrumentTokioAdapter) -> Self {
+impl<B: Backend> From<InstrumentTokioAdapter<B>> for AsyncInstrument<B> {
+    fn from(mut value: InstrumentTokioAdapter<B>) -> Self {
         if let Some(id) = value.read_current.take() {
             value.instr.cancel_job(id.job_id);
         }
@@ -47,15 +48,15 @@ impl From<InstrumentTokioAdapter> for AsyncInstrument {
     }
 }
 
-impl From<InstrumentTokioAdapter> for Instrument {
-    fn from(value: InstrumentTokioAdapter) -> Self {
-        let async_instr: AsyncInstrument = value.into();
+impl<B: Backend> From<InstrumentTokioAdapter<B>> for Instrument<B> {
+    fn from(value: InstrumentTokioAdapter<B>) -> Self {
+        let async_instr: AsyncInstrument<B> = value.into();
         async_instr.into()
     }
 }
 
-impl InstrumentTokioAdapter {
-    pub fn new(instr: AsyncInstrument) -> Self {
+impl<B: Backend> InstrumentTokioAdapter<B> {
+    pub fn new(instr: AsyncInstrument<B>) -> Self {
         Self {
             instr,
             read_current: None,
@@ -89,7 +90,7 @@ impl InstrumentTokioAdapter {
             Ok(ret) => {
                 self.read_current = None;
                 match ret {
-                    Ok(n) => {
+                    Ok((n, _)) => {
                         let n = n.min(self.read_buf.len());
                         buf.put_slice(&self.read_buf[..n]);
                         Poll::Ready(Ok(()))
@@ -120,7 +121,7 @@ impl InstrumentTokioAdapter {
             Ok(ret) => {
                 self.write_current = None;
                 match ret {
-                    Ok(n) => Poll::Ready(Ok(n)),
+                    Ok((n, _)) => Poll::Ready(Ok(n)),
                     Err(e) => {
                         log::error!("tokio async write completion error: {}", e);
                         Poll::Ready(Err(Self::map_vs_err(e)))
@@ -139,7 +140,7 @@ impl InstrumentTokioAdapter {
     }
 }
 
-impl Drop for InstrumentTokioAdapter {
+impl<B: Backend> Drop for InstrumentTokioAdapter<B> {
     fn drop(&mut self) {
         if let Some(id) = self.read_current.take() {
             self.instr.cancel_job(id.job_id);
@@ -150,7 +151,7 @@ impl Drop for InstrumentTokioAdapter {
     }
 }
 
-impl AsyncRead for InstrumentTokioAdapter {
+impl<B: Backend> AsyncRead for InstrumentTokioAdapter<B> {
     fn poll_read(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -180,7 +181,7 @@ impl AsyncRead for InstrumentTokioAdapter {
     }
 }
 
-impl AsyncWrite for InstrumentTokioAdapter {
+impl<B: Backend> AsyncWrite for InstrumentTokioAdapter<B> {
     fn poll_write(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
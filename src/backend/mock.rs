@@ -0,0 +1,2301 @@
+//!
+//! A pure-Rust in-memory [`Backend`], for testing instrument drivers without a VISA installation.
+//!
+//! Resources are registered process-wide through [`MockResource::register`], and stay visible to every
+//! `DefaultRM<Mock>` until the returned [`MockHandle`] is dropped, so use unique resource names in parallel tests.
+//!
+//! The mock is deliberately simple:
+//!
+//! + every write completed with END (see `VI_ATTR_SEND_END_EN`) or ending with the termination character forms a message,
+//!   which is answered by the canned replies registered with [`MockResource::respond`] or the closure from [`MockResource::with_responder`];
+//! + each reply is a message sent with END, reads honour `VI_ATTR_TERMCHAR`/`VI_ATTR_TERMCHAR_EN`
+//!   and return `VI_ERROR_TMO` immediately if nothing is queued;
+//! + `viBufWrite` fills a write buffer of `VI_ATTR_WR_BUF_SIZE` bytes, sent without END when full and with END on `viFlush`,
+//!   `viBufRead` reads the device directly;
+//! + `viRead`, `viWrite` and `viLock` post `VI_EVENT_EXCEPTION` to the handlers of the session when failing;
+//! + asynchronous operations complete synchronously and post `VI_EVENT_IO_COMPLETION` before returning `VI_SUCCESS_SYNC`;
+//! + register based operations access the byte-addressed memory given by [`MockResource::with_memory`],
+//!   honouring the byte order and increment attributes, any byte outside of it is a bus error;
+//! + mapped windows must lie in that memory and are only accessible through peek and poke, `VI_ATTR_WIN_ACCESS` is `VI_USE_OPERS`;
+//! + USB control transfers are only supported on `USB` resources, control-in requests are answered by [`MockResource::with_control_in`]
+//!   and any other one stalls with `VI_ERROR_IO`, every transfer is recorded for [`MockHandle::control_transfers`];
+//! + Word Serial commands are only supported on `VXI` and `GPIB-VXI` resources, they are recorded for [`MockHandle::vxi_commands`]
+//!   and answered by [`MockResource::with_vxi_response`], reading a response when none is pending returns `VI_ERROR_TMO`;
+//! + trigger mappings are only supported on `VXI`, `GPIB-VXI` and `PXI` resources and only recorded, see [`MockHandle::trigger_mappings`];
+//! + attribute expressions in [`find_res_list`](crate::AsResourceManager::find_res_list) are ignored.
+//!
+//! # Example
+//!
+//! ```
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::ffi::CString;
+//! use std::io::{BufRead, BufReader, Write};
+//! use visa_rs::{backend::mock::{Mock, MockResource}, prelude::*};
+//!
+//! let _dev = MockResource::new("TCPIP0::192.0.2.1::inst0::INSTR")
+//!     .respond("*IDN?", "ACME,Model 1,0,1.0\n")
+//!     .register();
+//! let rm = DefaultRM::<Mock>::with_backend()?;
+//! let rsc = rm.find_res(&CString::new("TCPIP?*192.0.2.1?*").unwrap().into())?;
+//! let instr = rm.open(&rsc, AccessMode::NO_LOCK, TIMEOUT_IMMEDIATE)?;
+//! (&instr).write_all(b"*IDN?\n")?;
+//! let mut idn = String::new();
+//! BufReader::new(&instr).read_line(&mut idn)?;
+//! assert_eq!(idn, "ACME,Model 1,0,1.0\n");
+//! # Ok(())
+//! # }
+//! ```
+//!
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    ffi::{c_char, CStr},
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use visa_sys as vs;
+
+use super::Backend;
+use crate::{
+    enums::{
+        attribute::{AttrKind, Attribute},
+        event::EventKind,
+        memory::AddressSpace,
+        status::{CompletionCode, ErrorCode},
+        trigger::TrigLine,
+    },
+    flags::AccessMode,
+    AsResourceManager, DefaultRM, Instrument,
+};
+
+/// The in-memory backend, see [module level doc](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Mock;
+
+type Responder = dyn FnMut(&[u8]) -> Option<Vec<u8>> + Send;
+
+type HandlerFn = unsafe extern "system" fn(
+    vs::ViSession,
+    vs::ViEventType,
+    vs::ViEvent,
+    *mut std::ffi::c_void,
+) -> vs::ViStatus;
+
+/// Description of a simulated resource, made visible to [`Mock`] by [`Self::register`].
+pub struct MockResource {
+    name: String,
+    replies: Vec<(Vec<u8>, Vec<u8>)>,
+    responder: Option<Box<Responder>>,
+    attrs: BTreeMap<vs::ViAttr, Value>,
+    memory: Memory,
+    control_in: BTreeMap<(u8, u8), Vec<u8>>,
+    vxi_responses: BTreeMap<u32, u32>,
+}
+
+impl std::fmt::Debug for MockResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockResource")
+            .field("name", &self.name)
+            .field("replies", &self.replies.len())
+            .field("responder", &self.responder.is_some())
+            .field("memory", &self.memory.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl MockResource {
+    /// A resource with the given resource name, e.g. `"GPIB0::1::INSTR"`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            replies: Vec::new(),
+            responder: None,
+            attrs: BTreeMap::new(),
+            memory: Memory::new(),
+            control_in: BTreeMap::new(),
+            vxi_responses: BTreeMap::new(),
+        }
+    }
+
+    /// Answer `reply` each time a message equal to `query` is written.
+    ///
+    /// Trailing whitespace is ignored and the comparison is ASCII case-insensitive, as for SCPI commands.
+    /// The reply is queued as is, so include the terminator expected by the reader.
+    pub fn respond(mut self, query: impl AsRef<[u8]>, reply: impl AsRef<[u8]>) -> Self {
+        self.replies
+            .push((query.as_ref().to_vec(), reply.as_ref().to_vec()));
+        self
+    }
+
+    /// Called with every message not answered by [`Self::respond`], returned bytes are queued as a reply.
+    pub fn with_responder(
+        mut self,
+        responder: impl FnMut(&[u8]) -> Option<Vec<u8>> + Send + 'static,
+    ) -> Self {
+        self.responder = Some(Box::new(responder));
+        self
+    }
+
+    /// Initial value of an attribute for sessions opened to this resource.
+    pub fn with_attr(mut self, attr: impl Into<Attribute>) -> Self {
+        let attr = attr.into();
+        self.attrs.insert(attr.kind() as _, Value::from(&attr));
+        self
+    }
+
+    /// Initial value of a string attribute, e.g. [`AttrKind::AttrTcpipHostname`], which can't be built outside of the crate.
+    pub fn with_str_attr(mut self, kind: AttrKind, value: impl Into<String>) -> Self {
+        self.attrs.insert(kind as _, Value::Str(value.into()));
+        self
+    }
+
+    /// Maps `bytes` at `offset` of `space`, as seen on the bus, for register based operations.
+    pub fn with_memory(
+        mut self,
+        space: AddressSpace,
+        offset: u64,
+        bytes: impl AsRef<[u8]>,
+    ) -> Self {
+        map_memory(&mut self.memory, space, offset, bytes.as_ref());
+        self
+    }
+
+    /// Answers control-in transfers with `bmRequestType` and `bRequest` equal to `request_type` and `request` with `data`,
+    /// truncated to the requested length.
+    pub fn with_control_in(
+        mut self,
+        request_type: u8,
+        request: u8,
+        data: impl AsRef<[u8]>,
+    ) -> Self {
+        self.control_in
+            .insert((request_type, request), data.as_ref().to_vec());
+        self
+    }
+
+    /// Answers the Word Serial command `cmd` with `response`, 16-bit responses are truncated.
+    pub fn with_vxi_response(mut self, cmd: u32, response: u32) -> Self {
+        self.vxi_responses.insert(cmd, response);
+        self
+    }
+
+    /// Makes the resource visible to [`Mock`], replacing any resource registered with the same name.
+    pub fn register(self) -> MockHandle {
+        let key = self.name.to_ascii_uppercase();
+        let device = Device {
+            name: self.name,
+            replies: self.replies,
+            responder: self.responder,
+            attrs: self.attrs,
+            memory: self.memory,
+            control_in: self.control_in,
+            control_transfers: Vec::new(),
+            vxi_responses: self.vxi_responses,
+            vxi_commands: Vec::new(),
+            vxi_pending: None,
+            trigger_maps: BTreeSet::new(),
+            input: Vec::new(),
+            written: Vec::new(),
+            output: VecDeque::new(),
+            stb: 0,
+            triggers: 0,
+            lock: None,
+        };
+        state().devices.insert(key.clone(), device);
+        MockHandle { key }
+    }
+
+    /// Registers the resource and opens a session to it without lock, through a new resource manager.
+    ///
+    /// Closing the resource manager closes the session, so keep it alive with the handle.
+    pub fn open(self) -> crate::Result<(MockHandle, DefaultRM<Mock>, Instrument<Mock>)> {
+        let handle = self.register();
+        let rm = DefaultRM::<Mock>::with_backend()?;
+        let instr = handle.open(&rm)?;
+        Ok((handle, rm, instr))
+    }
+}
+
+/// Handle to a registered [`MockResource`], used to drive and inspect the simulated device.
+///
+/// The resource is unregistered on drop, sessions still open to it then fail with `VI_ERROR_CONN_LOST`.
+#[derive(Debug)]
+pub struct MockHandle {
+    key: String,
+}
+
+impl MockHandle {
+    fn with_device<T>(&self, f: impl FnOnce(&mut Device) -> T) -> T {
+        f(state()
+            .devices
+            .get_mut(&self.key)
+            .expect("registered device should exist until its handle drops"))
+    }
+
+    /// Opens another session to the resource without lock, through `rm`.
+    pub fn open(&self, rm: &DefaultRM<Mock>) -> crate::Result<Instrument<Mock>> {
+        rm.open(
+            &std::ffi::CString::new(self.key.as_str())
+                .expect("resource names have no nul")
+                .into(),
+            AccessMode::NO_LOCK,
+            crate::TIMEOUT_IMMEDIATE,
+        )
+    }
+
+    /// Queues a message to be read, as if sent by the device with END.
+    pub fn push_response(&self, msg: impl AsRef<[u8]>) {
+        self.with_device(|d| d.output.push_back(msg.as_ref().to_vec()))
+    }
+
+    /// All bytes written to the device so far.
+    pub fn written(&self) -> Vec<u8> {
+        self.with_device(|d| d.written.clone())
+    }
+
+    /// Returns and clears the bytes written to the device so far.
+    pub fn take_written(&self) -> Vec<u8> {
+        self.with_device(|d| std::mem::take(&mut d.written))
+    }
+
+    /// Sets the status byte returned by [`read_stb`](crate::Instrument::read_stb).
+    pub fn set_stb(&self, stb: u16) {
+        self.with_device(|d| d.stb = stb)
+    }
+
+    /// Number of calls to [`assert_trigger`](crate::Instrument::assert_trigger) on this device.
+    pub fn triggers(&self) -> usize {
+        self.with_device(|d| d.triggers)
+    }
+
+    /// All USB control transfers on this device so far, in both directions.
+    pub fn control_transfers(&self) -> Vec<ControlTransfer> {
+        self.with_device(|d| d.control_transfers.clone())
+    }
+
+    /// All Word Serial commands sent to this device so far.
+    pub fn vxi_commands(&self) -> Vec<u32> {
+        self.with_device(|d| d.vxi_commands.clone())
+    }
+
+    /// Trigger lines currently mapped on this device, as `(source, destination)` pairs.
+    pub fn trigger_mappings(&self) -> Vec<(TrigLine, TrigLine)> {
+        self.with_device(|d| {
+            d.trigger_maps
+                .iter()
+                .filter_map(|&(src, dest)| Some((src.try_into().ok()?, dest.try_into().ok()?)))
+                .collect()
+        })
+    }
+
+    /// Bytes mapped at `offset` of `space`, `None` if any of them is not mapped.
+    pub fn memory(&self, space: AddressSpace, offset: u64, len: usize) -> Option<Vec<u8>> {
+        self.with_device(|d| {
+            (offset..offset + len as u64)
+                .map(|o| d.memory.get(&(space as _, o)).copied())
+                .collect()
+        })
+    }
+
+    /// Maps or overwrites `bytes` at `offset` of `space`, see [`MockResource::with_memory`].
+    pub fn set_memory(&self, space: AddressSpace, offset: u64, bytes: impl AsRef<[u8]>) {
+        self.with_device(|d| map_memory(&mut d.memory, space, offset, bytes.as_ref()))
+    }
+
+    /// Posts an event to every session opened to this device, see [`Self::raise_event_with`].
+    pub fn raise_event(&self, kind: EventKind) {
+        self.raise_event_with(kind, [])
+    }
+
+    /// Posts an event carrying `attrs` to every session opened to this device.
+    ///
+    /// Sessions enabled with [`Mechanism::Queue`](crate::enums::event::Mechanism::Queue) queue the event,
+    /// sessions enabled with [`Mechanism::Handler`](crate::enums::event::Mechanism::Handler) run their handlers on the current thread.
+    pub fn raise_event_with(&self, kind: EventKind, attrs: impl IntoIterator<Item = Attribute>) {
+        let attrs: Vec<_> = attrs
+            .into_iter()
+            .map(|a| (a.kind() as vs::ViAttr, Value::from(&a)))
+            .collect();
+        let sessions: Vec<_> = state()
+            .objects
+            .iter()
+            .filter(|(_, o)| matches!(&o.kind, Kind::Session(s) if s.device == self.key))
+            .map(|(id, _)| *id)
+            .collect();
+        for vi in sessions {
+            post_event(vi, kind as _, attrs.clone());
+        }
+    }
+}
+
+/// A USB control transfer seen by the device, see [`MockHandle::control_transfers`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControlTransfer {
+    pub request_type: u8,
+    pub request: u8,
+    pub value: u16,
+    pub index: u16,
+    /// Bytes sent to the device, or returned by it for control-in transfers
+    pub data: Vec<u8>,
+}
+
+impl Drop for MockHandle {
+    fn drop(&mut self) {
+        state().devices.remove(&self.key);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Num(vs::ViAttrState),
+    Str(String),
+}
+
+impl From<&Attribute> for Value {
+    fn from(attr: &Attribute) -> Self {
+        match attr.visa_str() {
+            Some(s) => Value::Str(
+                CStr::from_bytes_until_nul(s)
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            ),
+            None => Value::Num(attr.as_attr_state()),
+        }
+    }
+}
+
+enum LockState {
+    Exclusive {
+        holder: vs::ViSession,
+        count: u32,
+    },
+    Shared {
+        key: String,
+        holders: BTreeMap<vs::ViSession, u32>,
+    },
+}
+
+/// Byte-addressed bus memory, keyed by address space and offset.
+type Memory = BTreeMap<(vs::ViUInt16, u64), u8>;
+
+fn map_memory(memory: &mut Memory, space: AddressSpace, offset: u64, bytes: &[u8]) {
+    memory.extend(
+        (offset..)
+            .zip(bytes)
+            .map(|(o, b)| ((space as vs::ViUInt16, o), *b)),
+    );
+}
+
+struct Device {
+    name: String,
+    replies: Vec<(Vec<u8>, Vec<u8>)>,
+    responder: Option<Box<Responder>>,
+    attrs: BTreeMap<vs::ViAttr, Value>,
+    memory: Memory,
+    control_in: BTreeMap<(u8, u8), Vec<u8>>,
+    control_transfers: Vec<ControlTransfer>,
+    vxi_responses: BTreeMap<u32, u32>,
+    vxi_commands: Vec<u32>,
+    vxi_pending: Option<u32>,
+    trigger_maps: BTreeSet<(vs::ViInt16, vs::ViInt16)>,
+    input: Vec<u8>,
+    written: Vec<u8>,
+    output: VecDeque<Vec<u8>>,
+    stb: u16,
+    triggers: usize,
+    lock: Option<LockState>,
+}
+
+impl Device {
+    fn accessible_by(&self, vi: vs::ViSession) -> bool {
+        match &self.lock {
+            None => true,
+            Some(LockState::Exclusive { holder, .. }) => *holder == vi,
+            Some(LockState::Shared { holders, .. }) => holders.contains_key(&vi),
+        }
+    }
+
+    /// Receives `data`, a message is complete if sent with END or ending with `termchar`.
+    fn receive(&mut self, data: &[u8], end: bool, termchar: Option<u8>) {
+        self.written.extend_from_slice(data);
+        self.input.extend_from_slice(data);
+        if end || (termchar.is_some() && data.last().copied() == termchar) {
+            let msg = std::mem::take(&mut self.input);
+            self.reply_to(msg);
+        }
+    }
+
+    fn reply_to(&mut self, msg: Vec<u8>) {
+        let trimmed = msg.trim_ascii_end();
+        if let Some((_, reply)) = self
+            .replies
+            .iter()
+            .find(|(q, _)| q.trim_ascii_end().eq_ignore_ascii_case(trimmed))
+        {
+            self.output.push_back(reply.clone());
+        } else if let Some(reply) = self.responder.as_mut().and_then(|f| f(&msg)) {
+            self.output.push_back(reply);
+        }
+    }
+
+    /// Element of `width` bytes at `offset`, bytes in bus order are swapped if `big` is false.
+    fn load(
+        &self,
+        space: vs::ViUInt16,
+        offset: u64,
+        width: usize,
+        big: bool,
+    ) -> Result<u64, vs::ViStatus> {
+        let mut bytes = [0u8; 8];
+        for (o, b) in (offset..).zip(&mut bytes[..width]) {
+            *b = *self
+                .memory
+                .get(&(space, o))
+                .ok_or(err(ErrorCode::ErrorBerr))?;
+        }
+        if big {
+            bytes[..width].reverse();
+        }
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn store(
+        &mut self,
+        space: vs::ViUInt16,
+        offset: u64,
+        width: usize,
+        big: bool,
+        value: u64,
+    ) -> Result<(), vs::ViStatus> {
+        let mut bytes = value.to_le_bytes();
+        if big {
+            bytes[..width].reverse();
+        }
+        if (offset..offset + width as u64).any(|o| !self.memory.contains_key(&(space, o))) {
+            return Err(err(ErrorCode::ErrorBerr));
+        }
+        for (o, b) in (offset..).zip(&bytes[..width]) {
+            self.memory.insert((space, o), *b);
+        }
+        Ok(())
+    }
+
+    /// Status of viUnlock() if `vi` held a lock
+    fn release(&mut self, vi: vs::ViSession) -> Option<vs::ViStatus> {
+        match &mut self.lock {
+            Some(LockState::Exclusive { holder, count }) if *holder == vi => {
+                *count -= 1;
+                if *count == 0 {
+                    self.lock = None;
+                    return Some(SUCCESS);
+                }
+                Some(ok(CompletionCode::SuccessNestedExclusive))
+            }
+            Some(LockState::Shared { holders, .. }) => {
+                let count = holders.get_mut(&vi)?;
+                *count -= 1;
+                if *count > 0 {
+                    return Some(ok(CompletionCode::SuccessNestedShared));
+                }
+                holders.remove(&vi);
+                if holders.is_empty() {
+                    self.lock = None;
+                }
+                Some(SUCCESS)
+            }
+            _ => None,
+        }
+    }
+}
+
+struct SessionObj {
+    device: String,
+    enabled: BTreeMap<vs::ViEventType, vs::ViUInt16>,
+    handlers: Vec<(vs::ViEventType, HandlerFn, usize)>,
+    queue: VecDeque<vs::ViEvent>,
+    window: Option<Window>,
+    /// Formatted I/O write buffer, filled by `buf_write`
+    write_buf: Vec<u8>,
+}
+
+const BUF_SIZE: vs::ViAttrState = 4096;
+
+/// Window mapped by `map_address_ex`, at a fake address only meaningful to peek and poke.
+#[derive(Clone, Copy)]
+struct Window {
+    base: usize,
+    space: vs::ViUInt16,
+    offset: u64,
+    size: usize,
+}
+
+const WINDOW_BASE: usize = 0x4000_0000;
+
+enum Kind {
+    Rm,
+    Session(SessionObj),
+    FindList(VecDeque<String>),
+    Event(vs::ViEventType),
+}
+
+struct Object {
+    parent: vs::ViObject,
+    kind: Kind,
+    attrs: BTreeMap<vs::ViAttr, Value>,
+}
+
+struct State {
+    next_object: vs::ViObject,
+    next_job: vs::ViJobId,
+    next_key: u32,
+    objects: BTreeMap<vs::ViObject, Object>,
+    devices: BTreeMap<String, Device>,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            next_object: 1,
+            next_job: 1,
+            next_key: 1,
+            objects: BTreeMap::new(),
+            devices: BTreeMap::new(),
+        }
+    }
+
+    fn insert(
+        &mut self,
+        parent: vs::ViObject,
+        kind: Kind,
+        attrs: BTreeMap<vs::ViAttr, Value>,
+    ) -> vs::ViObject {
+        let id = self.next_object;
+        self.next_object += 1;
+        self.objects.insert(
+            id,
+            Object {
+                parent,
+                kind,
+                attrs,
+            },
+        );
+        id
+    }
+
+    fn is_rm(&self, vi: vs::ViSession) -> bool {
+        matches!(self.objects.get(&vi), Some(Object { kind: Kind::Rm, .. }))
+    }
+
+    fn session(&mut self, vi: vs::ViSession) -> Result<&mut SessionObj, vs::ViStatus> {
+        match self.objects.get_mut(&vi) {
+            Some(Object {
+                kind: Kind::Session(s),
+                ..
+            }) => Ok(s),
+            _ => Err(err(ErrorCode::ErrorInvObject)),
+        }
+    }
+
+    /// Session attributes and the device it is opened to, checking locks.
+    fn device(
+        &mut self,
+        vi: vs::ViSession,
+    ) -> Result<(&BTreeMap<vs::ViAttr, Value>, &mut Device), vs::ViStatus> {
+        let (attrs, name) = match self.objects.get(&vi) {
+            Some(Object {
+                kind: Kind::Session(s),
+                attrs,
+                ..
+            }) => (attrs, &s.device),
+            _ => return Err(err(ErrorCode::ErrorInvObject)),
+        };
+        let device = self
+            .devices
+            .get_mut(name)
+            .ok_or(err(ErrorCode::ErrorConnLost))?;
+        if !device.accessible_by(vi) {
+            return Err(err(ErrorCode::ErrorRsrcLocked));
+        }
+        Ok((attrs, device))
+    }
+
+    fn close(&mut self, vi: vs::ViObject) -> vs::ViStatus {
+        let Some(obj) = self.objects.remove(&vi) else {
+            return err(ErrorCode::ErrorInvObject);
+        };
+        if let Kind::Session(s) = &obj.kind {
+            if let Some(device) = self.devices.get_mut(&s.device) {
+                while device.release(vi).is_some() {}
+            }
+        }
+        let children: Vec<_> = self
+            .objects
+            .iter()
+            .filter(|(_, o)| o.parent == vi)
+            .map(|(id, _)| *id)
+            .collect();
+        for child in children {
+            self.close(child);
+        }
+        SUCCESS
+    }
+}
+
+static STATE: Mutex<State> = Mutex::new(State::new());
+static EVENT_QUEUED: Condvar = Condvar::new();
+
+fn state() -> MutexGuard<'static, State> {
+    // user callbacks never run with the lock held, a poisoned lock can only come from a panicking responder
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+const SUCCESS: vs::ViStatus = vs::VI_SUCCESS as _;
+
+fn err(e: ErrorCode) -> vs::ViStatus {
+    e.into()
+}
+
+fn ok(c: CompletionCode) -> vs::ViStatus {
+    c.into()
+}
+
+/// Posts `VI_EVENT_EXCEPTION` if `status` is an error, handlers run before the failing operation returns
+fn raise_exception(vi: vs::ViSession, status: vs::ViStatus, oper_name: &str) -> vs::ViStatus {
+    if ErrorCode::try_from(status).is_ok() {
+        post_event(
+            vi,
+            EventKind::EventException as _,
+            vec![
+                (AttrKind::AttrStatus as _, Value::Num(status as _)),
+                (AttrKind::AttrOperName as _, Value::Str(oper_name.into())),
+            ],
+        );
+    }
+    status
+}
+
+macro_rules! try_status {
+    ($e:expr) => {
+        match $e {
+            Ok(o) => o,
+            Err(e) => return e,
+        }
+    };
+}
+
+unsafe fn write_str(dst: *mut vs::ViChar, s: &str) {
+    if dst.is_null() {
+        return;
+    }
+    let len = s.len().min(vs::VI_FIND_BUFLEN as usize - 1);
+    std::ptr::copy_nonoverlapping(s.as_ptr(), dst as *mut u8, len);
+    *dst.add(len) = 0;
+}
+
+unsafe fn read_str(src: *const c_char) -> String {
+    CStr::from_ptr(src).to_string_lossy().into_owned()
+}
+
+unsafe fn write_num(dst: *mut std::ffi::c_void, size: usize, v: vs::ViAttrState) {
+    match size {
+        1 => (dst as *mut u8).write_unaligned(v as _),
+        2 => (dst as *mut u16).write_unaligned(v as _),
+        4 => (dst as *mut u32).write_unaligned(v as _),
+        _ => (dst as *mut u64).write_unaligned(v as _),
+    }
+}
+
+unsafe fn read_num(src: *const std::ffi::c_void, size: usize) -> u64 {
+    match size {
+        1 => (src as *const u8).read_unaligned() as _,
+        2 => (src as *const u16).read_unaligned() as _,
+        4 => (src as *const u32).read_unaligned() as _,
+        _ => (src as *const u64).read_unaligned(),
+    }
+}
+
+fn num(attrs: &BTreeMap<vs::ViAttr, Value>, kind: AttrKind) -> Option<vs::ViAttrState> {
+    match attrs.get(&(kind as _)) {
+        Some(Value::Num(n)) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Interface type and board number of a resource name.
+fn parse_intf(name: &str) -> (vs::ViUInt16, vs::ViUInt16) {
+    const PREFIXES: [(&str, u64); 7] = [
+        ("GPIB-VXI", vs::VI_INTF_GPIB_VXI),
+        ("GPIB", vs::VI_INTF_GPIB),
+        ("VXI", vs::VI_INTF_VXI),
+        ("ASRL", vs::VI_INTF_ASRL),
+        ("PXI", vs::VI_INTF_PXI),
+        ("TCPIP", vs::VI_INTF_TCPIP),
+        ("USB", vs::VI_INTF_USB),
+    ];
+    let upper = name.to_ascii_uppercase();
+    for (prefix, ty) in PREFIXES {
+        if let Some(rest) = upper.strip_prefix(prefix) {
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            return (ty as _, digits.parse().unwrap_or(0));
+        }
+    }
+    (0, 0)
+}
+
+/// Queues the event or runs the handlers of session `vi`, without holding the state lock while calling handlers.
+fn post_event(vi: vs::ViSession, kind: vs::ViEventType, attrs: Vec<(vs::ViAttr, Value)>) {
+    let mut attrs: BTreeMap<_, _> = attrs.into_iter().collect();
+    attrs.insert(
+        AttrKind::AttrEventType as _,
+        Value::Num(kind as vs::ViAttrState),
+    );
+    let mut st = state();
+    let Ok(session) = st.session(vi) else {
+        return;
+    };
+    let mech = session.enabled.get(&kind).copied().unwrap_or(0);
+    let handlers: Vec<_> = session
+        .handlers
+        .iter()
+        .rev()
+        .filter(|(k, _, _)| *k == kind)
+        .map(|(_, h, u)| (*h, *u))
+        .collect();
+    if mech & vs::VI_QUEUE as vs::ViUInt16 != 0 {
+        let max_len = st
+            .objects
+            .get(&vi)
+            .and_then(|o| num(&o.attrs, AttrKind::AttrMaxQueueLength))
+            .unwrap_or(50);
+        let queued = st.session(vi).map(|s| s.queue.len()).unwrap_or(0);
+        if (queued as vs::ViAttrState) < max_len {
+            let event = st.insert(vi, Kind::Event(kind), attrs.clone());
+            if let Ok(s) = st.session(vi) {
+                s.queue.push_back(event);
+            }
+            EVENT_QUEUED.notify_all();
+        }
+    }
+    if mech & vs::VI_HNDLR as vs::ViUInt16 != 0 && !handlers.is_empty() {
+        let event = st.insert(vi, Kind::Event(kind), attrs);
+        drop(st);
+        for (handler, user) in handlers {
+            if unsafe { handler(vi, kind, event, user as _) }
+                == vs::VI_SUCCESS_NCHAIN as vs::ViStatus
+            {
+                break;
+            }
+        }
+        state().close(event);
+    }
+}
+
+unsafe fn read_impl(
+    vi: vs::ViSession,
+    buf: vs::ViPBuf,
+    cnt: vs::ViUInt32,
+    ret: &mut usize,
+) -> vs::ViStatus {
+    let mut st = state();
+    let (attrs, device) = try_status!(st.device(vi));
+    let termchar = (num(attrs, AttrKind::AttrTermcharEn) != Some(0))
+        .then(|| num(attrs, AttrKind::AttrTermchar))
+        .flatten()
+        .map(|c| c as u8);
+    let Some(msg) = device.output.front_mut() else {
+        return err(ErrorCode::ErrorTmo);
+    };
+    let max = (cnt as usize).min(msg.len());
+    let (len, status) = match termchar.and_then(|t| msg[..max].iter().position(|c| *c == t)) {
+        Some(p) if p + 1 < msg.len() => (p + 1, ok(CompletionCode::SuccessTermChar)),
+        _ if max == msg.len() => (max, SUCCESS),
+        _ => (max, ok(CompletionCode::SuccessMaxCnt)),
+    };
+    if len > 0 {
+        std::ptr::copy_nonoverlapping(msg.as_ptr(), buf, len);
+    }
+    msg.drain(..len);
+    if msg.is_empty() {
+        device.output.pop_front();
+    }
+    *ret = len;
+    status
+}
+
+unsafe fn write_impl(vi: vs::ViSession, buf: vs::ViConstBuf, cnt: vs::ViUInt32) -> vs::ViStatus {
+    let data = if cnt == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(buf, cnt as _)
+    };
+    try_status!(send(&mut state(), vi, data, true));
+    SUCCESS
+}
+
+/// Sends `data` to the device of `vi`, with END if `end` and `VI_ATTR_SEND_END_EN` is set.
+fn send(st: &mut State, vi: vs::ViSession, data: &[u8], end: bool) -> Result<(), vs::ViStatus> {
+    let (attrs, device) = st.device(vi)?;
+    let end = end && num(attrs, AttrKind::AttrSendEndEn) != Some(0);
+    let termchar = num(attrs, AttrKind::AttrTermchar).map(|c| c as u8);
+    device.receive(data, end, termchar);
+    Ok(())
+}
+
+/// Byte order and increment attributes of `vi` for reading (`src`) or writing the bus.
+fn access_mode(attrs: &BTreeMap<vs::ViAttr, Value>, src: bool) -> (bool, u64) {
+    let (order, increment) = if src {
+        (AttrKind::AttrSrcByteOrder, AttrKind::AttrSrcIncrement)
+    } else {
+        (AttrKind::AttrDestByteOrder, AttrKind::AttrDestIncrement)
+    };
+    (
+        num(attrs, order) == Some(vs::VI_BIG_ENDIAN as _),
+        num(attrs, increment).unwrap_or(1) as _,
+    )
+}
+
+unsafe fn move_in_impl(
+    vi: vs::ViSession,
+    space: vs::ViUInt16,
+    offset: vs::ViBusAddress64,
+    width: usize,
+    length: vs::ViBusSize,
+    buf: *mut std::ffi::c_void,
+) -> vs::ViStatus {
+    let mut st = state();
+    let (attrs, device) = try_status!(st.device(vi));
+    let (big, increment) = access_mode(attrs, true);
+    for i in 0..length as usize {
+        let at = offset + i as u64 * increment * width as u64;
+        let value = try_status!(device.load(space, at, width, big));
+        write_num(buf.byte_add(i * width), width, value as _);
+    }
+    SUCCESS
+}
+
+unsafe fn move_out_impl(
+    vi: vs::ViSession,
+    space: vs::ViUInt16,
+    offset: vs::ViBusAddress64,
+    width: usize,
+    length: vs::ViBusSize,
+    buf: *const std::ffi::c_void,
+) -> vs::ViStatus {
+    let mut st = state();
+    let (attrs, device) = try_status!(st.device(vi));
+    let (big, increment) = access_mode(attrs, false);
+    for i in 0..length as usize {
+        let at = offset + i as u64 * increment * width as u64;
+        let value = read_num(buf.byte_add(i * width), width);
+        try_status!(device.store(space, at, width, big, value));
+    }
+    SUCCESS
+}
+
+/// Session attributes, device and address in bus memory of a peek or poke of `width` bytes at `address`.
+fn window_access(
+    st: &mut State,
+    vi: vs::ViSession,
+    address: vs::ViAddr,
+    width: usize,
+) -> Option<(bool, &mut Device, vs::ViUInt16, u64)> {
+    let window = st.session(vi).ok()?.window?;
+    let at = (address as usize).checked_sub(window.base)?;
+    if at + width > window.size {
+        return None;
+    }
+    let (attrs, device) = st.device(vi).ok()?;
+    let big = num(attrs, AttrKind::AttrWinByteOrder) == Some(vs::VI_BIG_ENDIAN as _);
+    Some((big, device, window.space, window.offset + at as u64))
+}
+
+unsafe fn peek_impl(
+    vi: vs::ViSession,
+    address: vs::ViAddr,
+    width: usize,
+    val: *mut std::ffi::c_void,
+) {
+    let mut st = state();
+    if let Some((big, device, space, at)) = window_access(&mut st, vi, address, width) {
+        if let Ok(v) = device.load(space, at, width, big) {
+            write_num(val, width, v as _);
+        }
+    }
+}
+
+unsafe fn poke_impl(vi: vs::ViSession, address: vs::ViAddr, width: usize, val: u64) {
+    let mut st = state();
+    if let Some((big, device, space, at)) = window_access(&mut st, vi, address, width) {
+        let _ = device.store(space, at, width, big, val);
+    }
+}
+
+/// Like [`State::device`], failing with `VI_ERROR_NSUP_OPER` unless the resource is on one of the interfaces `intfs`.
+fn device_on<'a>(
+    st: &'a mut State,
+    vi: vs::ViSession,
+    intfs: &[u64],
+) -> Result<(&'a BTreeMap<vs::ViAttr, Value>, &'a mut Device), vs::ViStatus> {
+    let (attrs, device) = st.device(vi)?;
+    let ty = parse_intf(&device.name).0;
+    if !intfs.iter().any(|&i| i as vs::ViUInt16 == ty) {
+        return Err(err(ErrorCode::ErrorNsupOper));
+    }
+    Ok((attrs, device))
+}
+
+fn complete_async(
+    vi: vs::ViSession,
+    oper_name: &str,
+    buf: vs::ViAddr,
+    status: vs::ViStatus,
+    ret: usize,
+    job_id: *mut vs::ViJobId,
+) -> vs::ViStatus {
+    let id = {
+        let mut st = state();
+        let id = st.next_job;
+        st.next_job += 1;
+        id
+    };
+    if !job_id.is_null() {
+        unsafe { *job_id = id };
+    }
+    post_event(
+        vi,
+        EventKind::EventIoCompletion as _,
+        vec![
+            (AttrKind::AttrStatus as _, Value::Num(status as _)),
+            (AttrKind::AttrJobId as _, Value::Num(id as _)),
+            (AttrKind::AttrBuffer as _, Value::Num(buf as _)),
+            (AttrKind::AttrRetCount32 as _, Value::Num(ret as _)),
+            (AttrKind::AttrRetCount64 as _, Value::Num(ret as _)),
+            (AttrKind::AttrOperName as _, Value::Str(oper_name.into())),
+        ],
+    );
+    ok(CompletionCode::SuccessSync)
+}
+
+impl Backend for Mock {
+    unsafe fn open_default_rm(vi: *mut vs::ViSession) -> vs::ViStatus {
+        *vi = state().insert(0, Kind::Rm, BTreeMap::new());
+        SUCCESS
+    }
+
+    unsafe fn find_rsrc(
+        sesn: vs::ViSession,
+        expr: vs::ViConstString,
+        vi: *mut vs::ViFindList,
+        ret_cnt: *mut vs::ViUInt32,
+        desc: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        if !st.is_rm(sesn) {
+            return err(ErrorCode::ErrorInvObject);
+        }
+        let expr = read_str(expr);
+        let Some(pattern) = pattern::Pattern::parse(expr.split('{').next().unwrap_or_default())
+        else {
+            return err(ErrorCode::ErrorInvExpr);
+        };
+        let mut found: VecDeque<_> = st
+            .devices
+            .values()
+            .filter(|d| pattern.matches(&d.name))
+            .map(|d| d.name.clone())
+            .collect();
+        let Some(first) = found.pop_front() else {
+            return err(ErrorCode::ErrorRsrcNfound);
+        };
+        write_str(desc, &first);
+        if !ret_cnt.is_null() {
+            *ret_cnt = found.len() as vs::ViUInt32 + 1;
+        }
+        if !vi.is_null() {
+            let attrs = [(AttrKind::AttrRmSession as vs::ViAttr, Value::Num(sesn as _))];
+            *vi = st.insert(sesn, Kind::FindList(found), attrs.into());
+        }
+        SUCCESS
+    }
+
+    unsafe fn find_next(vi: vs::ViFindList, desc: *mut vs::ViChar) -> vs::ViStatus {
+        match state().objects.get_mut(&vi) {
+            Some(Object {
+                kind: Kind::FindList(found),
+                ..
+            }) => match found.pop_front() {
+                Some(next) => {
+                    write_str(desc, &next);
+                    SUCCESS
+                }
+                None => err(ErrorCode::ErrorRsrcNfound),
+            },
+            _ => err(ErrorCode::ErrorInvObject),
+        }
+    }
+
+    unsafe fn parse_rsrc(
+        rm_sesn: vs::ViSession,
+        rsrc_name: vs::ViConstRsrc,
+        intf_type: *mut vs::ViUInt16,
+        intf_num: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        Self::parse_rsrc_ex(
+            rm_sesn,
+            rsrc_name,
+            intf_type,
+            intf_num,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    }
+
+    unsafe fn parse_rsrc_ex(
+        rm_sesn: vs::ViSession,
+        rsrc_name: vs::ViConstRsrc,
+        intf_type: *mut vs::ViUInt16,
+        intf_num: *mut vs::ViUInt16,
+        rsrc_class: *mut vs::ViChar,
+        expanded_unaliased_name: *mut vs::ViChar,
+        alias_if_exists: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        let st = state();
+        if !st.is_rm(rm_sesn) {
+            return err(ErrorCode::ErrorInvObject);
+        }
+        let Some(device) = st.devices.get(&read_str(rsrc_name).to_ascii_uppercase()) else {
+            return err(ErrorCode::ErrorRsrcNfound);
+        };
+        let (ty, num) = parse_intf(&device.name);
+        if !intf_type.is_null() {
+            *intf_type = ty;
+        }
+        if !intf_num.is_null() {
+            *intf_num = num;
+        }
+        write_str(
+            rsrc_class,
+            device.name.rsplit("::").next().unwrap_or_default(),
+        );
+        write_str(expanded_unaliased_name, &device.name);
+        write_str(alias_if_exists, "");
+        SUCCESS
+    }
+
+    unsafe fn open(
+        sesn: vs::ViSession,
+        name: vs::ViConstRsrc,
+        mode: vs::ViAccessMode,
+        timeout: vs::ViUInt32,
+        vi: *mut vs::ViSession,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        if !st.is_rm(sesn) {
+            return err(ErrorCode::ErrorInvObject);
+        }
+        let key = read_str(name).to_ascii_uppercase();
+        let Some(device) = st.devices.get(&key) else {
+            return err(ErrorCode::ErrorRsrcNfound);
+        };
+        let (ty, num) = parse_intf(&device.name);
+        let mut attrs: BTreeMap<vs::ViAttr, Value> = [
+            (AttrKind::AttrTmoValue, Value::Num(2000)),
+            (AttrKind::AttrTermchar, Value::Num(0x0A)),
+            (AttrKind::AttrTermcharEn, Value::Num(vs::VI_FALSE as _)),
+            (AttrKind::AttrSendEndEn, Value::Num(vs::VI_TRUE as _)),
+            (AttrKind::AttrSuppressEndEn, Value::Num(vs::VI_FALSE as _)),
+            (AttrKind::AttrRdBufSize, Value::Num(BUF_SIZE)),
+            (AttrKind::AttrWrBufSize, Value::Num(BUF_SIZE)),
+            (
+                AttrKind::AttrRdBufOperMode,
+                Value::Num(vs::VI_FLUSH_DISABLE as _),
+            ),
+            (
+                AttrKind::AttrWrBufOperMode,
+                Value::Num(vs::VI_FLUSH_WHEN_FULL as _),
+            ),
+            (AttrKind::AttrMaxQueueLength, Value::Num(50)),
+            (
+                AttrKind::AttrSrcByteOrder,
+                Value::Num(vs::VI_BIG_ENDIAN as _),
+            ),
+            (
+                AttrKind::AttrDestByteOrder,
+                Value::Num(vs::VI_BIG_ENDIAN as _),
+            ),
+            (AttrKind::AttrSrcIncrement, Value::Num(1)),
+            (AttrKind::AttrDestIncrement, Value::Num(1)),
+            (
+                AttrKind::AttrWinByteOrder,
+                Value::Num(vs::VI_BIG_ENDIAN as _),
+            ),
+            (AttrKind::AttrWinAccess, Value::Num(vs::VI_NMAPPED as _)),
+            (AttrKind::AttrRmSession, Value::Num(sesn as _)),
+            (AttrKind::AttrIntfType, Value::Num(ty as _)),
+            (AttrKind::AttrIntfNum, Value::Num(num as _)),
+            (AttrKind::AttrRsrcName, Value::Str(device.name.clone())),
+            (
+                AttrKind::AttrRsrcClass,
+                Value::Str(device.name.rsplit("::").next().unwrap_or_default().into()),
+            ),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k as vs::ViAttr, v))
+        .collect();
+        attrs.extend(device.attrs.iter().map(|(k, v)| (*k, v.clone())));
+        let session = SessionObj {
+            device: key,
+            enabled: BTreeMap::new(),
+            handlers: Vec::new(),
+            queue: VecDeque::new(),
+            window: None,
+            write_buf: Vec::new(),
+        };
+        let id = st.insert(sesn, Kind::Session(session), attrs);
+        drop(st);
+        if mode & vs::VI_EXCLUSIVE_LOCK != 0 {
+            let status = Self::lock(
+                id,
+                vs::VI_EXCLUSIVE_LOCK,
+                timeout,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+            );
+            if status < SUCCESS {
+                state().close(id);
+                return status;
+            }
+        }
+        *vi = id;
+        SUCCESS
+    }
+
+    unsafe fn close(vi: vs::ViObject) -> vs::ViStatus {
+        state().close(vi)
+    }
+
+    unsafe fn set_attribute(
+        vi: vs::ViObject,
+        attr_name: vs::ViAttr,
+        attr_value: vs::ViAttrState,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let Some(obj) = st.objects.get_mut(&vi) else {
+            return err(ErrorCode::ErrorInvObject);
+        };
+        let Some(attr) = AttrKind::try_from(attr_name)
+            .ok()
+            .and_then(|k| Attribute::try_from_kind(k))
+        else {
+            return err(ErrorCode::ErrorNsupAttr);
+        };
+        if attr.visa_str().is_some() {
+            return err(ErrorCode::ErrorAttrReadonly);
+        }
+        obj.attrs.insert(attr_name, Value::Num(attr_value));
+        SUCCESS
+    }
+
+    unsafe fn get_attribute(
+        vi: vs::ViObject,
+        attr_name: vs::ViAttr,
+        attr_value: *mut std::ffi::c_void,
+    ) -> vs::ViStatus {
+        let st = state();
+        let Some(obj) = st.objects.get(&vi) else {
+            return err(ErrorCode::ErrorInvObject);
+        };
+        let Some(attr) = AttrKind::try_from(attr_name)
+            .ok()
+            .and_then(|k| Attribute::try_from_kind(k))
+        else {
+            return err(ErrorCode::ErrorNsupAttr);
+        };
+        match obj.attrs.get(&attr_name) {
+            Some(Value::Num(n)) => write_num(attr_value, attr.c_size(), *n),
+            Some(Value::Str(s)) => write_str(attr_value as _, s),
+            None => return err(ErrorCode::ErrorNsupAttr),
+        }
+        SUCCESS
+    }
+
+    unsafe fn status_desc(
+        vi: vs::ViObject,
+        status: vs::ViStatus,
+        desc: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        if !state().objects.contains_key(&vi) {
+            return err(ErrorCode::ErrorInvObject);
+        }
+        let text = match (
+            ErrorCode::try_from(status),
+            CompletionCode::try_from(status),
+        ) {
+            (Ok(e), _) => e.to_string(),
+            (_, Ok(c)) => c.to_string(),
+            _ => {
+                write_str(desc, "Unknown status");
+                return ok(CompletionCode::WarnUnknownStatus);
+            }
+        };
+        write_str(desc, text.trim());
+        SUCCESS
+    }
+
+    unsafe fn terminate(
+        vi: vs::ViObject,
+        _degree: vs::ViUInt16,
+        job_id: vs::ViJobId,
+    ) -> vs::ViStatus {
+        if state().session(vi).is_err() {
+            return err(ErrorCode::ErrorInvObject);
+        }
+        // asynchronous operations complete before returning, there is never a job to abort
+        if job_id == vs::VI_NULL as vs::ViJobId {
+            SUCCESS
+        } else {
+            err(ErrorCode::ErrorInvJobId)
+        }
+    }
+
+    unsafe fn lock(
+        vi: vs::ViSession,
+        lock_type: vs::ViAccessMode,
+        _timeout: vs::ViUInt32,
+        requested_key: vs::ViConstKeyId,
+        access_key: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let name = match st.session(vi) {
+            Ok(s) => s.device.clone(),
+            Err(e) => return e,
+        };
+        let next_key = st.next_key;
+        let Some(device) = st.devices.get_mut(&name) else {
+            return err(ErrorCode::ErrorConnLost);
+        };
+        let requested = (!requested_key.is_null()).then(|| read_str(requested_key));
+        let (status, new_key) = match (lock_type, &mut device.lock) {
+            (vs::VI_EXCLUSIVE_LOCK, None) => {
+                device.lock = Some(LockState::Exclusive {
+                    holder: vi,
+                    count: 1,
+                });
+                (SUCCESS, false)
+            }
+            (vs::VI_EXCLUSIVE_LOCK, Some(LockState::Exclusive { holder, count }))
+                if *holder == vi =>
+            {
+                *count += 1;
+                (ok(CompletionCode::SuccessNestedExclusive), false)
+            }
+            (vs::VI_SHARED_LOCK, None) => {
+                let key = requested
+                    .filter(|k| !k.is_empty())
+                    .unwrap_or_else(|| format!("MockKey{next_key}"));
+                write_str(access_key, &key);
+                device.lock = Some(LockState::Shared {
+                    key,
+                    holders: [(vi, 1)].into(),
+                });
+                (SUCCESS, true)
+            }
+            (vs::VI_SHARED_LOCK, Some(LockState::Shared { key, holders })) => {
+                if let Some(count) = holders.get_mut(&vi) {
+                    *count += 1;
+                    write_str(access_key, key);
+                    (ok(CompletionCode::SuccessNestedShared), false)
+                } else if requested.as_ref() == Some(key) {
+                    holders.insert(vi, 1);
+                    write_str(access_key, key);
+                    (SUCCESS, false)
+                } else if requested.is_some() {
+                    (err(ErrorCode::ErrorInvAccessKey), false)
+                } else {
+                    (err(ErrorCode::ErrorTmo), false)
+                }
+            }
+            (vs::VI_EXCLUSIVE_LOCK | vs::VI_SHARED_LOCK, Some(_)) => {
+                (err(ErrorCode::ErrorTmo), false)
+            }
+            _ => (err(ErrorCode::ErrorInvLockType), false),
+        };
+        if new_key {
+            st.next_key += 1;
+        }
+        drop(st);
+        raise_exception(vi, status, "viLock")
+    }
+
+    unsafe fn unlock(vi: vs::ViSession) -> vs::ViStatus {
+        let mut st = state();
+        let name = match st.session(vi) {
+            Ok(s) => s.device.clone(),
+            Err(e) => return e,
+        };
+        match st.devices.get_mut(&name).map(|d| d.release(vi)) {
+            Some(Some(status)) => status,
+            Some(None) => err(ErrorCode::ErrorSesnNlocked),
+            None => err(ErrorCode::ErrorConnLost),
+        }
+    }
+
+    unsafe fn enable_event(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+        _context: vs::ViEventFilter,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let session = try_status!(st.session(vi));
+        let kinds: Vec<_> = if event_type == EventKind::AllEnabledEvents as vs::ViEventType {
+            session.enabled.keys().copied().collect()
+        } else if event_type == EventKind::EventException as vs::ViEventType
+            && mechanism & !(vs::VI_HNDLR as vs::ViUInt16) != 0
+        {
+            return err(ErrorCode::ErrorInvMech);
+        } else if EventKind::try_from(event_type).is_ok() {
+            vec![event_type]
+        } else {
+            return err(ErrorCode::ErrorInvEvent);
+        };
+        let mut status = SUCCESS;
+        for kind in kinds {
+            if mechanism & vs::VI_HNDLR as vs::ViUInt16 != 0
+                && !session.handlers.iter().any(|(k, _, _)| *k == kind)
+            {
+                return err(ErrorCode::ErrorHndlrNinstalled);
+            }
+            if let Some(old) = session.enabled.insert(kind, mechanism) {
+                if old & mechanism != 0 {
+                    status = ok(CompletionCode::SuccessEventEn);
+                }
+            }
+        }
+        status
+    }
+
+    unsafe fn disable_event(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let session = try_status!(st.session(vi));
+        let mut status = ok(CompletionCode::SuccessEventDis);
+        session.enabled.retain(|kind, mech| {
+            if event_type == *kind || event_type == EventKind::AllEnabledEvents as vs::ViEventType {
+                if *mech & mechanism != 0 {
+                    status = SUCCESS;
+                }
+                *mech &= !mechanism;
+            }
+            *mech != 0
+        });
+        status
+    }
+
+    unsafe fn discard_events(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        if mechanism & vs::VI_QUEUE as vs::ViUInt16 == 0 {
+            return try_status!(st.session(vi).map(|_| SUCCESS));
+        }
+        let queue = std::mem::take(&mut try_status!(st.session(vi)).queue);
+        let mut kept = VecDeque::new();
+        let mut status = ok(CompletionCode::SuccessQueueEmpty);
+        for event in queue {
+            let kind = match st.objects.get(&event) {
+                Some(Object {
+                    kind: Kind::Event(kind),
+                    ..
+                }) => *kind,
+                _ => continue,
+            };
+            if kind == event_type || event_type == EventKind::AllEnabledEvents as vs::ViEventType {
+                st.close(event);
+                status = SUCCESS;
+            } else {
+                kept.push_back(event);
+            }
+        }
+        try_status!(st.session(vi)).queue = kept;
+        status
+    }
+
+    unsafe fn wait_on_event(
+        vi: vs::ViSession,
+        in_event_type: vs::ViEventType,
+        timeout: vs::ViUInt32,
+        out_event_type: *mut vs::ViEventType,
+        out_context: *mut vs::ViEvent,
+    ) -> vs::ViStatus {
+        let deadline = (timeout != vs::VI_TMO_INFINITE)
+            .then(|| Instant::now() + Duration::from_millis(timeout as _));
+        let all = in_event_type == EventKind::AllEnabledEvents as vs::ViEventType;
+        let mut st = state();
+        loop {
+            let session = try_status!(st.session(vi));
+            let queued = vs::VI_QUEUE as vs::ViUInt16;
+            if !all && session.enabled.get(&in_event_type).copied().unwrap_or(0) & queued == 0 {
+                return err(ErrorCode::ErrorNenabled);
+            }
+            let queue = session.queue.clone();
+            let found = queue.iter().position(|e| match st.objects.get(e) {
+                Some(Object {
+                    kind: Kind::Event(kind),
+                    ..
+                }) => all || *kind == in_event_type,
+                _ => false,
+            });
+            if let Some(index) = found {
+                let session = try_status!(st.session(vi));
+                let event = session.queue.remove(index).expect("index just found");
+                let left = !session.queue.is_empty();
+                let kind = match st.objects.get(&event) {
+                    Some(Object {
+                        kind: Kind::Event(kind),
+                        ..
+                    }) => *kind,
+                    _ => unreachable!("queued events are event objects"),
+                };
+                if !out_event_type.is_null() {
+                    *out_event_type = kind;
+                }
+                if out_context.is_null() {
+                    st.close(event);
+                } else {
+                    *out_context = event;
+                }
+                return if left {
+                    ok(CompletionCode::SuccessQueueNempty)
+                } else {
+                    SUCCESS
+                };
+            }
+            st = match deadline {
+                None => EVENT_QUEUED.wait(st).unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return err(ErrorCode::ErrorTmo);
+                    }
+                    EVENT_QUEUED
+                        .wait_timeout(st, deadline - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+
+    unsafe fn install_handler(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        handler: vs::ViHndlr,
+        user_handle: vs::ViAddr,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let session = try_status!(st.session(vi));
+        let Some(handler) = handler else {
+            return err(ErrorCode::ErrorInvHndlrRef);
+        };
+        if EventKind::try_from(event_type).is_err() {
+            return err(ErrorCode::ErrorInvEvent);
+        }
+        session
+            .handlers
+            .push((event_type, handler, user_handle as usize));
+        SUCCESS
+    }
+
+    unsafe fn uninstall_handler(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        handler: vs::ViHndlr,
+        user_handle: vs::ViAddr,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let session = try_status!(st.session(vi));
+        let Some(handler) = handler else {
+            // VI_ANY_HNDLR
+            session.handlers.retain(|(k, _, _)| *k != event_type);
+            return SUCCESS;
+        };
+        match session.handlers.iter().rposition(|(k, h, u)| {
+            *k == event_type && *h as usize == handler as usize && *u == user_handle as usize
+        }) {
+            Some(index) => {
+                session.handlers.remove(index);
+                SUCCESS
+            }
+            None => err(ErrorCode::ErrorInvHndlrRef),
+        }
+    }
+
+    unsafe fn read(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let mut ret = 0;
+        let status = read_impl(vi, buf, cnt, &mut ret);
+        if !ret_cnt.is_null() {
+            *ret_cnt = ret as _;
+        }
+        raise_exception(vi, status, "viRead")
+    }
+
+    unsafe fn read_async(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        job_id: *mut vs::ViJobId,
+    ) -> vs::ViStatus {
+        try_status!(state().device(vi).map(|_| ()));
+        let mut ret = 0;
+        let status = read_impl(vi, buf, cnt, &mut ret);
+        complete_async(vi, "viReadAsync", buf as _, status, ret, job_id)
+    }
+
+    unsafe fn write(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let status = write_impl(vi, buf, cnt);
+        if !ret_cnt.is_null() {
+            *ret_cnt = if status >= SUCCESS { cnt } else { 0 };
+        }
+        raise_exception(vi, status, "viWrite")
+    }
+
+    unsafe fn write_async(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        job_id: *mut vs::ViJobId,
+    ) -> vs::ViStatus {
+        try_status!(state().device(vi).map(|_| ()));
+        let status = write_impl(vi, buf, cnt);
+        let ret = if status >= SUCCESS { cnt as usize } else { 0 };
+        complete_async(vi, "viWriteAsync", buf as _, status, ret, job_id)
+    }
+
+    unsafe fn assert_trigger(vi: vs::ViSession, _protocol: vs::ViUInt16) -> vs::ViStatus {
+        let mut st = state();
+        let (_, device) = try_status!(st.device(vi));
+        device.triggers += 1;
+        SUCCESS
+    }
+
+    unsafe fn read_stb(vi: vs::ViSession, status: *mut vs::ViUInt16) -> vs::ViStatus {
+        let mut st = state();
+        let (_, device) = try_status!(st.device(vi));
+        *status = device.stb;
+        // serial poll clears the RQS bit
+        device.stb &= !0x40;
+        SUCCESS
+    }
+
+    unsafe fn clear(vi: vs::ViSession) -> vs::ViStatus {
+        let mut st = state();
+        let (_, device) = try_status!(st.device(vi));
+        device.input.clear();
+        device.output.clear();
+        SUCCESS
+    }
+
+    unsafe fn set_buf(vi: vs::ViSession, mask: vs::ViUInt16, size: vs::ViUInt32) -> vs::ViStatus {
+        let mut st = state();
+        try_status!(st.session(vi).map(|_| ()));
+        let attrs = &mut st.objects.get_mut(&vi).unwrap().attrs;
+        for (buf, attr) in [
+            (vs::VI_READ_BUF, AttrKind::AttrRdBufSize),
+            (vs::VI_WRITE_BUF, AttrKind::AttrWrBufSize),
+        ] {
+            if mask & buf as vs::ViUInt16 != 0 {
+                attrs.insert(attr as _, Value::Num(size as _));
+            }
+        }
+        SUCCESS
+    }
+
+    unsafe fn flush(vi: vs::ViSession, mask: vs::ViUInt16) -> vs::ViStatus {
+        let mut st = state();
+        let session = try_status!(st.session(vi));
+        if mask & vs::VI_WRITE_BUF_DISCARD as vs::ViUInt16 != 0 {
+            session.write_buf.clear();
+        }
+        if mask & vs::VI_WRITE_BUF as vs::ViUInt16 != 0 {
+            let data = std::mem::take(&mut session.write_buf);
+            if !data.is_empty() {
+                try_status!(send(&mut st, vi, &data, true));
+            }
+        }
+        SUCCESS
+    }
+
+    unsafe fn buf_write(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let data = if cnt == 0 {
+            &[][..]
+        } else {
+            std::slice::from_raw_parts(buf, cnt as _)
+        };
+        let mut st = state();
+        let (attrs, _) = try_status!(st.device(vi));
+        let size = num(attrs, AttrKind::AttrWrBufSize)
+            .unwrap_or(BUF_SIZE)
+            .max(1) as usize;
+        let session = st.session(vi).unwrap();
+        session.write_buf.extend_from_slice(data);
+        // a full buffer is sent without END
+        let full = session.write_buf.len() / size * size;
+        let out: Vec<_> = session.write_buf.drain(..full).collect();
+        if !out.is_empty() {
+            try_status!(send(&mut st, vi, &out, false));
+        }
+        if !ret_cnt.is_null() {
+            *ret_cnt = cnt;
+        }
+        SUCCESS
+    }
+
+    unsafe fn buf_read(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        Self::read(vi, buf, cnt, ret_cnt)
+    }
+
+    unsafe fn vxi_command_query(
+        vi: vs::ViSession,
+        mode: vs::ViUInt16,
+        cmd: vs::ViUInt32,
+        response: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let (_, device) = try_status!(device_on(
+            &mut st,
+            vi,
+            &[vs::VI_INTF_VXI, vs::VI_INTF_GPIB_VXI]
+        ));
+        let cmd_mask: u32 = match mode as vs::ViUInt32 & (vs::VI_VXI_CMD16 | vs::VI_VXI_CMD32) {
+            0 => 0,
+            vs::VI_VXI_CMD16 => 0xFFFF,
+            vs::VI_VXI_CMD32 => u32::MAX,
+            _ => return err(ErrorCode::ErrorInvMode),
+        };
+        let resp_mask: u32 = match mode as vs::ViUInt32 & (vs::VI_VXI_RESP16 | vs::VI_VXI_RESP32) {
+            0 => 0,
+            vs::VI_VXI_RESP16 => 0xFFFF,
+            vs::VI_VXI_RESP32 => u32::MAX,
+            _ => return err(ErrorCode::ErrorInvMode),
+        };
+        if cmd_mask == 0 && resp_mask == 0 || mode & !0x0606 != 0 {
+            return err(ErrorCode::ErrorInvMode);
+        }
+        if cmd_mask != 0 {
+            let cmd = cmd as u32 & cmd_mask;
+            device.vxi_commands.push(cmd);
+            device.vxi_pending = device.vxi_responses.get(&cmd).copied();
+        }
+        if resp_mask != 0 {
+            let Some(resp) = device.vxi_pending.take() else {
+                return err(ErrorCode::ErrorTmo);
+            };
+            if !response.is_null() {
+                *response = (resp & resp_mask) as _;
+            }
+        }
+        SUCCESS
+    }
+
+    unsafe fn map_trigger(
+        vi: vs::ViSession,
+        trig_src: vs::ViInt16,
+        trig_dest: vs::ViInt16,
+        mode: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let (_, device) = try_status!(device_on(
+            &mut st,
+            vi,
+            &[vs::VI_INTF_VXI, vs::VI_INTF_GPIB_VXI, vs::VI_INTF_PXI]
+        ));
+        if mode != 0 {
+            return err(ErrorCode::ErrorInvMode);
+        }
+        if trig_src == trig_dest
+            || TrigLine::try_from(trig_src).is_err()
+            || TrigLine::try_from(trig_dest).is_err()
+        {
+            return err(ErrorCode::ErrorInvLine);
+        }
+        if device.trigger_maps.insert((trig_src, trig_dest)) {
+            SUCCESS
+        } else {
+            ok(CompletionCode::SuccessTrigMapped)
+        }
+    }
+
+    unsafe fn unmap_trigger(
+        vi: vs::ViSession,
+        trig_src: vs::ViInt16,
+        trig_dest: vs::ViInt16,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let (_, device) = try_status!(device_on(
+            &mut st,
+            vi,
+            &[vs::VI_INTF_VXI, vs::VI_INTF_GPIB_VXI, vs::VI_INTF_PXI]
+        ));
+        let before = device.trigger_maps.len();
+        device.trigger_maps.retain(|&(src, dest)| {
+            src != trig_src || (trig_dest != vs::VI_TRIG_ALL as vs::ViInt16 && dest != trig_dest)
+        });
+        if device.trigger_maps.len() == before {
+            return err(ErrorCode::ErrorTrigNmapped);
+        }
+        SUCCESS
+    }
+
+    unsafe fn usb_control_out(
+        vi: vs::ViSession,
+        request_type: vs::ViInt16,
+        request: vs::ViInt16,
+        value: vs::ViUInt16,
+        index: vs::ViUInt16,
+        length: vs::ViUInt16,
+        buf: vs::ViConstBuf,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let (_, device) = try_status!(device_on(&mut st, vi, &[vs::VI_INTF_USB]));
+        let data = if length == 0 {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(buf, length as _).to_vec()
+        };
+        device.control_transfers.push(ControlTransfer {
+            request_type: request_type as _,
+            request: request as _,
+            value,
+            index,
+            data,
+        });
+        SUCCESS
+    }
+
+    unsafe fn usb_control_in(
+        vi: vs::ViSession,
+        request_type: vs::ViInt16,
+        request: vs::ViInt16,
+        value: vs::ViUInt16,
+        index: vs::ViUInt16,
+        length: vs::ViUInt16,
+        buf: vs::ViPBuf,
+        ret_cnt: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let mut st = state();
+        let (_, device) = try_status!(device_on(&mut st, vi, &[vs::VI_INTF_USB]));
+        let Some(reply) = device.control_in.get(&(request_type as u8, request as u8)) else {
+            return err(ErrorCode::ErrorIo);
+        };
+        let data = reply[..reply.len().min(length as _)].to_vec();
+        std::ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len());
+        if !ret_cnt.is_null() {
+            *ret_cnt = data.len() as _;
+        }
+        device.control_transfers.push(ControlTransfer {
+            request_type: request_type as _,
+            request: request as _,
+            value,
+            index,
+            data,
+        });
+        SUCCESS
+    }
+
+    unsafe fn in8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        move_in_impl(vi, space, offset, 1, 1, val as _)
+    }
+
+    unsafe fn out8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt8,
+    ) -> vs::ViStatus {
+        move_out_impl(vi, space, offset, 1, 1, &val as *const u8 as _)
+    }
+
+    unsafe fn move_in8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        move_in_impl(vi, space, offset, 1, length, buf as _)
+    }
+
+    unsafe fn move_out8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        move_out_impl(vi, space, offset, 1, length, buf as _)
+    }
+
+    unsafe fn in16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        move_in_impl(vi, space, offset, 2, 1, val as _)
+    }
+
+    unsafe fn out16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        move_out_impl(vi, space, offset, 2, 1, &val as *const u16 as _)
+    }
+
+    unsafe fn move_in16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        move_in_impl(vi, space, offset, 2, length, buf as _)
+    }
+
+    unsafe fn move_out16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        move_out_impl(vi, space, offset, 2, length, buf as _)
+    }
+
+    unsafe fn in32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        move_in_impl(vi, space, offset, 4, 1, val as _)
+    }
+
+    unsafe fn out32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt32,
+    ) -> vs::ViStatus {
+        // ViUInt32 may be wider than 32 bits in the bindings
+        let val = val as u32;
+        move_out_impl(vi, space, offset, 4, 1, &val as *const u32 as _)
+    }
+
+    unsafe fn move_in32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        move_in_impl(vi, space, offset, 4, length, buf as _)
+    }
+
+    unsafe fn move_out32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        move_out_impl(vi, space, offset, 4, length, buf as _)
+    }
+
+    unsafe fn in64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        move_in_impl(vi, space, offset, 8, 1, val as _)
+    }
+
+    unsafe fn out64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt64,
+    ) -> vs::ViStatus {
+        move_out_impl(vi, space, offset, 8, 1, &val as *const u64 as _)
+    }
+
+    unsafe fn move_in64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        move_in_impl(vi, space, offset, 8, length, buf as _)
+    }
+
+    unsafe fn move_out64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        move_out_impl(vi, space, offset, 8, length, buf as _)
+    }
+
+    unsafe fn map_address_ex(
+        vi: vs::ViSession,
+        map_space: vs::ViUInt16,
+        map_offset: vs::ViBusAddress64,
+        map_size: vs::ViBusSize,
+        access: vs::ViBoolean,
+        suggested: vs::ViAddr,
+        address: *mut vs::ViAddr,
+    ) -> vs::ViStatus {
+        if access != vs::VI_FALSE as vs::ViBoolean {
+            return err(ErrorCode::ErrorInvAccMode);
+        }
+        if map_size == 0 {
+            return err(ErrorCode::ErrorInvSize);
+        }
+        let mut st = state();
+        if try_status!(st.session(vi)).window.is_some() {
+            return err(ErrorCode::ErrorWindowMapped);
+        }
+        let (_, device) = try_status!(st.device(vi));
+        if (map_offset..map_offset + map_size as vs::ViBusAddress64)
+            .any(|o| !device.memory.contains_key(&(map_space, o)))
+        {
+            return err(ErrorCode::ErrorInvOffset);
+        }
+        let base = if suggested.is_null() {
+            WINDOW_BASE
+        } else {
+            suggested as usize
+        };
+        try_status!(st.session(vi)).window = Some(Window {
+            base,
+            space: map_space,
+            offset: map_offset,
+            size: map_size as _,
+        });
+        if let Some(obj) = st.objects.get_mut(&vi) {
+            obj.attrs.extend(
+                [
+                    (AttrKind::AttrWinAccess, vs::VI_USE_OPERS as vs::ViAttrState),
+                    (AttrKind::AttrWinBaseAddr64, map_offset as _),
+                    (AttrKind::AttrWinSize64, map_size as _),
+                ]
+                .map(|(k, v)| (k as vs::ViAttr, Value::Num(v))),
+            );
+        }
+        *address = base as _;
+        SUCCESS
+    }
+
+    unsafe fn unmap_address(vi: vs::ViSession) -> vs::ViStatus {
+        let mut st = state();
+        if try_status!(st.session(vi)).window.take().is_none() {
+            return err(ErrorCode::ErrorWindowNmapped);
+        }
+        if let Some(obj) = st.objects.get_mut(&vi) {
+            obj.attrs.insert(
+                AttrKind::AttrWinAccess as _,
+                Value::Num(vs::VI_NMAPPED as _),
+            );
+        }
+        SUCCESS
+    }
+
+    unsafe fn peek8(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt8) {
+        peek_impl(vi, address, 1, val as _)
+    }
+
+    unsafe fn poke8(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt8) {
+        poke_impl(vi, address, 1, val as _)
+    }
+
+    unsafe fn peek16(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt16) {
+        peek_impl(vi, address, 2, val as _)
+    }
+
+    unsafe fn poke16(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt16) {
+        poke_impl(vi, address, 2, val as _)
+    }
+
+    unsafe fn peek32(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt32) {
+        peek_impl(vi, address, 4, val as _)
+    }
+
+    unsafe fn poke32(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt32) {
+        poke_impl(vi, address, 4, val as _)
+    }
+
+    unsafe fn peek64(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt64) {
+        peek_impl(vi, address, 8, val as _)
+    }
+
+    unsafe fn poke64(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt64) {
+        poke_impl(vi, address, 8, val as _)
+    }
+
+    unsafe fn move_ex(
+        vi: vs::ViSession,
+        src_space: vs::ViUInt16,
+        src_offset: vs::ViBusAddress64,
+        src_width: vs::ViUInt16,
+        dest_space: vs::ViUInt16,
+        dest_offset: vs::ViBusAddress64,
+        dest_width: vs::ViUInt16,
+        src_length: vs::ViBusSize,
+    ) -> vs::ViStatus {
+        let width = src_width as usize;
+        if ![1, 2, 4, 8].contains(&width) || dest_width != src_width {
+            return err(ErrorCode::ErrorNsupWidth);
+        }
+        let mut data = vec![0u64; src_length as usize];
+        let status = move_in_impl(
+            vi,
+            src_space,
+            src_offset,
+            width,
+            src_length,
+            data.as_mut_ptr() as _,
+        );
+        if status != SUCCESS {
+            return status;
+        }
+        // elements are packed by `width` in `data`, as a user buffer would be
+        move_out_impl(
+            vi,
+            dest_space,
+            dest_offset,
+            width,
+            src_length,
+            data.as_ptr() as _,
+        )
+    }
+}
+
+/// Matching of resource names against VISA regular expressions as described in [`find_res_list`](crate::AsResourceManager::find_res_list).
+mod pattern {
+    enum Node {
+        Any,
+        Char(u8),
+        Class {
+            negated: bool,
+            ranges: Vec<(u8, u8)>,
+        },
+        Group(Vec<Vec<Piece>>),
+    }
+
+    enum Repeat {
+        One,
+        Star,
+        Plus,
+    }
+
+    struct Piece {
+        node: Node,
+        repeat: Repeat,
+    }
+
+    pub(super) struct Pattern(Vec<Vec<Piece>>);
+
+    type Cont<'a> = &'a mut dyn FnMut(&[u8]) -> bool;
+
+    impl Pattern {
+        pub(super) fn parse(expr: &str) -> Option<Self> {
+            let expr = expr.trim().to_ascii_uppercase();
+            let mut bytes = expr.as_bytes();
+            let alts = parse_alts(&mut bytes)?;
+            bytes.is_empty().then_some(Self(alts))
+        }
+
+        pub(super) fn matches(&self, name: &str) -> bool {
+            let name = name.to_ascii_uppercase();
+            match_alts(&self.0, name.as_bytes(), &mut |rest| rest.is_empty())
+        }
+    }
+
+    fn parse_alts(input: &mut &[u8]) -> Option<Vec<Vec<Piece>>> {
+        let mut alts = vec![Vec::new()];
+        while let Some((&c, rest)) = input.split_first() {
+            let node = match c {
+                b')' => break,
+                b'|' => {
+                    *input = rest;
+                    alts.push(Vec::new());
+                    continue;
+                }
+                b'(' => {
+                    *input = rest;
+                    let group = parse_alts(input)?;
+                    *input = input.strip_prefix(b")")?;
+                    Node::Group(group)
+                }
+                b'[' => {
+                    let end = rest.iter().position(|c| *c == b']')?;
+                    let (negated, mut list) = match rest[..end].split_first() {
+                        Some((b'^', list)) => (true, list),
+                        _ => (false, &rest[..end]),
+                    };
+                    *input = &rest[end + 1..];
+                    let mut ranges = Vec::new();
+                    while let Some((&lo, tail)) = list.split_first() {
+                        match tail {
+                            [b'-', hi, tail @ ..] => {
+                                ranges.push((lo, *hi));
+                                list = tail;
+                            }
+                            _ => {
+                                ranges.push((lo, lo));
+                                list = tail;
+                            }
+                        }
+                    }
+                    Node::Class { negated, ranges }
+                }
+                b'\\' => {
+                    let (&c, rest) = rest.split_first()?;
+                    *input = rest;
+                    Node::Char(c)
+                }
+                b'?' => {
+                    *input = rest;
+                    Node::Any
+                }
+                b'*' | b'+' => return None,
+                c => {
+                    *input = rest;
+                    Node::Char(c)
+                }
+            };
+            let repeat = match input.first() {
+                Some(b'*') => Repeat::Star,
+                Some(b'+') => Repeat::Plus,
+                _ => Repeat::One,
+            };
+            if !matches!(repeat, Repeat::One) {
+                *input = &input[1..];
+            }
+            alts.last_mut()
+                .expect("at least one alternative")
+                .push(Piece { node, repeat });
+        }
+        Some(alts)
+    }
+
+    fn match_alts(alts: &[Vec<Piece>], s: &[u8], k: Cont<'_>) -> bool {
+        alts.iter().any(|seq| match_seq(seq, s, k))
+    }
+
+    fn match_seq(seq: &[Piece], s: &[u8], k: Cont<'_>) -> bool {
+        let Some((piece, rest)) = seq.split_first() else {
+            return k(s);
+        };
+        match piece.repeat {
+            Repeat::One => match_node(&piece.node, s, &mut |s| match_seq(rest, s, k)),
+            Repeat::Star => match_star(&piece.node, rest, s, k),
+            Repeat::Plus => {
+                match_node(&piece.node, s, &mut |s| match_star(&piece.node, rest, s, k))
+            }
+        }
+    }
+
+    fn match_star(node: &Node, rest: &[Piece], s: &[u8], k: Cont<'_>) -> bool {
+        match_node(node, s, &mut |t| {
+            t.len() < s.len() && match_star(node, rest, t, k)
+        }) || match_seq(rest, s, k)
+    }
+
+    fn match_node(node: &Node, s: &[u8], k: Cont<'_>) -> bool {
+        match node {
+            Node::Group(alts) => match_alts(alts, s, k),
+            _ => match s.split_first() {
+                Some((c, rest)) => {
+                    let hit = match node {
+                        Node::Any => true,
+                        Node::Char(x) => x == c,
+                        Node::Class { negated, ranges } => {
+                            ranges.iter().any(|(lo, hi)| (lo..=hi).contains(&c)) != *negated
+                        }
+                        Node::Group(_) => unreachable!(),
+                    };
+                    hit && k(rest)
+                }
+                None => false,
+            },
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::Pattern;
+
+        #[test]
+        fn visa_expressions() {
+            let cases = [
+                ("GPIB?*INSTR", "GPIB1::1::1::INSTR", true),
+                ("GPIB[0-9]*::?*INSTR", "GPIB0::2::INSTR", true),
+                ("GPIB[^0]::?*INSTR", "GPIB0::2::INSTR", false),
+                ("GPIB[^0]::?*INSTR", "GPIB1::1::1::INSTR", true),
+                ("ASRL1+::INSTR", "ASRL11::INSTR", true),
+                ("ASRL1+::INSTR", "ASRL2::INSTR", false),
+                ("(GPIB|VXI)?*INSTR", "vxi0::3::instr", true),
+                ("(GPIB0|VXI0)::1::INSTR", "GPIB0::1::INSTR", true),
+                ("(GPIB0|VXI0)::1::INSTR", "GPIB0::2::INSTR", false),
+                ("?*", "TCPIP0::host::inst0::INSTR", true),
+                ("VXI0::?*", "VXI0::MEMACC", true),
+                ("\\?*", "?", true),
+            ];
+            for (expr, name, expected) in cases {
+                let pattern = Pattern::parse(expr).unwrap();
+                assert_eq!(pattern.matches(name), expected, "{expr} ~ {name}");
+            }
+            assert!(Pattern::parse("(GPIB").is_none());
+            assert!(Pattern::parse("*INSTR").is_none());
+        }
+    }
+}
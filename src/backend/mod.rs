@@ -0,0 +1,1127 @@
+//!
+//! Defines [`Backend`], the set of raw VISA operations behind [`DefaultRM`](crate::DefaultRM) and [`Instrument`](crate::Instrument).
+//!
+//! [`Visa`] forwards every call to the VISA library bound by [`visa_sys`], and is the default type parameter everywhere,
+//! [`mock::Mock`] is a pure-Rust in-memory implementation for testing drivers without a VISA installation.
+//!
+//! Operations mirror the C API one to one: same parameters, same [`ViStatus`](vs::ViStatus) return value, `vi` prefix dropped and names in snake case.
+//! Operations not essential to message-based sessions have a default implementation returning `VI_ERROR_NSUP_OPER`,
+//! except viPeekXX/viPokeXX, which cannot report errors and must be implemented along with viMapAddressEx.
+//!
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use visa_sys as vs;
+
+use crate::enums::status::ErrorCode;
+
+pub mod mock;
+
+pub use mock::Mock;
+
+const NSUP_OPER: vs::ViStatus = ErrorCode::ErrorNsupOper as _;
+
+/// Raw VISA operations, implemented by zero-sized marker types.
+///
+/// # Safety
+///
+/// All operations follow the contract of the corresponding VISA C function, pointers passed in must be valid for the size VISA expects.
+///
+/// Register values are passed as in VISA, where `ViUInt32` is 32 bits wide, even if the binding's [`vs::ViUInt32`] is wider on some platforms:
+/// the `*mut vs::ViUInt32` of [`in32_ex`](Self::in32_ex), [`move_in32_ex`](Self::move_in32_ex), [`move_out32_ex`](Self::move_out32_ex)
+/// and [`peek32`](Self::peek32) point to `u32` elements, and implementations must read or write exactly 4 bytes per element.
+#[allow(clippy::missing_safety_doc)]
+pub trait Backend:
+    Debug + Clone + Copy + PartialEq + Eq + Hash + Send + Sync + Unpin + 'static
+{
+    /// viOpenDefaultRM
+    unsafe fn open_default_rm(vi: *mut vs::ViSession) -> vs::ViStatus;
+    /// viFindRsrc
+    unsafe fn find_rsrc(
+        sesn: vs::ViSession,
+        expr: vs::ViConstString,
+        vi: *mut vs::ViFindList,
+        ret_cnt: *mut vs::ViUInt32,
+        desc: *mut vs::ViChar,
+    ) -> vs::ViStatus;
+    /// viFindNext
+    unsafe fn find_next(vi: vs::ViFindList, desc: *mut vs::ViChar) -> vs::ViStatus;
+    /// viParseRsrc
+    unsafe fn parse_rsrc(
+        rm_sesn: vs::ViSession,
+        rsrc_name: vs::ViConstRsrc,
+        intf_type: *mut vs::ViUInt16,
+        intf_num: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (rm_sesn, rsrc_name, intf_type, intf_num);
+        NSUP_OPER
+    }
+    /// viParseRsrcEx
+    unsafe fn parse_rsrc_ex(
+        rm_sesn: vs::ViSession,
+        rsrc_name: vs::ViConstRsrc,
+        intf_type: *mut vs::ViUInt16,
+        intf_num: *mut vs::ViUInt16,
+        rsrc_class: *mut vs::ViChar,
+        expanded_unaliased_name: *mut vs::ViChar,
+        alias_if_exists: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        let _ = (
+            rm_sesn,
+            rsrc_name,
+            intf_type,
+            intf_num,
+            rsrc_class,
+            expanded_unaliased_name,
+            alias_if_exists,
+        );
+        NSUP_OPER
+    }
+    /// viOpen
+    unsafe fn open(
+        sesn: vs::ViSession,
+        name: vs::ViConstRsrc,
+        mode: vs::ViAccessMode,
+        timeout: vs::ViUInt32,
+        vi: *mut vs::ViSession,
+    ) -> vs::ViStatus;
+    /// viClose
+    unsafe fn close(vi: vs::ViObject) -> vs::ViStatus;
+    /// viSetAttribute
+    unsafe fn set_attribute(
+        vi: vs::ViObject,
+        attr_name: vs::ViAttr,
+        attr_value: vs::ViAttrState,
+    ) -> vs::ViStatus;
+    /// viGetAttribute
+    unsafe fn get_attribute(
+        vi: vs::ViObject,
+        attr_name: vs::ViAttr,
+        attr_value: *mut std::ffi::c_void,
+    ) -> vs::ViStatus;
+    /// viStatusDesc
+    unsafe fn status_desc(
+        vi: vs::ViObject,
+        status: vs::ViStatus,
+        desc: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        let _ = (vi, status, desc);
+        NSUP_OPER
+    }
+    /// viTerminate
+    unsafe fn terminate(
+        vi: vs::ViObject,
+        degree: vs::ViUInt16,
+        job_id: vs::ViJobId,
+    ) -> vs::ViStatus {
+        let _ = (vi, degree, job_id);
+        NSUP_OPER
+    }
+    /// viLock
+    unsafe fn lock(
+        vi: vs::ViSession,
+        lock_type: vs::ViAccessMode,
+        timeout: vs::ViUInt32,
+        requested_key: vs::ViConstKeyId,
+        access_key: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        let _ = (vi, lock_type, timeout, requested_key, access_key);
+        NSUP_OPER
+    }
+    /// viUnlock
+    unsafe fn unlock(vi: vs::ViSession) -> vs::ViStatus {
+        let _ = vi;
+        NSUP_OPER
+    }
+    /// viEnableEvent
+    unsafe fn enable_event(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+        context: vs::ViEventFilter,
+    ) -> vs::ViStatus {
+        let _ = (vi, event_type, mechanism, context);
+        NSUP_OPER
+    }
+    /// viDisableEvent
+    unsafe fn disable_event(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, event_type, mechanism);
+        NSUP_OPER
+    }
+    /// viDiscardEvents
+    unsafe fn discard_events(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, event_type, mechanism);
+        NSUP_OPER
+    }
+    /// viWaitOnEvent
+    unsafe fn wait_on_event(
+        vi: vs::ViSession,
+        in_event_type: vs::ViEventType,
+        timeout: vs::ViUInt32,
+        out_event_type: *mut vs::ViEventType,
+        out_context: *mut vs::ViEvent,
+    ) -> vs::ViStatus {
+        let _ = (vi, in_event_type, timeout, out_event_type, out_context);
+        NSUP_OPER
+    }
+    /// viInstallHandler
+    unsafe fn install_handler(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        handler: vs::ViHndlr,
+        user_handle: vs::ViAddr,
+    ) -> vs::ViStatus {
+        let _ = (vi, event_type, handler, user_handle);
+        NSUP_OPER
+    }
+    /// viUninstallHandler
+    unsafe fn uninstall_handler(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        handler: vs::ViHndlr,
+        user_handle: vs::ViAddr,
+    ) -> vs::ViStatus {
+        let _ = (vi, event_type, handler, user_handle);
+        NSUP_OPER
+    }
+    /// viRead
+    unsafe fn read(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus;
+    /// viReadAsync
+    unsafe fn read_async(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        job_id: *mut vs::ViJobId,
+    ) -> vs::ViStatus {
+        let _ = (vi, buf, cnt, job_id);
+        NSUP_OPER
+    }
+    /// viWrite
+    unsafe fn write(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus;
+    /// viWriteAsync
+    unsafe fn write_async(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        job_id: *mut vs::ViJobId,
+    ) -> vs::ViStatus {
+        let _ = (vi, buf, cnt, job_id);
+        NSUP_OPER
+    }
+    /// viAssertTrigger
+    unsafe fn assert_trigger(vi: vs::ViSession, protocol: vs::ViUInt16) -> vs::ViStatus {
+        let _ = (vi, protocol);
+        NSUP_OPER
+    }
+    /// viReadSTB
+    unsafe fn read_stb(vi: vs::ViSession, status: *mut vs::ViUInt16) -> vs::ViStatus {
+        let _ = (vi, status);
+        NSUP_OPER
+    }
+    /// viClear
+    unsafe fn clear(vi: vs::ViSession) -> vs::ViStatus {
+        let _ = vi;
+        NSUP_OPER
+    }
+    /// viSetBuf
+    unsafe fn set_buf(vi: vs::ViSession, mask: vs::ViUInt16, size: vs::ViUInt32) -> vs::ViStatus {
+        let _ = (vi, mask, size);
+        NSUP_OPER
+    }
+    /// viFlush
+    unsafe fn flush(vi: vs::ViSession, mask: vs::ViUInt16) -> vs::ViStatus {
+        let _ = (vi, mask);
+        NSUP_OPER
+    }
+    /// viBufWrite
+    unsafe fn buf_write(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, buf, cnt, ret_cnt);
+        NSUP_OPER
+    }
+    /// viBufRead
+    unsafe fn buf_read(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, buf, cnt, ret_cnt);
+        NSUP_OPER
+    }
+    /// viGpibControlREN
+    unsafe fn gpib_control_ren(vi: vs::ViSession, mode: vs::ViUInt16) -> vs::ViStatus {
+        let _ = (vi, mode);
+        NSUP_OPER
+    }
+    /// viGpibControlATN
+    unsafe fn gpib_control_atn(vi: vs::ViSession, mode: vs::ViUInt16) -> vs::ViStatus {
+        let _ = (vi, mode);
+        NSUP_OPER
+    }
+    /// viGpibSendIFC
+    unsafe fn gpib_send_ifc(vi: vs::ViSession) -> vs::ViStatus {
+        let _ = vi;
+        NSUP_OPER
+    }
+    /// viGpibCommand
+    unsafe fn gpib_command(
+        vi: vs::ViSession,
+        cmd: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, cmd, cnt, ret_cnt);
+        NSUP_OPER
+    }
+    /// viGpibPassControl
+    unsafe fn gpib_pass_control(
+        vi: vs::ViSession,
+        prim_addr: vs::ViUInt16,
+        sec_addr: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, prim_addr, sec_addr);
+        NSUP_OPER
+    }
+    /// viAssertUtilSignal
+    unsafe fn assert_util_signal(vi: vs::ViSession, line: vs::ViUInt16) -> vs::ViStatus {
+        let _ = (vi, line);
+        NSUP_OPER
+    }
+    /// viAssertIntrSignal
+    unsafe fn assert_intr_signal(
+        vi: vs::ViSession,
+        mode: vs::ViInt16,
+        status_id: vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, mode, status_id);
+        NSUP_OPER
+    }
+    /// viVxiCommandQuery
+    unsafe fn vxi_command_query(
+        vi: vs::ViSession,
+        mode: vs::ViUInt16,
+        cmd: vs::ViUInt32,
+        response: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, mode, cmd, response);
+        NSUP_OPER
+    }
+    /// viMapTrigger
+    unsafe fn map_trigger(
+        vi: vs::ViSession,
+        trig_src: vs::ViInt16,
+        trig_dest: vs::ViInt16,
+        mode: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, trig_src, trig_dest, mode);
+        NSUP_OPER
+    }
+    /// viUnmapTrigger
+    unsafe fn unmap_trigger(
+        vi: vs::ViSession,
+        trig_src: vs::ViInt16,
+        trig_dest: vs::ViInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, trig_src, trig_dest);
+        NSUP_OPER
+    }
+    /// viUsbControlOut
+    unsafe fn usb_control_out(
+        vi: vs::ViSession,
+        request_type: vs::ViInt16,
+        request: vs::ViInt16,
+        value: vs::ViUInt16,
+        index: vs::ViUInt16,
+        length: vs::ViUInt16,
+        buf: vs::ViConstBuf,
+    ) -> vs::ViStatus {
+        let _ = (vi, request_type, request, value, index, length, buf);
+        NSUP_OPER
+    }
+    /// viUsbControlIn
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn usb_control_in(
+        vi: vs::ViSession,
+        request_type: vs::ViInt16,
+        request: vs::ViInt16,
+        value: vs::ViUInt16,
+        index: vs::ViUInt16,
+        length: vs::ViUInt16,
+        buf: vs::ViPBuf,
+        ret_cnt: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (
+            vi,
+            request_type,
+            request,
+            value,
+            index,
+            length,
+            buf,
+            ret_cnt,
+        );
+        NSUP_OPER
+    }
+    /// viIn8Ex
+    unsafe fn in8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, val);
+        NSUP_OPER
+    }
+    /// viOut8Ex
+    unsafe fn out8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt8,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, val);
+        NSUP_OPER
+    }
+    /// viIn16Ex
+    unsafe fn in16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, val);
+        NSUP_OPER
+    }
+    /// viOut16Ex
+    unsafe fn out16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, val);
+        NSUP_OPER
+    }
+    /// viIn32Ex
+    ///
+    /// `val` points to a `u32`, only 4 bytes may be written, see [trait level doc](Self).
+    unsafe fn in32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, val);
+        NSUP_OPER
+    }
+    /// viOut32Ex
+    unsafe fn out32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, val);
+        NSUP_OPER
+    }
+    /// viIn64Ex
+    unsafe fn in64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, val);
+        NSUP_OPER
+    }
+    /// viOut64Ex
+    unsafe fn out64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt64,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, val);
+        NSUP_OPER
+    }
+    /// viMoveIn8Ex
+    unsafe fn move_in8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, length, buf);
+        NSUP_OPER
+    }
+    /// viMoveOut8Ex
+    unsafe fn move_out8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, length, buf);
+        NSUP_OPER
+    }
+    /// viMoveIn16Ex
+    unsafe fn move_in16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, length, buf);
+        NSUP_OPER
+    }
+    /// viMoveOut16Ex
+    unsafe fn move_out16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, length, buf);
+        NSUP_OPER
+    }
+    /// viMoveIn32Ex
+    ///
+    /// `buf` points to `length` `u32` elements, 4 bytes each, see [trait level doc](Self).
+    unsafe fn move_in32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, length, buf);
+        NSUP_OPER
+    }
+    /// viMoveOut32Ex
+    ///
+    /// `buf` points to `length` `u32` elements, 4 bytes each, see [trait level doc](Self).
+    unsafe fn move_out32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, length, buf);
+        NSUP_OPER
+    }
+    /// viMoveIn64Ex
+    unsafe fn move_in64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, length, buf);
+        NSUP_OPER
+    }
+    /// viMoveOut64Ex
+    unsafe fn move_out64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        let _ = (vi, space, offset, length, buf);
+        NSUP_OPER
+    }
+    /// viMoveEx
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn move_ex(
+        vi: vs::ViSession,
+        src_space: vs::ViUInt16,
+        src_offset: vs::ViBusAddress64,
+        src_width: vs::ViUInt16,
+        dest_space: vs::ViUInt16,
+        dest_offset: vs::ViBusAddress64,
+        dest_width: vs::ViUInt16,
+        src_length: vs::ViBusSize,
+    ) -> vs::ViStatus {
+        let _ = (
+            vi,
+            src_space,
+            src_offset,
+            src_width,
+            dest_space,
+            dest_offset,
+            dest_width,
+            src_length,
+        );
+        NSUP_OPER
+    }
+    /// viMapAddressEx
+    unsafe fn map_address_ex(
+        vi: vs::ViSession,
+        map_space: vs::ViUInt16,
+        map_offset: vs::ViBusAddress64,
+        map_size: vs::ViBusSize,
+        access: vs::ViBoolean,
+        suggested: vs::ViAddr,
+        address: *mut vs::ViAddr,
+    ) -> vs::ViStatus {
+        let _ = (
+            vi, map_space, map_offset, map_size, access, suggested, address,
+        );
+        NSUP_OPER
+    }
+    /// viUnmapAddress
+    unsafe fn unmap_address(vi: vs::ViSession) -> vs::ViStatus {
+        let _ = vi;
+        NSUP_OPER
+    }
+    /// viPeek8, only called with addresses in a window mapped by [`map_address_ex`](Self::map_address_ex)
+    unsafe fn peek8(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt8);
+    /// viPoke8, only called with addresses in a window mapped by [`map_address_ex`](Self::map_address_ex)
+    unsafe fn poke8(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt8);
+    /// viPeek16, only called with addresses in a window mapped by [`map_address_ex`](Self::map_address_ex)
+    unsafe fn peek16(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt16);
+    /// viPoke16, only called with addresses in a window mapped by [`map_address_ex`](Self::map_address_ex)
+    unsafe fn poke16(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt16);
+    /// viPeek32, only called with addresses in a window mapped by [`map_address_ex`](Self::map_address_ex)
+    ///
+    /// `val` points to a `u32`, only 4 bytes may be written, see [trait level doc](Self).
+    unsafe fn peek32(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt32);
+    /// viPoke32, only called with addresses in a window mapped by [`map_address_ex`](Self::map_address_ex)
+    unsafe fn poke32(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt32);
+    /// viPeek64, only called with addresses in a window mapped by [`map_address_ex`](Self::map_address_ex)
+    unsafe fn peek64(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt64);
+    /// viPoke64, only called with addresses in a window mapped by [`map_address_ex`](Self::map_address_ex)
+    unsafe fn poke64(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt64);
+}
+
+/// The VISA library bound by [`visa_sys`], default backend of all sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Visa;
+
+impl Backend for Visa {
+    unsafe fn open_default_rm(vi: *mut vs::ViSession) -> vs::ViStatus {
+        // every other call goes through a session opened here, so only this one needs the check
+        #[cfg(feature = "dynamic-load")]
+        if let Err(e) = crate::library::load() {
+            log::error!("{}", e);
+            return ErrorCode::ErrorLibraryNfound as _;
+        }
+        vs::viOpenDefaultRM(vi)
+    }
+    unsafe fn find_rsrc(
+        sesn: vs::ViSession,
+        expr: vs::ViConstString,
+        vi: *mut vs::ViFindList,
+        ret_cnt: *mut vs::ViUInt32,
+        desc: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        vs::viFindRsrc(sesn, expr, vi, ret_cnt, desc)
+    }
+    unsafe fn find_next(vi: vs::ViFindList, desc: *mut vs::ViChar) -> vs::ViStatus {
+        vs::viFindNext(vi, desc)
+    }
+    unsafe fn parse_rsrc(
+        rm_sesn: vs::ViSession,
+        rsrc_name: vs::ViConstRsrc,
+        intf_type: *mut vs::ViUInt16,
+        intf_num: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viParseRsrc(rm_sesn, rsrc_name, intf_type, intf_num)
+    }
+    unsafe fn parse_rsrc_ex(
+        rm_sesn: vs::ViSession,
+        rsrc_name: vs::ViConstRsrc,
+        intf_type: *mut vs::ViUInt16,
+        intf_num: *mut vs::ViUInt16,
+        rsrc_class: *mut vs::ViChar,
+        expanded_unaliased_name: *mut vs::ViChar,
+        alias_if_exists: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        vs::viParseRsrcEx(
+            rm_sesn,
+            rsrc_name,
+            intf_type,
+            intf_num,
+            rsrc_class,
+            expanded_unaliased_name,
+            alias_if_exists,
+        )
+    }
+    unsafe fn open(
+        sesn: vs::ViSession,
+        name: vs::ViConstRsrc,
+        mode: vs::ViAccessMode,
+        timeout: vs::ViUInt32,
+        vi: *mut vs::ViSession,
+    ) -> vs::ViStatus {
+        vs::viOpen(sesn, name, mode, timeout, vi)
+    }
+    unsafe fn close(vi: vs::ViObject) -> vs::ViStatus {
+        vs::viClose(vi)
+    }
+    unsafe fn set_attribute(
+        vi: vs::ViObject,
+        attr_name: vs::ViAttr,
+        attr_value: vs::ViAttrState,
+    ) -> vs::ViStatus {
+        vs::viSetAttribute(vi, attr_name, attr_value)
+    }
+    unsafe fn get_attribute(
+        vi: vs::ViObject,
+        attr_name: vs::ViAttr,
+        attr_value: *mut std::ffi::c_void,
+    ) -> vs::ViStatus {
+        vs::viGetAttribute(vi, attr_name, attr_value)
+    }
+    unsafe fn status_desc(
+        vi: vs::ViObject,
+        status: vs::ViStatus,
+        desc: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        vs::viStatusDesc(vi, status, desc)
+    }
+    unsafe fn terminate(
+        vi: vs::ViObject,
+        degree: vs::ViUInt16,
+        job_id: vs::ViJobId,
+    ) -> vs::ViStatus {
+        vs::viTerminate(vi, degree, job_id)
+    }
+    unsafe fn lock(
+        vi: vs::ViSession,
+        lock_type: vs::ViAccessMode,
+        timeout: vs::ViUInt32,
+        requested_key: vs::ViConstKeyId,
+        access_key: *mut vs::ViChar,
+    ) -> vs::ViStatus {
+        vs::viLock(vi, lock_type, timeout, requested_key, access_key)
+    }
+    unsafe fn unlock(vi: vs::ViSession) -> vs::ViStatus {
+        vs::viUnlock(vi)
+    }
+    unsafe fn enable_event(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+        context: vs::ViEventFilter,
+    ) -> vs::ViStatus {
+        vs::viEnableEvent(vi, event_type, mechanism, context)
+    }
+    unsafe fn disable_event(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viDisableEvent(vi, event_type, mechanism)
+    }
+    unsafe fn discard_events(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        mechanism: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viDiscardEvents(vi, event_type, mechanism)
+    }
+    unsafe fn wait_on_event(
+        vi: vs::ViSession,
+        in_event_type: vs::ViEventType,
+        timeout: vs::ViUInt32,
+        out_event_type: *mut vs::ViEventType,
+        out_context: *mut vs::ViEvent,
+    ) -> vs::ViStatus {
+        vs::viWaitOnEvent(vi, in_event_type, timeout, out_event_type, out_context)
+    }
+    unsafe fn install_handler(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        handler: vs::ViHndlr,
+        user_handle: vs::ViAddr,
+    ) -> vs::ViStatus {
+        vs::viInstallHandler(vi, event_type, handler, user_handle)
+    }
+    unsafe fn uninstall_handler(
+        vi: vs::ViSession,
+        event_type: vs::ViEventType,
+        handler: vs::ViHndlr,
+        user_handle: vs::ViAddr,
+    ) -> vs::ViStatus {
+        vs::viUninstallHandler(vi, event_type, handler, user_handle)
+    }
+    unsafe fn read(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viRead(vi, buf, cnt, ret_cnt)
+    }
+    unsafe fn read_async(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        job_id: *mut vs::ViJobId,
+    ) -> vs::ViStatus {
+        vs::viReadAsync(vi, buf, cnt, job_id)
+    }
+    unsafe fn write(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viWrite(vi, buf, cnt, ret_cnt)
+    }
+    unsafe fn write_async(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        job_id: *mut vs::ViJobId,
+    ) -> vs::ViStatus {
+        vs::viWriteAsync(vi, buf, cnt, job_id)
+    }
+    unsafe fn assert_trigger(vi: vs::ViSession, protocol: vs::ViUInt16) -> vs::ViStatus {
+        vs::viAssertTrigger(vi, protocol)
+    }
+    unsafe fn read_stb(vi: vs::ViSession, status: *mut vs::ViUInt16) -> vs::ViStatus {
+        vs::viReadSTB(vi, status)
+    }
+    unsafe fn clear(vi: vs::ViSession) -> vs::ViStatus {
+        vs::viClear(vi)
+    }
+    unsafe fn set_buf(vi: vs::ViSession, mask: vs::ViUInt16, size: vs::ViUInt32) -> vs::ViStatus {
+        vs::viSetBuf(vi, mask, size)
+    }
+    unsafe fn flush(vi: vs::ViSession, mask: vs::ViUInt16) -> vs::ViStatus {
+        vs::viFlush(vi, mask)
+    }
+    unsafe fn buf_write(
+        vi: vs::ViSession,
+        buf: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viBufWrite(vi, buf, cnt, ret_cnt)
+    }
+    unsafe fn buf_read(
+        vi: vs::ViSession,
+        buf: vs::ViPBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viBufRead(vi, buf, cnt, ret_cnt)
+    }
+    unsafe fn gpib_control_ren(vi: vs::ViSession, mode: vs::ViUInt16) -> vs::ViStatus {
+        vs::viGpibControlREN(vi, mode)
+    }
+    unsafe fn gpib_control_atn(vi: vs::ViSession, mode: vs::ViUInt16) -> vs::ViStatus {
+        vs::viGpibControlATN(vi, mode)
+    }
+    unsafe fn gpib_send_ifc(vi: vs::ViSession) -> vs::ViStatus {
+        vs::viGpibSendIFC(vi)
+    }
+    unsafe fn gpib_command(
+        vi: vs::ViSession,
+        cmd: vs::ViConstBuf,
+        cnt: vs::ViUInt32,
+        ret_cnt: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viGpibCommand(vi, cmd, cnt, ret_cnt)
+    }
+    unsafe fn gpib_pass_control(
+        vi: vs::ViSession,
+        prim_addr: vs::ViUInt16,
+        sec_addr: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viGpibPassControl(vi, prim_addr, sec_addr)
+    }
+    unsafe fn assert_util_signal(vi: vs::ViSession, line: vs::ViUInt16) -> vs::ViStatus {
+        vs::viAssertUtilSignal(vi, line)
+    }
+    unsafe fn assert_intr_signal(
+        vi: vs::ViSession,
+        mode: vs::ViInt16,
+        status_id: vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viAssertIntrSignal(vi, mode, status_id)
+    }
+    unsafe fn vxi_command_query(
+        vi: vs::ViSession,
+        mode: vs::ViUInt16,
+        cmd: vs::ViUInt32,
+        response: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viVxiCommandQuery(vi, mode, cmd, response)
+    }
+    unsafe fn map_trigger(
+        vi: vs::ViSession,
+        trig_src: vs::ViInt16,
+        trig_dest: vs::ViInt16,
+        mode: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viMapTrigger(vi, trig_src, trig_dest, mode)
+    }
+    unsafe fn unmap_trigger(
+        vi: vs::ViSession,
+        trig_src: vs::ViInt16,
+        trig_dest: vs::ViInt16,
+    ) -> vs::ViStatus {
+        vs::viUnmapTrigger(vi, trig_src, trig_dest)
+    }
+    unsafe fn usb_control_out(
+        vi: vs::ViSession,
+        request_type: vs::ViInt16,
+        request: vs::ViInt16,
+        value: vs::ViUInt16,
+        index: vs::ViUInt16,
+        length: vs::ViUInt16,
+        buf: vs::ViConstBuf,
+    ) -> vs::ViStatus {
+        vs::viUsbControlOut(vi, request_type, request, value, index, length, buf)
+    }
+    unsafe fn usb_control_in(
+        vi: vs::ViSession,
+        request_type: vs::ViInt16,
+        request: vs::ViInt16,
+        value: vs::ViUInt16,
+        index: vs::ViUInt16,
+        length: vs::ViUInt16,
+        buf: vs::ViPBuf,
+        ret_cnt: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viUsbControlIn(
+            vi,
+            request_type,
+            request,
+            value,
+            index,
+            length,
+            buf,
+            ret_cnt,
+        )
+    }
+    unsafe fn in8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        vs::viIn8Ex(vi, space, offset, val)
+    }
+    unsafe fn out8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt8,
+    ) -> vs::ViStatus {
+        vs::viOut8Ex(vi, space, offset, val)
+    }
+    unsafe fn in16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viIn16Ex(vi, space, offset, val)
+    }
+    unsafe fn out16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viOut16Ex(vi, space, offset, val)
+    }
+    unsafe fn in32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viIn32Ex(vi, space, offset, val)
+    }
+    unsafe fn out32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viOut32Ex(vi, space, offset, val)
+    }
+    unsafe fn in64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        vs::viIn64Ex(vi, space, offset, val)
+    }
+    unsafe fn out64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        val: vs::ViUInt64,
+    ) -> vs::ViStatus {
+        vs::viOut64Ex(vi, space, offset, val)
+    }
+    unsafe fn move_in8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        vs::viMoveIn8Ex(vi, space, offset, length, buf)
+    }
+    unsafe fn move_out8_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt8,
+    ) -> vs::ViStatus {
+        vs::viMoveOut8Ex(vi, space, offset, length, buf)
+    }
+    unsafe fn move_in16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viMoveIn16Ex(vi, space, offset, length, buf)
+    }
+    unsafe fn move_out16_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt16,
+    ) -> vs::ViStatus {
+        vs::viMoveOut16Ex(vi, space, offset, length, buf)
+    }
+    unsafe fn move_in32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viMoveIn32Ex(vi, space, offset, length, buf)
+    }
+    unsafe fn move_out32_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt32,
+    ) -> vs::ViStatus {
+        vs::viMoveOut32Ex(vi, space, offset, length, buf)
+    }
+    unsafe fn move_in64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        vs::viMoveIn64Ex(vi, space, offset, length, buf)
+    }
+    unsafe fn move_out64_ex(
+        vi: vs::ViSession,
+        space: vs::ViUInt16,
+        offset: vs::ViBusAddress64,
+        length: vs::ViBusSize,
+        buf: *mut vs::ViUInt64,
+    ) -> vs::ViStatus {
+        vs::viMoveOut64Ex(vi, space, offset, length, buf)
+    }
+    unsafe fn move_ex(
+        vi: vs::ViSession,
+        src_space: vs::ViUInt16,
+        src_offset: vs::ViBusAddress64,
+        src_width: vs::ViUInt16,
+        dest_space: vs::ViUInt16,
+        dest_offset: vs::ViBusAddress64,
+        dest_width: vs::ViUInt16,
+        src_length: vs::ViBusSize,
+    ) -> vs::ViStatus {
+        vs::viMoveEx(
+            vi,
+            src_space,
+            src_offset,
+            src_width,
+            dest_space,
+            dest_offset,
+            dest_width,
+            src_length,
+        )
+    }
+    unsafe fn map_address_ex(
+        vi: vs::ViSession,
+        map_space: vs::ViUInt16,
+        map_offset: vs::ViBusAddress64,
+        map_size: vs::ViBusSize,
+        access: vs::ViBoolean,
+        suggested: vs::ViAddr,
+        address: *mut vs::ViAddr,
+    ) -> vs::ViStatus {
+        vs::viMapAddressEx(
+            vi, map_space, map_offset, map_size, access, suggested, address,
+        )
+    }
+    unsafe fn unmap_address(vi: vs::ViSession) -> vs::ViStatus {
+        vs::viUnmapAddress(vi)
+    }
+    unsafe fn peek8(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt8) {
+        vs::viPeek8(vi, address, val)
+    }
+    unsafe fn poke8(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt8) {
+        vs::viPoke8(vi, address, val)
+    }
+    unsafe fn peek16(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt16) {
+        vs::viPeek16(vi, address, val)
+    }
+    unsafe fn poke16(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt16) {
+        vs::viPoke16(vi, address, val)
+    }
+    unsafe fn peek32(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt32) {
+        vs::viPeek32(vi, address, val)
+    }
+    unsafe fn poke32(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt32) {
+        vs::viPoke32(vi, address, val)
+    }
+    unsafe fn peek64(vi: vs::ViSession, address: vs::ViAddr, val: *mut vs::ViUInt64) {
+        vs::viPeek64(vi, address, val)
+    }
+    unsafe fn poke64(vi: vs::ViSession, address: vs::ViAddr, val: vs::ViUInt64) {
+        vs::viPoke64(vi, address, val)
+    }
+}
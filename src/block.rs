@@ -0,0 +1,591 @@
+//!
+//! IEEE 488.2 arbitrary block data, as sent by `CURVe?`/`WAV:DATA?` like queries.
+//!
+//! A definite length block is `#<n><len><data>`, where `<n>` is the number of digits of the decimal `<len>`,
+//! an indefinite length block is `#0<data>` terminated by a newline sent with END,
+//! or by the termination character if END is disabled, see [`Instrument::read_binary_block`].
+//!
+//! [`Instrument::read_binary_block`] and [`Instrument::write_binary_block`] transfer blocks of [`BlockElement`]s,
+//! with the byte order selected by [`ByteOrder`]. The same operations are available on the `AsyncInstrument` returned by [`Instrument::into_async`].
+//!
+//! ```no_run
+//! # fn f(instr: &visa_rs::Instrument) -> Result<(), Box<dyn std::error::Error>> {
+//! use std::io::Write;
+//! use visa_rs::block::ByteOrder;
+//!
+//! (&*instr).write_all(b"FORM REAL,32;:TRAC:DATA?\n")?;
+//! let trace: Vec<f32> = instr.read_binary_block(ByteOrder::Big)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+
+use std::fmt::Display;
+
+use crate::{
+    async_io::{AsyncInstrument, AsyncRead, AsyncReadStatus},
+    backend::Backend,
+    enums::status::{CompletionCode, ErrorCode},
+    wrap_raw_error_in_unsafe, AsRawSs, Error, Instrument,
+};
+use visa_sys as vs;
+
+/// Bytes requested at once while reading up to END
+const CHUNK_SIZE: usize = 4096;
+/// Most bytes allocated ahead of the data of a definite length block, as its length comes from the response itself
+const MAX_PREALLOC: usize = CHUNK_SIZE * 64;
+
+/// Byte order of the elements of a block, set on the instrument by e.g. `FORM:BORD NORM|SWAP`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+    /// Most significant byte first, `NORMal` in SCPI
+    Big,
+    /// Least significant byte first, `SWAPped` in SCPI
+    Little,
+}
+
+/// Elements a block can be decoded into
+pub trait BlockElement: Copy {
+    /// Size in bytes
+    const SIZE: usize;
+    /// Decodes an element from exactly [`Self::SIZE`] bytes
+    fn from_bytes(bytes: &[u8], order: ByteOrder) -> Self;
+    /// Appends the encoded element to `out`
+    fn to_bytes(self, order: ByteOrder, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_block_element {
+    ($($t:ty)*) => {
+        $(
+            impl BlockElement for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+                fn from_bytes(bytes: &[u8], order: ByteOrder) -> Self {
+                    let bytes = bytes.try_into().expect("element size");
+                    match order {
+                        ByteOrder::Big => <$t>::from_be_bytes(bytes),
+                        ByteOrder::Little => <$t>::from_le_bytes(bytes),
+                    }
+                }
+                fn to_bytes(self, order: ByteOrder, out: &mut Vec<u8>) {
+                    match order {
+                        ByteOrder::Big => out.extend_from_slice(&self.to_be_bytes()),
+                        ByteOrder::Little => out.extend_from_slice(&self.to_le_bytes()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_block_element!(u8 i8 u16 i16 u32 i32 u64 i64 f32 f64);
+
+/// Error reading a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    /// The response doesn't start with `#`, holds the first byte received
+    NotABlock(Option<u8>),
+    /// The digit count or the length following `#` is not decimal, holds the header received after `#`
+    InvalidHeader(Vec<u8>),
+    /// END was received before the length announced in the header
+    Truncated { expected: usize, received: usize },
+    /// The data length is not a multiple of the element size
+    Misaligned { len: usize, size: usize },
+    /// The transfer failed
+    Visa(Error),
+}
+
+impl Display for BlockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockError::NotABlock(Some(b)) => {
+                write!(
+                    f,
+                    "expected a block starting with '#', found {:?}",
+                    *b as char
+                )
+            }
+            BlockError::NotABlock(None) => write!(f, "expected a block, found an empty response"),
+            BlockError::InvalidHeader(h) => {
+                write!(f, "invalid block header '#{}'", String::from_utf8_lossy(h))
+            }
+            BlockError::Truncated { expected, received } => write!(
+                f,
+                "block truncated, expected {expected} bytes, received {received}"
+            ),
+            BlockError::Misaligned { len, size } => write!(
+                f,
+                "block of {len} bytes is not a multiple of the element size {size}"
+            ),
+            BlockError::Visa(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BlockError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BlockError::Visa(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for BlockError {
+    fn from(e: Error) -> Self {
+        Self::Visa(e)
+    }
+}
+
+impl From<BlockError> for Error {
+    /// Malformed blocks become [`ErrorInvFmt`](ErrorCode::ErrorInvFmt)
+    fn from(e: BlockError) -> Self {
+        match e {
+            BlockError::Visa(e) => e,
+            _ => Error(ErrorCode::ErrorInvFmt),
+        }
+    }
+}
+
+/// Decodes the data of a block
+pub fn decode<T: BlockElement>(data: &[u8], order: ByteOrder) -> Result<Vec<T>, BlockError> {
+    if !data.len().is_multiple_of(T::SIZE) {
+        return Err(BlockError::Misaligned {
+            len: data.len(),
+            size: T::SIZE,
+        });
+    }
+    Ok(data
+        .chunks_exact(T::SIZE)
+        .map(|c| T::from_bytes(c, order))
+        .collect())
+}
+
+/// Encodes `data` as a definite length block, header included
+pub fn encode<T: BlockElement>(data: &[T], order: ByteOrder) -> Vec<u8> {
+    let len = (data.len() * T::SIZE).to_string();
+    let mut out = Vec::with_capacity(2 + len.len() + data.len() * T::SIZE);
+    out.push(b'#');
+    out.push(b'0' + len.len() as u8);
+    out.extend_from_slice(len.as_bytes());
+    for d in data {
+        d.to_bytes(order, &mut out);
+    }
+    out
+}
+
+#[derive(Debug)]
+enum Stage {
+    /// `#` and the digit count
+    Header,
+    /// the decimal length of the given number of digits
+    Length(usize),
+    /// data of the given length
+    Definite(usize),
+    /// data up to END or the termination character
+    Indefinite,
+    /// the rest of the response up to END or the termination character, usually the terminator
+    Trailer,
+    Done,
+}
+
+/// Parses a block fed by successive reads, shared by [`Instrument::read_binary_block`] and [`Block`](crate::formatted::Block)
+#[derive(Debug)]
+pub(crate) struct BlockReader {
+    stage: Stage,
+    header: Vec<u8>,
+    pub(crate) data: Vec<u8>,
+    /// the termination character ends the response, END being disabled
+    term_ends: bool,
+    /// read the rest of the response after a definite length block
+    trailer: bool,
+    indefinite: bool,
+}
+
+impl BlockReader {
+    pub(crate) fn new<B: Backend>(instr: &Instrument<B>) -> Self {
+        Self::with_term_ends(instr.end_disabled())
+    }
+
+    fn with_term_ends(term_ends: bool) -> Self {
+        Self {
+            stage: Stage::Header,
+            header: Vec::new(),
+            data: Vec::new(),
+            term_ends,
+            trailer: true,
+            indefinite: false,
+        }
+    }
+
+    /// Stops after the data, leaving the rest of the response unread
+    pub(crate) fn without_trailer(mut self) -> Self {
+        self.trailer = false;
+        self
+    }
+
+    /// The block was indefinite, so it ended with the response
+    pub(crate) fn is_indefinite(&self) -> bool {
+        self.indefinite
+    }
+
+    /// Stage after the data of a definite length block
+    fn after_data(&self, end: bool) -> Stage {
+        if end || !self.trailer {
+            Stage::Done
+        } else {
+            Stage::Trailer
+        }
+    }
+
+    /// Number of bytes to read next, `None` when the block is complete
+    pub(crate) fn want(&self) -> Option<usize> {
+        match self.stage {
+            Stage::Header => Some(2 - self.header.len()),
+            Stage::Length(n) => Some(n - self.header.len()),
+            Stage::Definite(len) => Some((len - self.data.len()).min(MAX_PREALLOC)),
+            Stage::Indefinite | Stage::Trailer => Some(CHUNK_SIZE),
+            Stage::Done => None,
+        }
+    }
+
+    /// Consumes bytes read, `code` tells how the read stopped.
+    ///
+    /// The header and definite length data are read over the termination character,
+    /// indefinite length data and the trailer end at it only if END is disabled.
+    pub(crate) fn feed(&mut self, bytes: &[u8], code: CompletionCode) -> Result<(), BlockError> {
+        let term = self.term_ends && code == CompletionCode::SuccessTermChar;
+        let end = !matches!(
+            code,
+            CompletionCode::SuccessMaxCnt | CompletionCode::SuccessTermChar
+        );
+        match self.stage {
+            Stage::Header => {
+                self.header.extend_from_slice(bytes);
+                match self.header[..] {
+                    [] | [b'#'] if end => {
+                        return Err(BlockError::NotABlock(self.header.first().copied()))
+                    }
+                    [] | [b'#'] => {}
+                    [b'#', b'0'] => {
+                        self.stage = Stage::Indefinite;
+                        self.indefinite = true;
+                    }
+                    [b'#', n @ b'1'..=b'9'] => self.stage = Stage::Length((n - b'0') as _),
+                    [b'#', n] => return Err(BlockError::InvalidHeader(vec![n])),
+                    [b, ..] => return Err(BlockError::NotABlock(Some(b))),
+                }
+                if !matches!(self.stage, Stage::Header) {
+                    self.header.clear();
+                }
+            }
+            Stage::Length(n) => {
+                self.header.extend_from_slice(bytes);
+                let digits = self.header.len() == n;
+                let len = std::str::from_utf8(&self.header)
+                    .ok()
+                    .filter(|l| l.bytes().all(|c| c.is_ascii_digit()))
+                    .and_then(|l| l.parse().ok());
+                match len {
+                    Some(len) if digits => {
+                        self.stage = Stage::Definite(len);
+                        self.data.reserve(len.min(MAX_PREALLOC));
+                        if len == 0 {
+                            self.stage = self.after_data(end);
+                        }
+                    }
+                    _ if !digits && !end => {}
+                    _ => {
+                        let mut header = vec![b'0' + n as u8];
+                        header.append(&mut self.header);
+                        return Err(BlockError::InvalidHeader(header));
+                    }
+                }
+            }
+            Stage::Definite(len) => {
+                self.data.extend_from_slice(bytes);
+                if self.data.len() == len {
+                    self.stage = self.after_data(end);
+                } else if end {
+                    return Err(BlockError::Truncated {
+                        expected: len,
+                        received: self.data.len(),
+                    });
+                }
+            }
+            Stage::Indefinite => {
+                self.data.extend_from_slice(bytes);
+                if end || term {
+                    // the block is terminated by NL^END, or the termination character alone
+                    if self.data.last() == Some(&b'\n') || term {
+                        self.data.pop();
+                    }
+                    self.stage = Stage::Done;
+                }
+            }
+            Stage::Trailer if end || term => self.stage = Stage::Done,
+            Stage::Trailer | Stage::Done => {}
+        }
+        Ok(())
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Reads a block with viRead() and decodes it, the response must start with `#`.
+    ///
+    /// Definite length blocks are read over the termination character,
+    /// the rest of the response after the block, usually the terminator, is discarded.
+    /// Indefinite length blocks end at END, or at the termination character if END is disabled
+    /// by `VI_ATTR_SUPPRESS_END_EN` or a serial port with `VI_ATTR_ASRL_END_IN` set to `VI_ASRL_END_NONE`.
+    pub fn read_binary_block<T: BlockElement>(
+        &self,
+        order: ByteOrder,
+    ) -> Result<Vec<T>, BlockError> {
+        let mut reader = BlockReader::new(self);
+        let mut buf = Vec::new();
+        while let Some(want) = reader.want() {
+            buf.resize(want, 0);
+            let mut ret_cnt: vs::ViUInt32 = 0;
+            let code = wrap_raw_error_in_unsafe!(B::read(
+                self.as_raw_ss(),
+                buf.as_mut_ptr(),
+                want as _,
+                &mut ret_cnt as _
+            ))?;
+            reader.feed(&buf[..ret_cnt as usize], code)?;
+        }
+        decode(&reader.data, order)
+    }
+
+    /// Writes `command` followed by `data` as a definite length block and the termination character `VI_ATTR_TERMCHAR`, in a single viWrite().
+    ///
+    /// ```no_run
+    /// # fn f(instr: &visa_rs::Instrument) -> visa_rs::Result<()> {
+    /// use visa_rs::block::ByteOrder;
+    /// let wave: Vec<i16> = (0..1000).map(|i| (i * 32) as i16).collect();
+    /// instr.write_binary_block("SOUR:ARB:DATA ", &wave, ByteOrder::Little)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_binary_block<T: BlockElement>(
+        &self,
+        command: &str,
+        data: &[T],
+        order: ByteOrder,
+    ) -> crate::Result<()> {
+        let mut msg = command.as_bytes().to_vec();
+        msg.append(&mut encode(data, order));
+        msg.push(self.termchar());
+        self.write_all_raw(&msg)
+    }
+}
+
+impl<B: Backend> AsyncInstrument<B> {
+    /// Asynchronous [`Instrument::read_binary_block`]
+    pub async fn read_binary_block<T: BlockElement>(
+        &self,
+        order: ByteOrder,
+    ) -> Result<Vec<T>, BlockError> {
+        let mut reader = BlockReader::new(self.instrument());
+        let mut buf = Vec::new();
+        while let Some(want) = reader.want() {
+            buf.resize(want, 0);
+            let (cnt, code) = AsyncReadStatus(AsyncRead::new(self, &mut buf)).await?;
+            reader.feed(&buf[..cnt.min(want)], code)?;
+        }
+        decode(&reader.data, order)
+    }
+
+    /// Asynchronous [`Instrument::write_binary_block`]
+    pub async fn write_binary_block<T: BlockElement>(
+        &self,
+        command: &str,
+        data: &[T],
+        order: ByteOrder,
+    ) -> crate::Result<()> {
+        let mut msg = command.as_bytes().to_vec();
+        msg.append(&mut encode(data, order));
+        msg.push(self.instrument().termchar());
+        let mut written = 0;
+        while written < msg.len() {
+            written += self.async_write(&msg[written..]).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{backend::mock::MockResource, enums::status::CompletionCode};
+    use anyhow::Result;
+    use std::io::Write;
+
+    const MORE: CompletionCode = CompletionCode::SuccessMaxCnt;
+    const TERM: CompletionCode = CompletionCode::SuccessTermChar;
+    const END: CompletionCode = CompletionCode::Success;
+
+    fn parse(chunks: &[(&[u8], CompletionCode)]) -> Result<Vec<u8>, BlockError> {
+        parse_with(false, chunks)
+    }
+
+    fn parse_with(
+        term_ends: bool,
+        chunks: &[(&[u8], CompletionCode)],
+    ) -> Result<Vec<u8>, BlockError> {
+        let mut reader = BlockReader::with_term_ends(term_ends);
+        for (bytes, code) in chunks {
+            reader.feed(bytes, *code)?;
+        }
+        assert!(reader.want().is_none());
+        Ok(reader.data)
+    }
+
+    #[test]
+    fn headers() {
+        assert_eq!(
+            parse(&[(b"#1", MORE), (b"3", MORE), (b"a\nc", MORE), (b"\n", END)]),
+            Ok(b"a\nc".to_vec())
+        );
+        assert_eq!(parse(&[(b"#0", MORE), (b"ab\n", END)]), Ok(b"ab".to_vec()));
+        assert_eq!(parse(&[(b"#2", MORE), (b"00", END)]), Ok(vec![]));
+        assert_eq!(
+            parse(&[(b"1,", MORE)]),
+            Err(BlockError::NotABlock(Some(b'1')))
+        );
+        assert_eq!(parse(&[(b"", END)]), Err(BlockError::NotABlock(None)));
+        assert_eq!(
+            parse(&[(b"#x", MORE)]),
+            Err(BlockError::InvalidHeader(b"x".to_vec()))
+        );
+        assert_eq!(
+            parse(&[(b"#2", MORE), (b"1a", MORE)]),
+            Err(BlockError::InvalidHeader(b"21a".to_vec()))
+        );
+        assert_eq!(
+            parse(&[(b"#1", MORE), (b"5", MORE), (b"ab", END)]),
+            Err(BlockError::Truncated {
+                expected: 5,
+                received: 2
+            })
+        );
+    }
+
+    #[test]
+    fn bogus_length() {
+        // the length of the header is not trusted for allocations
+        let mut reader = BlockReader::with_term_ends(false);
+        reader.feed(b"#9", MORE).unwrap();
+        reader.feed(b"999999999", MORE).unwrap();
+        assert_eq!(reader.want(), Some(MAX_PREALLOC));
+        assert!(reader.data.capacity() <= MAX_PREALLOC);
+        assert_eq!(
+            reader.feed(b"ab", END),
+            Err(BlockError::Truncated {
+                expected: 999999999,
+                received: 2
+            })
+        );
+    }
+
+    #[test]
+    fn termchar_without_end() {
+        // a termination character in definite length data is read over
+        assert_eq!(
+            parse_with(
+                true,
+                &[
+                    (b"#1", MORE),
+                    (b"3", MORE),
+                    (b"a\n", TERM),
+                    (b"c", MORE),
+                    (b"\n", TERM)
+                ]
+            ),
+            Ok(b"a\nc".to_vec())
+        );
+        assert_eq!(
+            parse_with(true, &[(b"#0", MORE), (b"ab\n", TERM)]),
+            Ok(b"ab".to_vec())
+        );
+        assert_eq!(
+            parse_with(true, &[(b"#0", MORE), (b"ab;", TERM)]),
+            Ok(b"ab".to_vec())
+        );
+        // with END enabled the termination character is data
+        assert_eq!(
+            parse(&[(b"#0", MORE), (b"a\n", TERM), (b"b\n", END)]),
+            Ok(b"a\nb".to_vec())
+        );
+    }
+
+    #[test]
+    fn encode_decode() {
+        let data = [1.5f32, -2.0];
+        let block = encode(&data, ByteOrder::Little);
+        assert_eq!(&block[..3], b"#18");
+        assert_eq!(
+            decode::<f32>(&block[3..], ByteOrder::Little),
+            Ok(data.to_vec())
+        );
+        assert_eq!(decode::<i16>(&[1, 2], ByteOrder::Big), Ok(vec![0x0102]));
+        assert_eq!(decode::<i16>(&[1, 2], ByteOrder::Little), Ok(vec![0x0201]));
+        assert_eq!(
+            decode::<i32>(&[0; 6], ByteOrder::Big),
+            Err(BlockError::Misaligned { len: 6, size: 4 })
+        );
+    }
+
+    #[test]
+    fn binary_blocks() -> Result<()> {
+        let curve = [
+            b"#18".as_slice(),
+            &1.5f32.to_le_bytes(),
+            &(-2.0f32).to_le_bytes(),
+            b"\n",
+        ]
+        .concat();
+        let (dev, _rm, instr) = MockResource::new("TCPIP0::mock-scope::INSTR")
+            .respond("CURV?", curve)
+            .respond("WAV?", b"#0\x01\n\x02\n")
+            .respond("BAD?", "#x12\n")
+            .open()?;
+
+        (&instr).write_all(b"CURV?\n")?;
+        assert_eq!(
+            instr.read_binary_block::<f32>(ByteOrder::Little)?,
+            [1.5, -2.0]
+        );
+        (&instr).write_all(b"WAV?\n")?;
+        assert_eq!(instr.read_binary_block::<i8>(ByteOrder::Big)?, [1, 10, 2]);
+        (&instr).write_all(b"BAD?\n")?;
+        assert_eq!(
+            instr.read_binary_block::<u8>(ByteOrder::Big),
+            Err(BlockError::InvalidHeader(b"x".to_vec()))
+        );
+        instr.clear()?;
+        dev.take_written();
+        instr.write_binary_block("DATA ", &[0x0102i16, 3], ByteOrder::Big)?;
+        assert_eq!(dev.take_written(), b"DATA #14\x01\x02\x00\x03\n");
+
+        let instr = instr.into_async()?;
+        let task = async {
+            instr.async_write(b"WAV?\n").await?;
+            assert_eq!(
+                instr.read_binary_block::<u8>(ByteOrder::Big).await?,
+                [1, 10, 2]
+            );
+            instr
+                .write_binary_block("DATA ", &[1.0f64], ByteOrder::Little)
+                .await?;
+            Result::<()>::Ok(())
+        };
+        tokio::runtime::Builder::new_current_thread()
+            .build()?
+            .block_on(task)?;
+        assert_eq!(
+            dev.take_written(),
+            [b"WAV?\nDATA #18".as_slice(), &1.0f64.to_le_bytes(), b"\n"].concat()
+        );
+        Ok(())
+    }
+}
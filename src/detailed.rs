@@ -0,0 +1,228 @@
+//! Opt-in errors recording where a VISA call failed.
+//!
+//! [`Error`] only carries an [`ErrorCode`], so it prints the same text whichever call or instrument failed.
+//! [`ResultExt::detailed`] turns it into a [`DetailedError`], which also records the failing VISA operation,
+//! the resource name from `VI_ATTR_RSRC_NAME`, the description given by viStatusDesc() and the raw status.
+//!
+//! The operation is the last VISA call that failed on the current thread, recorded by the wrappers of this crate,
+//! so call [`ResultExt::detailed`] right on the result of the failing method.
+//!
+//! ```no_run
+//! # fn f(instr: &visa_rs::Instrument) -> Result<(), visa_rs::detailed::DetailedError> {
+//! use visa_rs::{detailed::ResultExt, enums::status::ErrorCode};
+//!
+//! // the error tells whether viWrite or viRead failed
+//! match instr.query("*IDN?").detailed(instr) {
+//!     // still matchable by code
+//!     Err(e) if e.code() == ErrorCode::ErrorTmo => eprintln!("{e}"),
+//!     r => println!("{}", r?),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cell::Cell;
+
+use super::*;
+use enums::{attribute::SpecAttr, status::ErrorCode};
+use session::AsRawSs;
+
+thread_local! {
+    /// Status and call expression of the last VISA call that failed on this thread
+    static LAST_FAILURE: Cell<Option<(vs::ViStatus, &'static str)>> = const { Cell::new(None) };
+}
+
+/// Records a failed call, used by `wrap_raw_error_in_unsafe!`
+#[doc(hidden)]
+pub fn record_failure(status: vs::ViStatus, call: &'static str) {
+    LAST_FAILURE.with(|last| last.set(Some((status, call))));
+}
+
+/// Name of the VISA function called by `call`, e.g. `viRead` for `B::read(vi, buf, cnt, ret)`
+fn operation_name(call: &str) -> String {
+    let path = call.split('(').next().unwrap_or_default();
+    let name = path.rsplit("::").next().unwrap_or_default().trim();
+    if name.starts_with("vi") && !name.contains('_') {
+        return name.to_string();
+    }
+    let mut ret = String::from("vi");
+    for word in name.split('_') {
+        match word {
+            // viGpibControlATN, viGpibControlREN
+            "atn" | "ren" => ret.push_str(&word.to_uppercase()),
+            _ => {
+                let mut chars = word.chars();
+                ret.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                ret.push_str(chars.as_str());
+            }
+        }
+    }
+    ret
+}
+
+/// An [`Error`] with the context it occurred in, see the [module](self) documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DetailedError {
+    error: Error,
+    operation: Option<String>,
+    resource: Option<String>,
+    description: Option<String>,
+}
+
+impl DetailedError {
+    /// Records `error` returned on the session `ss`.
+    ///
+    /// The operation is the last VISA call that failed on this thread, if it failed with `error`.
+    /// The resource name and the description are queried from `ss`, and left empty if that fails, e.g. because the session is already closed.
+    pub fn new<S: AsRawSs>(error: Error, ss: &S) -> Self {
+        let operation = LAST_FAILURE
+            .with(Cell::take)
+            .filter(|(status, _)| *status == error.into())
+            .map(|(_, call)| operation_name(call));
+        let resource = attribute::AttrRsrcName::get_from(ss)
+            .ok()
+            .map(|name| name.into_inner().to_string());
+        let mut desc: VisaBuf = new_visa_buf();
+        let description = wrap_raw_error_in_unsafe!(S::Backend::status_desc(
+            ss.as_raw_ss(),
+            error.into(),
+            desc.as_mut_ptr() as _
+        ))
+        .ok()
+        .and_then(|_| VisaString::try_from(desc).ok())
+        .map(|d| d.to_string());
+        Self {
+            error,
+            operation,
+            resource,
+            description,
+        }
+    }
+
+    /// Code to match on
+    pub fn code(&self) -> ErrorCode {
+        self.error.0
+    }
+
+    /// Error without context
+    pub fn error(&self) -> Error {
+        self.error
+    }
+
+    /// Status returned by the VISA library
+    pub fn status(&self) -> vs::ViStatus {
+        self.error.into()
+    }
+
+    /// Failing VISA operation, e.g. `viRead`, `None` if the error was not returned by the VISA library
+    pub fn operation(&self) -> Option<&str> {
+        self.operation.as_deref()
+    }
+
+    /// Name of the resource the session was opened to
+    pub fn resource(&self) -> Option<&str> {
+        self.resource.as_deref()
+    }
+
+    /// Description of the status given by viStatusDesc()
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
+
+impl std::fmt::Display for DetailedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.operation.as_deref().unwrap_or("VISA operation")
+        )?;
+        if let Some(resource) = &self.resource {
+            write!(f, " on {resource}")?;
+        }
+        write!(f, " failed with {:#010X}: ", self.status() as u32)?;
+        match &self.description {
+            Some(desc) => write!(f, "{desc}"),
+            None => write!(f, "{}", self.error.0.to_string().trim()),
+        }
+    }
+}
+
+impl std::error::Error for DetailedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<DetailedError> for Error {
+    fn from(e: DetailedError) -> Self {
+        e.error
+    }
+}
+
+impl From<DetailedError> for ErrorCode {
+    fn from(e: DetailedError) -> Self {
+        e.error.0
+    }
+}
+
+impl From<DetailedError> for std::io::Error {
+    fn from(e: DetailedError) -> Self {
+        std::io::Error::new(e.code().io_error_kind(), e)
+    }
+}
+
+/// Adds context to the [`Error`] of a [`Result`]
+pub trait ResultExt<T> {
+    /// Turns the error into a [`DetailedError`] returned on the session `ss`
+    fn detailed<S: AsRawSs>(self, ss: &S) -> std::result::Result<T, DetailedError>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn detailed<S: AsRawSs>(self, ss: &S) -> std::result::Result<T, DetailedError> {
+        self.map_err(|e| DetailedError::new(e, ss))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{backend::mock::MockResource, enums::status::ErrorCode, Error, TIMEOUT_IMMEDIATE};
+
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn detailed_errors() -> Result<()> {
+        let (dev, rm, a) = MockResource::new("TCPIP0::10.0.0.9::inst0::INSTR").open()?;
+        let b = dev.open(&rm)?;
+        a.lock_exclusive(TIMEOUT_IMMEDIATE)?;
+        let err = b.query("*IDN?").detailed(&b).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::ErrorRsrcLocked);
+        assert_eq!(err.status(), ErrorCode::ErrorRsrcLocked.into());
+        assert_eq!(err.operation(), Some("viWrite"));
+        assert_eq!(err.resource(), Some("TCPIP0::10.0.0.9::inst0::INSTR"));
+        let desc = b
+            .status_desc(Error(ErrorCode::ErrorRsrcLocked))?
+            .to_string();
+        assert_eq!(err.description(), Some(desc.as_str()));
+        assert_eq!(
+            err.to_string(),
+            format!("viWrite on TCPIP0::10.0.0.9::inst0::INSTR failed with 0xBFFF000F: {desc}")
+        );
+        assert_eq!(Error::from(err), Error(ErrorCode::ErrorRsrcLocked));
+
+        // errors not returned by VISA have no operation
+        let err = b
+            .wait_on_events(&[], TIMEOUT_IMMEDIATE)
+            .detailed(&b)
+            .unwrap_err();
+        assert_eq!(err.operation(), None);
+        assert!(err.to_string().starts_with("VISA operation on "));
+        assert_eq!(
+            operation_name("B::gpib_control_ren(vi, mode)"),
+            "viGpibControlREN"
+        );
+        assert_eq!(operation_name("vs::viFindRsrc(vi, expr)"), "viFindRsrc");
+        Ok(())
+    }
+}
@@ -86,16 +86,40 @@
 //!     }
 //! }
 //! ```
+//!
+//! Attributes whose range only lists named values, like `VI_ATTR_ASRL_PARITY`, also get an enum named after the attribute without the `Attr` prefix,
+//! with the common prefix of the values removed:
+//!
+//! ```ignore
+//! ///Values listed for [`AttrAsrlParity`]
+//! #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+//! pub enum AsrlParity {
+//!     ///VI_ASRL_PAR_NONE
+//!     None,
+//!     ///VI_ASRL_PAR_ODD
+//!     Odd,
+//!     // ...
+//! }
+//!
+//! impl From<AsrlParity> for AttrAsrlParity { /* ... */ }
+//! impl TryFrom<AttrAsrlParity> for AsrlParity { type Error = crate::Error; /* ... */ }
+//! impl From<AsrlParity> for Attribute { /* ... */ }
+//! impl std::fmt::Display for AsrlParity { /* writes VI_ASRL_PAR_NONE, ... */ }
+//! ```
+//!
+//! Bit mask attributes, those described as bit-ORed values, a bit vector or a bit map like `VI_ATTR_ASRL_FLOW_CNTRL`,
+//! get [`bitflags`] types instead, e.g. `AsrlFlowCntrl::XON_XOFF | AsrlFlowCntrl::RTS_CTS`,
+//! and their `new_checked` accepts any combination of the bits.
+//! The bits are the values listed in the range, or those the description names, e.g. `VxiVmeIntrStatus::LINE1` for "bits 0-6 corresponding to interrupt lines 1-7".
 
-use crate::{wrap_raw_error_in_unsafe, Result};
+use crate::{backend::Backend, session::AsRawSs, wrap_raw_error_in_unsafe, Outcome, Result};
 
 pub use attributes::*;
-use visa_sys as vs;
-pub trait HasAttribute: crate::session::AsRawSs {
+pub trait HasAttribute: AsRawSs {
     /// if want a specific attribute, use [`SpecAttr::get_from`]
     fn get_attr(&self, attr_kind: AttrKind) -> Result<Attribute> {
         let mut attr = unsafe { Attribute::from_kind(attr_kind) };
-        wrap_raw_error_in_unsafe!(vs::viGetAttribute(
+        wrap_raw_error_in_unsafe!(<Self as AsRawSs>::Backend::get_attribute(
             self.as_raw_ss(),
             attr_kind as _,
             attr.mut_c_void()
@@ -103,17 +127,21 @@ pub trait HasAttribute: crate::session::AsRawSs {
         Ok(attr)
     }
     fn set_attr(&self, attr: impl Into<Attribute>) -> Result<()> {
+        self.set_attr_outcome(attr).map(Outcome::into_inner)
+    }
+    /// Same as [`Self::set_attr`], returning the completion code, e.g. [`WarnNsupAttrState`](crate::enums::status::CompletionCode::WarnNsupAttrState)
+    fn set_attr_outcome(&self, attr: impl Into<Attribute>) -> Result<Outcome<()>> {
         let attr: Attribute = attr.into();
-        wrap_raw_error_in_unsafe!(vs::viSetAttribute(
+        let code = wrap_raw_error_in_unsafe!(<Self as AsRawSs>::Backend::set_attribute(
             self.as_raw_ss(),
             attr.kind() as _,
             attr.as_attr_state(),
         ))?;
-        Ok(())
+        Ok(Outcome::new((), code))
     }
 }
 
-impl<T: crate::session::AsRawSs> HasAttribute for T {}
+impl<T: AsRawSs> HasAttribute for T {}
 
 /// Trait for all specific attributes
 pub trait SpecAttr: Sized {
@@ -129,7 +157,7 @@ pub trait SpecAttr: Sized {
     fn mut_c_void(&mut self) -> *mut ::std::ffi::c_void;
     fn get_from<S: HasAttribute>(s: &S) -> Result<Self> {
         let mut ret = unsafe { Self::zero() };
-        wrap_raw_error_in_unsafe!(vs::viGetAttribute(
+        wrap_raw_error_in_unsafe!(<S as AsRawSs>::Backend::get_attribute(
             s.as_raw_ss(),
             Self::KIND as _,
             ret.mut_c_void()
@@ -144,6 +172,113 @@ impl<T: SpecAttr> PartialEq<T> for AttrKind {
     }
 }
 
+/// Access, scope, type, default and range of an attribute as listed in the NI-VISA document, returned by [`AttrKind::meta`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AttrMeta {
+    /// Listed as "Read/Write" or "Read Only"
+    pub readable: bool,
+    /// Listed as "Read/Write"
+    pub writable: bool,
+    /// `None` for attributes of events
+    pub scope: Option<AttrScope>,
+    /// Type of the value in C on the current platform, e.g. `ViUInt16`
+    pub c_type: &'static str,
+    /// `None` if not applicable
+    pub default: Option<i128>,
+    /// Values listed as valid, empty if not specified
+    pub range: &'static [RangeItem],
+    /// Resource classes the range is listed for, empty if it doesn't depend on the resource class
+    pub applicable_resource_classes: &'static [ResourceClass],
+}
+
+impl AttrMeta {
+    /// Whether `value` is listed in [`Self::range`], always true if the range is not specified
+    pub fn accepts(&self, value: i128) -> bool {
+        self.range.is_empty() || self.range.iter().any(|x| x.contains(value))
+    }
+}
+
+/// See [`AttrMeta::scope`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttrScope {
+    /// The attribute applies to the session only
+    Local,
+    /// The attribute applies to every session of the resource
+    Global,
+}
+
+/// See [`AttrMeta::range`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RangeItem {
+    /// A single value, e.g. `VI_ASRL_PAR_NONE (0)`
+    Value {
+        name: Option<&'static str>,
+        value: i128,
+    },
+    /// Values from `min` to `max` inclusive
+    Range {
+        name: Option<&'static str>,
+        min: i128,
+        max: i128,
+    },
+}
+
+impl RangeItem {
+    pub fn contains(&self, v: i128) -> bool {
+        match *self {
+            Self::Value { value, .. } => value == v,
+            Self::Range { min, max, .. } => (min..=max).contains(&v),
+        }
+    }
+}
+
+/// Resource classes an attribute range is listed for in the NI-VISA document, see [`AttrMeta::applicable_resource_classes`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceClass {
+    Pxi,
+    Serial,
+    Gpib,
+    Vxi,
+    Tcpip,
+    UsbRaw,
+    UsbInstr,
+}
+
+/// (De)serializes the buffer of string attributes as the string in it
+/// Serialized form of an enumerated attribute, the VISA name of its value, or the value itself if not listed
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum NamedValue<T> {
+    Name(String),
+    Value(T),
+}
+
+#[cfg(feature = "serde")]
+mod visa_buf_serde {
+    use crate::{new_visa_buf, VisaBuf};
+    use serde::{de::Error, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(buf: &VisaBuf, s: S) -> Result<S::Ok, S::Error> {
+        let len = buf.iter().position(|&x| x == 0).unwrap_or(buf.len());
+        s.serialize_str(std::str::from_utf8(&buf[..len]).map_err(S::Error::custom)?)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<VisaBuf, D::Error> {
+        let s = String::deserialize(d)?;
+        let mut buf = new_visa_buf();
+        // keep the trailing nul
+        if s.len() >= buf.len() || s.contains('\0') {
+            return Err(D::Error::custom(format!(
+                "expected a string without nul of less than {} bytes",
+                buf.len()
+            )));
+        }
+        buf[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(buf)
+    }
+}
+
 mod attributes {
     #![allow(non_upper_case_globals)]
     #![allow(overflowing_literals)]
@@ -733,6 +868,21 @@ mod attributes {
             const VI_ATTR_TCPIP_HOSTNAME: r#"This specifies the host name of the device. If no host name is available, this attribute returns an empty string."#
             (Read Only Global) ( ViString) [static as N/A in N/A]
 
+            const VI_ATTR_TCPIP_HISLIP_ENCRYPTION_EN: r#"VI_ATTR_TCPIP_HISLIP_ENCRYPTION_EN specifies whether the HiSLIP connection is encrypted with TLS. It is valid only for HiSLIP 2.0 connections."#
+            (Read/Write Local) ( ViBoolean) [static as N/A in VI_TRUE (1) VI_FALSE (0)]
+
+            const VI_ATTR_TCPIP_HISLIP_MAX_MESSAGE_KB: r#"This is the maximum HiSLIP message size in kilobytes VISA will accept from a HiSLIP system. Larger messages are rejected by VISA."#
+            (Read/Write Local) ( ViUInt32) [static as 1024 in 0 to FFFFFFFFh]
+
+            const VI_ATTR_TCPIP_HISLIP_OVERLAP_EN: r#"This enables HiSLIP 'Overlap' mode. The value defaults to the mode suggested by the instrument on HiSLIP connection. If disabled, the connection uses 'Synchronous' mode to detect and recover from interrupted errors. If enabled, the connection uses 'Overlapped' mode to allow overlapped responses. If changed, VISA will do a Device Clear operation to change the mode."#
+            (Read/Write Local) ( ViBoolean) [static as N/A in VI_TRUE (1) VI_FALSE (0)]
+
+            const VI_ATTR_TCPIP_HISLIP_VERSION: r#"This is the HiSLIP protocol version used for a particular HiSLIP connection. Currently, HiSLIP version 1.0 would return a ViVersion value of 0x00100000."#
+            (Read Only Local) ( ViVersion) [static as N/A in 0h to FFFFFFFFh]
+
+            const VI_ATTR_TCPIP_IS_HISLIP: r#"VI_ATTR_TCPIP_IS_HISLIP specifies whether this resource uses the HiSLIP protocol."#
+            (Read Only Global) ( ViBoolean) [static as N/A in VI_TRUE (1) VI_FALSE (0)]
+
             const VI_ATTR_TCPIP_KEEPALIVE: r#"Setting this attribute to TRUE requests that a TCP/IP provider enable the use of keep-alive packets on TCP connections. After the system detects that a connection was dropped, VISA returns a lost connection error code on subsequent I/O calls on the session. The time required for the system to detect that the connection was dropped is dependent on the system and is not settable."#
             (Read/Write Local) ( ViBoolean) [static as VI_FALSE in VI_TRUE(1) VI_FALSE(0)]
 
@@ -742,6 +892,24 @@ mod attributes {
             const VI_ATTR_TCPIP_PORT: r#"This specifies the port number for a given TCPIP address. For a TCPIP SOCKET Resource, this is a required part of the address string."#
             (Read Only Global) ( ViUInt16) [static as N/A in 0 to FFFFh]
 
+            const VI_ATTR_TCPIP_SASL_MECHANISM: r#"VI_ATTR_TCPIP_SASL_MECHANISM is the SASL mechanism used to authenticate the client on a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
+            const VI_ATTR_TCPIP_SERVER_CERT_EXPIRATION_DATE: r#"VI_ATTR_TCPIP_SERVER_CERT_EXPIRATION_DATE is the expiration date of the certificate the server presented on a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
+            const VI_ATTR_TCPIP_SERVER_CERT_IS_PERPETUAL: r#"VI_ATTR_TCPIP_SERVER_CERT_IS_PERPETUAL specifies whether the certificate the server presented on a secure HiSLIP connection never expires."#
+            (Read Only Local) ( ViBoolean) [static as N/A in VI_TRUE (1) VI_FALSE (0)]
+
+            const VI_ATTR_TCPIP_SERVER_CERT_ISSUER_NAME: r#"VI_ATTR_TCPIP_SERVER_CERT_ISSUER_NAME is the issuer name of the certificate the server presented on a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
+            const VI_ATTR_TCPIP_SERVER_CERT_SUBJECT_NAME: r#"VI_ATTR_TCPIP_SERVER_CERT_SUBJECT_NAME is the subject name of the certificate the server presented on a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
+            const VI_ATTR_TCPIP_TLS_CIPHER_SUITE: r#"VI_ATTR_TCPIP_TLS_CIPHER_SUITE is the name of the TLS cipher suite negotiated for a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
             const VI_ATTR_TERMCHAR: r#"VI_ATTR_TERMCHAR is the termination character. When the termination character is read and VI_ATTR_TERMCHAR_EN is enabled during a read operation, the read operation terminates. For a Serial INSTR session, VI_ATTR_TERMCHAR is Read/Write when the corresponding session is not enabled to receive VI_EVENT_ASRL_TERMCHAR events. When the session is enabled to receive VI_EVENT_ASRL_TERMCHAR events, the attribute VI_ATTR_TERMCHAR is Read Only. For all other session types, the attribute VI_ATTR_TERMCHAR is always Read/Write."#
             (Read/Write Local) ( ViUInt8) [static as 0Ah (linefeed) in 0 to FFh]
 
@@ -915,7 +1083,9 @@ mod attributes {
                 $(
                     #[doc=$desc]
                     #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+                    #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize), serde(transparent))]
                     pub struct $attr_id{
+                        #[cfg_attr(feature = "serde", serde(with = "super::visa_buf_serde"))]
                         value: $crate::VisaBuf
                     }
                     impl $attr_id{
@@ -966,6 +1136,21 @@ mod attributes {
             const VI_ATTR_TCPIP_HOSTNAME: r#"This specifies the host name of the device. If no host name is available, this attribute returns an empty string."#
             (Read Only Global) ( ViString) [static as N/A in N/A]
 
+            const VI_ATTR_TCPIP_SASL_MECHANISM: r#"VI_ATTR_TCPIP_SASL_MECHANISM is the SASL mechanism used to authenticate the client on a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
+            const VI_ATTR_TCPIP_SERVER_CERT_EXPIRATION_DATE: r#"VI_ATTR_TCPIP_SERVER_CERT_EXPIRATION_DATE is the expiration date of the certificate the server presented on a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
+            const VI_ATTR_TCPIP_SERVER_CERT_ISSUER_NAME: r#"VI_ATTR_TCPIP_SERVER_CERT_ISSUER_NAME is the issuer name of the certificate the server presented on a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
+            const VI_ATTR_TCPIP_SERVER_CERT_SUBJECT_NAME: r#"VI_ATTR_TCPIP_SERVER_CERT_SUBJECT_NAME is the subject name of the certificate the server presented on a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
+            const VI_ATTR_TCPIP_TLS_CIPHER_SUITE: r#"VI_ATTR_TCPIP_TLS_CIPHER_SUITE is the name of the TLS cipher suite negotiated for a secure HiSLIP connection."#
+            (Read Only Local) ( ViString) [static as N/A in N/A]
+
             const VI_ATTR_USB_SERIAL_NUM: r#"VI_ATTR_USB_SERIAL_NUM specifies the USB serial number of this device."#
             (Read Only Global) ( ViString) [static as N/A in N/A]
 
@@ -973,3 +1158,113 @@ mod attributes {
             (Read Only Global) ( ViRsrc) [static as N/A in N/A]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{backend::mock::MockResource, enums::status::ErrorCode, Error};
+    use anyhow::Result;
+
+    #[test]
+    fn meta() {
+        let parity = AttrKind::AttrAsrlParity.meta().unwrap();
+        assert!(parity.readable && parity.writable);
+        assert_eq!(parity.scope, Some(AttrScope::Global));
+        assert_eq!(parity.c_type, "ViUInt16");
+        assert_eq!(parity.default, Some(0));
+        assert_eq!(
+            parity.range[4],
+            RangeItem::Value {
+                name: Some("VI_ASRL_PAR_SPACE"),
+                value: 4
+            }
+        );
+        assert!(parity.accepts(4) && !parity.accepts(5));
+        assert!(parity.applicable_resource_classes.is_empty());
+
+        let class = AttrKind::AttrRsrcClass.meta().unwrap();
+        assert!(class.readable && !class.writable);
+
+        let xon = AttrKind::AttrAsrlXonChar.meta().unwrap();
+        assert_eq!(xon.default, Some(0x11));
+        assert!(xon.accepts(0xFF) && !xon.accepts(0x100));
+
+        let prot = AttrKind::AttrIoProt.meta().unwrap();
+        assert_eq!(prot.scope, Some(AttrScope::Local));
+        assert_eq!(prot.default, Some(1));
+        assert!(prot
+            .applicable_resource_classes
+            .contains(&ResourceClass::Gpib));
+        assert!(prot.accepts(5) && !prot.accepts(6));
+
+        let name = AttrKind::AttrRsrcName.meta().unwrap();
+        assert!(!name.writable);
+        assert_eq!((name.c_type, name.default), ("ViRsrc", None));
+
+        let status = AttrKind::AttrStatus.meta().unwrap();
+        assert_eq!(status.scope, None);
+        assert!(status.range.is_empty());
+    }
+
+    #[test]
+    fn enumerated_attributes() -> Result<()> {
+        let (_dev, _rm, instr) = MockResource::new("ASRL9::INSTR").open()?;
+        assert_eq!(
+            IntfType::try_from(AttrIntfType::get_from(&instr)?)?,
+            IntfType::Asrl
+        );
+        instr.set_attr(AsrlParity::Even)?;
+        instr.set_attr(AsrlStopBits::One5)?;
+        let parity = AsrlParity::try_from(AttrAsrlParity::get_from(&instr)?)?;
+        assert!(matches!(parity, AsrlParity::Even));
+        assert_eq!(parity.to_string(), "VI_ASRL_PAR_EVEN");
+        assert_eq!(
+            AttrAsrlStopBits::get_from(&instr)?,
+            AttrAsrlStopBits::VI_ASRL_STOP_ONE5
+        );
+        assert_eq!(
+            AttrAsrlParity::from(AsrlParity::Space).into_inner(),
+            AttrAsrlParity::VI_ASRL_PAR_SPACE.into_inner()
+        );
+        assert_eq!(
+            AsrlStopBits::try_from(unsafe { AttrAsrlStopBits::new_unchecked(11) }),
+            Err(Error(ErrorCode::ErrorNsupAttrState))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bit_mask_attributes() -> Result<()> {
+        let (_dev, _rm, instr) = MockResource::new("ASRL10::INSTR").open()?;
+        instr.set_attr(AsrlFlowCntrl::empty())?;
+        assert_eq!(
+            AttrAsrlFlowCntrl::get_from(&instr)?,
+            AttrAsrlFlowCntrl::VI_ASRL_FLOW_NONE
+        );
+        instr.set_attr(AsrlFlowCntrl::XON_XOFF | AsrlFlowCntrl::RTS_CTS)?;
+        assert_eq!(
+            AsrlFlowCntrl::try_from(AttrAsrlFlowCntrl::get_from(&instr)?)?,
+            AsrlFlowCntrl::XON_XOFF | AsrlFlowCntrl::RTS_CTS
+        );
+        assert_eq!(
+            AttrAsrlFlowCntrl::new_checked(6),
+            Some((AsrlFlowCntrl::RTS_CTS | AsrlFlowCntrl::DTR_DSR).into())
+        );
+        assert_eq!(AttrAsrlFlowCntrl::new_checked(8), None);
+        assert_eq!(
+            AsrlFlowCntrl::try_from(unsafe { AttrAsrlFlowCntrl::new_unchecked(9) }),
+            Err(Error(ErrorCode::ErrorNsupAttrState))
+        );
+        let support =
+            VxiTrigSupport::try_from(unsafe { AttrVxiTrigSupport::new_unchecked(0x301) })?;
+        assert_eq!(
+            support,
+            VxiTrigSupport::TTL0 | VxiTrigSupport::ECL0 | VxiTrigSupport::ECL1
+        );
+        // bits named by the description
+        assert_eq!(VxiTrigStatus::ECL1.bits(), 1 << 9);
+        assert_eq!(VxiVmeIntrStatus::LINE1.bits(), 1);
+        assert_eq!(VxiVmeIntrStatus::all().bits(), 0x7F);
+        Ok(())
+    }
+}
@@ -1,13 +1,24 @@
 //!
 //! [EventKind] defined in VISA Library 7.1 specification,
-//! corresponding attributes defined in [attribute](super::attribute) mod,
+//! corresponding attributes defined in [attribute] mod,
 //! doc from [NI-VISA Product Documentation](https://www.ni.com/docs/en-US/bundle/ni-visa/page/ni-visa/events.html),
 //! with some difference ignored
 //!
 //!
 
+use std::marker::PhantomData;
+
 use visa_sys as vs;
 
+use crate::{
+    backend::{Backend, Visa},
+    enums::{
+        attribute::{self, AttrKind, SpecAttr},
+        status::{CompletionCode, ErrorCode},
+    },
+    wrap_raw_error_in_unsafe, Error, JobID, Result,
+};
+
 pub use event_kind::*;
 
 mod event_kind {
@@ -174,12 +185,13 @@ visa_rs_proc::repr! {
 /// See [`wait_on_event`](crate::Instrument::wait_on_event) and [`Callback`](crate::handler::Callback)
 ///
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct Event {
+pub struct Event<B: Backend = Visa> {
     pub(crate) handler: vs::ViEvent,
     pub(crate) kind: EventKind,
+    pub(crate) _backend: PhantomData<B>,
 }
 
-impl Event {
+impl<B: Backend> Event<B> {
     pub fn kind(&self) -> EventKind {
         self.kind
     }
@@ -187,26 +199,261 @@ impl Event {
         Self {
             handler,
             kind: EventKind::try_from(kind).expect("should be valid event kind"),
+            _backend: PhantomData,
+        }
+    }
+}
+
+impl<B: Backend> Event<B> {
+    /// Reads the attributes carried by this kind of event, see [`EventData`].
+    ///
+    /// Fails with [`ErrorSystemError`](ErrorCode::ErrorSystemError) if `VI_ATTR_STATUS` is a vendor specific status,
+    /// neither a completion nor an error code.
+    pub fn data(&self) -> Result<EventData> {
+        let status = || -> Result<Result<CompletionCode>> {
+            let status = attribute::AttrStatus::get_from(self)?.into_inner();
+            if let Ok(code) = CompletionCode::try_from(status) {
+                return Ok(Ok(code));
+            }
+            let err =
+                ErrorCode::try_from(status).map_err(|_| Error(ErrorCode::ErrorSystemError))?;
+            Ok(Err(Error(err)))
+        };
+        let oper_name = || -> Result<String> {
+            Ok(attribute::AttrOperName::get_from(self)?
+                .into_inner()
+                .to_string())
+        };
+        Ok(match self.kind {
+            EventKind::EventIoCompletion => EventData::IoCompletion {
+                job_id: JobID(attribute::AttrJobId::get_from(self)?.into_inner()),
+                status: status()?,
+                ret_count: attribute::AttrRetCount::get_from(self)?.into_inner() as _,
+                oper_name: oper_name()?,
+            },
+            EventKind::EventTrig => EventData::Trig {
+                trig_id: attribute::AttrRecvTrigId::get_from(self)?.into_inner() as _,
+            },
+            EventKind::EventServiceReq => EventData::ServiceReq,
+            EventKind::EventClear => EventData::Clear,
+            EventKind::EventException => EventData::Exception {
+                status: status()?,
+                oper_name: oper_name()?,
+            },
+            EventKind::EventGpibCic => EventData::GpibCic {
+                gained: attribute::AttrGpibRecvCicState::get_from(self)?.into_inner()
+                    != vs::VI_FALSE as _,
+            },
+            EventKind::EventGpibTalk => EventData::GpibTalk,
+            EventKind::EventGpibListen => EventData::GpibListen,
+            EventKind::EventVxiVmeSysfail => EventData::VxiVmeSysfail,
+            EventKind::EventVxiVmeSysreset => EventData::VxiVmeSysreset,
+            EventKind::EventVxiSigp => EventData::VxiSigp {
+                status_id: attribute::AttrSigpStatusId::get_from(self)?.into_inner() as _,
+            },
+            EventKind::EventVxiVmeIntr => EventData::VxiVmeIntr {
+                status_id: attribute::AttrIntrStatusId::get_from(self)?.into_inner() as _,
+                level: attribute::AttrRecvIntrLevel::get_from(self)?.into_inner() as _,
+            },
+            EventKind::EventPxiIntr => EventData::PxiIntr {
+                seq: attribute::AttrPxiRecvIntrSeq::get_from(self)?.into_inner() as _,
+                data: attribute::AttrPxiRecvIntrData::get_from(self)?.into_inner() as _,
+            },
+            EventKind::EventTcpipConnect => EventData::TcpipConnect,
+            EventKind::EventUsbIntr => {
+                let size = attribute::AttrUsbRecvIntrSize::get_from(self)?.into_inner();
+                // VISA copies the data into the buffer passed, unlike other attributes
+                let mut data = vec![0u8; size as _];
+                wrap_raw_error_in_unsafe!(B::get_attribute(
+                    self.handler,
+                    AttrKind::AttrUsbRecvIntrData as _,
+                    data.as_mut_ptr() as _
+                ))?;
+                EventData::UsbIntr {
+                    status: status()?,
+                    data,
+                }
+            }
+            // not the kind of an occurred event
+            EventKind::AllEnabledEvents => return Err(Error(ErrorCode::ErrorInvEvent)),
+        })
+    }
+}
+
+/// Attributes carried by an [`Event`], decoded by [`Event::data`].
+///
+/// Kinds carrying nothing but `VI_ATTR_EVENT_TYPE` have a unit variant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EventData {
+    /// An asynchronous operation completed, `VI_EVENT_IO_COMPLETION`
+    IoCompletion {
+        /// `VI_ATTR_JOB_ID`, compare with the id returned when starting the operation
+        job_id: JobID,
+        /// `VI_ATTR_STATUS`, result of the operation
+        status: Result<CompletionCode>,
+        /// `VI_ATTR_RET_COUNT`, number of elements transferred
+        ret_count: usize,
+        /// `VI_ATTR_OPER_NAME`, e.g. `viReadAsync`
+        oper_name: String,
+    },
+    /// `VI_EVENT_TRIG`
+    Trig {
+        /// `VI_ATTR_RECV_TRIG_ID`, `VI_TRIG_SW` (-1), `VI_TRIG_TTL0` (0) to `VI_TRIG_TTL7` (7) or `VI_TRIG_ECL0` (8) to `VI_TRIG_ECL1` (9)
+        trig_id: i16,
+    },
+    /// `VI_EVENT_SERVICE_REQ`
+    ServiceReq,
+    /// `VI_EVENT_CLEAR`
+    Clear,
+    /// An operation failed, `VI_EVENT_EXCEPTION`
+    Exception {
+        /// `VI_ATTR_STATUS`, error returned by the operation
+        status: Result<CompletionCode>,
+        /// `VI_ATTR_OPER_NAME`, e.g. `viRead`
+        oper_name: String,
+    },
+    /// `VI_EVENT_GPIB_CIC`
+    GpibCic {
+        /// `VI_ATTR_GPIB_RECV_CIC_STATE`, whether the controller gained or lost CIC status
+        gained: bool,
+    },
+    /// `VI_EVENT_GPIB_TALK`
+    GpibTalk,
+    /// `VI_EVENT_GPIB_LISTEN`
+    GpibListen,
+    /// `VI_EVENT_VXI_VME_SYSFAIL`
+    VxiVmeSysfail,
+    /// `VI_EVENT_VXI_VME_SYSRESET`
+    VxiVmeSysreset,
+    /// `VI_EVENT_VXI_SIGP`
+    VxiSigp {
+        /// `VI_ATTR_SIGP_STATUS_ID`
+        status_id: u16,
+    },
+    /// `VI_EVENT_VXI_VME_INTR`
+    VxiVmeIntr {
+        /// `VI_ATTR_INTR_STATUS_ID`
+        status_id: u32,
+        /// `VI_ATTR_RECV_INTR_LEVEL`, 1 to 7 or `VI_UNKNOWN_LEVEL` (-1)
+        level: i16,
+    },
+    /// `VI_EVENT_PXI_INTR`
+    PxiIntr {
+        /// `VI_ATTR_PXI_RECV_INTR_SEQ`
+        seq: i16,
+        /// `VI_ATTR_PXI_RECV_INTR_DATA`
+        data: u32,
+    },
+    /// `VI_EVENT_TCPIP_CONNECT`
+    TcpipConnect,
+    /// `VI_EVENT_USB_INTR`
+    UsbIntr {
+        /// `VI_ATTR_STATUS`
+        status: Result<CompletionCode>,
+        /// `VI_ATTR_USB_RECV_INTR_DATA`, of `VI_ATTR_USB_RECV_INTR_SIZE` bytes
+        data: Vec<u8>,
+    },
+}
+
+impl EventData {
+    /// Kind of the event the data comes from
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::IoCompletion { .. } => EventKind::EventIoCompletion,
+            Self::Trig { .. } => EventKind::EventTrig,
+            Self::ServiceReq => EventKind::EventServiceReq,
+            Self::Clear => EventKind::EventClear,
+            Self::Exception { .. } => EventKind::EventException,
+            Self::GpibCic { .. } => EventKind::EventGpibCic,
+            Self::GpibTalk => EventKind::EventGpibTalk,
+            Self::GpibListen => EventKind::EventGpibListen,
+            Self::VxiVmeSysfail => EventKind::EventVxiVmeSysfail,
+            Self::VxiVmeSysreset => EventKind::EventVxiVmeSysreset,
+            Self::VxiSigp { .. } => EventKind::EventVxiSigp,
+            Self::VxiVmeIntr { .. } => EventKind::EventVxiVmeIntr,
+            Self::PxiIntr { .. } => EventKind::EventPxiIntr,
+            Self::TcpipConnect => EventKind::EventTcpipConnect,
+            Self::UsbIntr { .. } => EventKind::EventUsbIntr,
         }
     }
 }
 
-impl Drop for Event {
+impl<B: Backend> Drop for Event<B> {
     fn drop(&mut self) {
         unsafe {
-            vs::viClose(self.handler);
+            B::close(self.handler);
         }
     }
 }
 
-impl PartialEq<EventKind> for Event {
+impl<B: Backend> PartialEq<EventKind> for Event<B> {
     fn eq(&self, other: &EventKind) -> bool {
         self.kind.eq(other)
     }
 }
 
-impl crate::session::AsRawSs for Event {
+impl<B: Backend> crate::session::AsRawSs for Event<B> {
+    type Backend = B;
     fn as_raw_ss(&self) -> crate::session::RawSs {
         self.handler
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{backend::mock::MockResource, enums::status::CompletionCode, TIMEOUT_IMMEDIATE};
+    use anyhow::Result;
+
+    #[test]
+    fn event_data() -> Result<()> {
+        let (dev, _rm, instr) = MockResource::new("VXI0::6::INSTR").open()?;
+        let all = EventKind::AllEnabledEvents;
+        for kind in [
+            EventKind::EventIoCompletion,
+            EventKind::EventTrig,
+            EventKind::EventGpibCic,
+            EventKind::EventVxiSigp,
+            EventKind::EventClear,
+        ] {
+            instr.enable_event(kind, Mechanism::Queue)?;
+        }
+        let job = unsafe { instr.visa_write_async(b"*TRG\n")? };
+        assert_eq!(
+            instr.wait_on_event(all, TIMEOUT_IMMEDIATE)?.data()?,
+            EventData::IoCompletion {
+                job_id: job,
+                status: Ok(CompletionCode::Success),
+                ret_count: 5,
+                oper_name: "viWriteAsync".into(),
+            }
+        );
+        dev.raise_event_with(
+            EventKind::EventTrig,
+            [attribute::AttrRecvTrigId::VI_TRIG_TTL3.into()],
+        );
+        dev.raise_event_with(
+            EventKind::EventGpibCic,
+            [attribute::AttrGpibRecvCicState::VI_TRUE.into()],
+        );
+        dev.raise_event_with(
+            EventKind::EventVxiSigp,
+            [unsafe { attribute::AttrSigpStatusId::new_unchecked(0x1234) }.into()],
+        );
+        dev.raise_event(EventKind::EventClear);
+        let data = std::iter::from_fn(|| instr.wait_on_event(all, TIMEOUT_IMMEDIATE).ok())
+            .map(|e| e.data())
+            .collect::<crate::Result<Vec<_>>>()?;
+        assert_eq!(
+            data,
+            [
+                EventData::Trig { trig_id: 3 },
+                EventData::GpibCic { gained: true },
+                EventData::VxiSigp { status_id: 0x1234 },
+                EventData::Clear,
+            ]
+        );
+        assert_eq!(data[3].kind(), EventKind::EventClear);
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+#![allow(overflowing_literals)]
+#![allow(non_upper_case_globals)]
+
+// pub const VI_LOCAL_SPACE: ViUInt32 = 0;
+// pub const VI_A16_SPACE: ViUInt32 = 1;
+// pub const VI_A24_SPACE: ViUInt32 = 2;
+// pub const VI_A32_SPACE: ViUInt32 = 3;
+// pub const VI_A64_SPACE: ViUInt32 = 4;
+// pub const VI_PXI_ALLOC_SPACE: ViUInt32 = 9;
+// pub const VI_PXI_CFG_SPACE: ViUInt32 = 10;
+// pub const VI_PXI_BAR0_SPACE: ViUInt32 = 11;
+// pub const VI_PXI_BAR1_SPACE: ViUInt32 = 12;
+// pub const VI_PXI_BAR2_SPACE: ViUInt32 = 13;
+// pub const VI_PXI_BAR3_SPACE: ViUInt32 = 14;
+// pub const VI_PXI_BAR4_SPACE: ViUInt32 = 15;
+// pub const VI_PXI_BAR5_SPACE: ViUInt32 = 16;
+// pub const VI_OPAQUE_SPACE: ViUInt32 = 65535;
+
+consts_to_enum! {
+    #[format=dbg]
+    #[repr(ViUInt16)]
+    /// Address space of register based operations.
+    ///
+    /// VXI, VME and GPIB-VXI sessions use the A16/A24/A32/A64 spaces, PXI sessions use the configuration and BAR spaces,
+    /// MEMACC sessions may use any space supported by the interface.
+    ///
+    /// See [`in_8`](crate::Instrument::in_8), [`out_8`](crate::Instrument::out_8), [`move_in_8`](crate::Instrument::move_in_8), [`move_out_8`](crate::Instrument::move_out_8) and [`visa_move`](crate::Instrument::visa_move)
+    ///
+    pub enum AddressSpace {
+        VI_LOCAL_SPACE          0       "Address the device local memory, for sessions to servant devices."
+        VI_A16_SPACE            1       "Address the A16 address space of VXI/MXI bus."
+        VI_A24_SPACE            2       "Address the A24 address space of VXI/MXI bus."
+        VI_A32_SPACE            3       "Address the A32 address space of VXI/MXI bus."
+        VI_A64_SPACE            4       "Address the A64 address space of VXI/MXI bus."
+        VI_PXI_ALLOC_SPACE      9       "Address the memory allocated for the PXI device, e.g. by VI_ATTR_PXI_MEM_SIZE_BAR."
+        VI_PXI_CFG_SPACE        10      "Address the PCI configuration space."
+        VI_PXI_BAR0_SPACE       11      "Address the PCI BAR0 space."
+        VI_PXI_BAR1_SPACE       12      "Address the PCI BAR1 space."
+        VI_PXI_BAR2_SPACE       13      "Address the PCI BAR2 space."
+        VI_PXI_BAR3_SPACE       14      "Address the PCI BAR3 space."
+        VI_PXI_BAR4_SPACE       15      "Address the PCI BAR4 space."
+        VI_PXI_BAR5_SPACE       16      "Address the PCI BAR5 space."
+        VI_OPAQUE_SPACE         0xFFFF  "Address space of the memory allocated by viMemAlloc."
+    }
+}
+
+// pub const VI_WIDTH_8: ViUInt32 = 1;
+// pub const VI_WIDTH_16: ViUInt32 = 2;
+// pub const VI_WIDTH_32: ViUInt32 = 4;
+// pub const VI_WIDTH_64: ViUInt32 = 8;
+
+consts_to_enum! {
+    #[format=dbg]
+    #[repr(ViUInt16)]
+    /// Width of each element transferred by register based block moves.
+    ///
+    /// See [`visa_move`](crate::Instrument::visa_move)
+    ///
+    pub enum DataWidth {
+        VI_WIDTH_8              1       "8-bit elements."
+        VI_WIDTH_16             2       "16-bit elements."
+        VI_WIDTH_32             4       "32-bit elements."
+        VI_WIDTH_64             8       "64-bit elements."
+    }
+}
+
+impl DataWidth {
+    /// Size of each element in bytes
+    pub fn bytes(self) -> usize {
+        u16::from(self) as _
+    }
+}
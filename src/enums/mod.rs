@@ -81,4 +81,7 @@ pub mod assert;
 pub mod attribute;
 pub mod event;
 pub mod gpib;
+pub mod memory;
 pub mod status;
+pub mod trigger;
+pub mod vxi;
@@ -124,6 +124,22 @@ mod completion {
     }
 }
 
+impl CompletionCode {
+    /// Whether the code is a `VI_WARN_*` code, the operation succeeded but possibly not as intended
+    pub fn is_warning(self) -> bool {
+        matches!(
+            self,
+            Self::WarnQueueOverflow
+                | Self::WarnConfigNloaded
+                | Self::WarnNullObject
+                | Self::WarnNsupAttrState
+                | Self::WarnUnknownStatus
+                | Self::WarnNsupBuf
+                | Self::WarnExtFuncNimpl
+        )
+    }
+}
+
 impl TryFrom<super::attribute::AttrStatus> for CompletionCode {
     type Error = ErrorCode;
     fn try_from(value: super::attribute::AttrStatus) -> Result<Self, Self::Error> {
@@ -135,3 +151,40 @@ impl TryFrom<super::attribute::AttrStatus> for CompletionCode {
         }
     }
 }
+
+impl ErrorCode {
+    /// Kind of the [`std::io::Error`] this error is converted to by the [`Read`](std::io::Read)/[`Write`](std::io::Write) implementations of [`Instrument`](crate::Instrument).
+    ///
+    /// Every error has a kind, grouped by cause: invalid arguments or setup, missing resources, unsupported features, locks and access rights, busy resources, transfer failures.
+    pub fn io_error_kind(self) -> std::io::ErrorKind {
+        use std::io::ErrorKind::*;
+        use ErrorCode::*;
+        match self {
+            ErrorTmo => TimedOut,
+            ErrorAbort => Interrupted,
+            ErrorAlloc => OutOfMemory,
+            ErrorInvObject => AddrNotAvailable,
+            ErrorRsrcNfound | ErrorLibraryNfound | ErrorIntfNumNconfig | ErrorHndlrNinstalled
+            | ErrorWindowNmapped | ErrorTrigNmapped => NotFound,
+            ErrorNsupOper | ErrorNimplOper | ErrorNsupAttr | ErrorNsupAttrState | ErrorNsupFmt
+            | ErrorNsupMode | ErrorNsupOffset | ErrorNsupVarWidth | ErrorNsupAlignOffset
+            | ErrorNsupWidth | ErrorNsupIntr | ErrorNsupLine | ErrorNsupMech | ErrorInvLockType
+            | ErrorMemNshared => Unsupported,
+            ErrorInvExpr | ErrorInvRsrcName | ErrorInvAccMode | ErrorInvDegree | ErrorInvJobId
+            | ErrorInvEvent | ErrorInvMech | ErrorInvHndlrRef | ErrorInvContext | ErrorInvSetup
+            | ErrorInvMask | ErrorInvFmt | ErrorInvSpace | ErrorInvOffset | ErrorInvWidth
+            | ErrorInvParameter | ErrorInvProt | ErrorInvSize | ErrorInvLength | ErrorInvMode
+            | ErrorInvLine | ErrorUserBuf => InvalidInput,
+            ErrorRsrcLocked | ErrorMachineNavail => ConnectionRefused,
+            ErrorNcic | ErrorNsysCntlr | ErrorAttrReadonly | ErrorInvAccessKey
+            | ErrorSesnNlocked | ErrorNpermission | ErrorFileAccess => PermissionDenied,
+            ErrorRsrcBusy | ErrorInProgress | ErrorRespPending | ErrorIntrPending
+            | ErrorLineInUse | ErrorWindowMapped => ResourceBusy,
+            ErrorRawWrProtViol | ErrorRawRdProtViol => InvalidData,
+            ErrorInpProtViol | ErrorOutpProtViol | ErrorBerr | ErrorConnLost => BrokenPipe,
+            ErrorSystemError | ErrorClosingFailed | ErrorQueueOverflow | ErrorNenabled
+            | ErrorQueueError | ErrorSrqNoccurred | ErrorNlisteners | ErrorAsrlParity
+            | ErrorAsrlFraming | ErrorAsrlOverrun | ErrorIo | ErrorFileIo => Other,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#![allow(overflowing_literals)]
+#![allow(non_upper_case_globals)]
+
+consts_to_enum! {
+    #[format=dbg]
+    #[repr(ViInt16)]
+    /// Trigger lines of VXI and PXI backplanes, and of the front panel of VXI controllers.
+    ///
+    /// VXI has TTL0 to TTL7 and ECL0 to ECL1, PXI has TTL0 to TTL11 (the PXI trigger bus) and the STAR lines.
+    ///
+    /// See [`map_trigger`](crate::Instrument::map_trigger)
+    ///
+    pub enum TrigLine {
+        VI_TRIG_TTL0            0
+        VI_TRIG_TTL1            1
+        VI_TRIG_TTL2            2
+        VI_TRIG_TTL3            3
+        VI_TRIG_TTL4            4
+        VI_TRIG_TTL5            5
+        VI_TRIG_TTL6            6
+        VI_TRIG_TTL7            7
+        VI_TRIG_ECL0            8
+        VI_TRIG_ECL1            9
+        VI_TRIG_ECL2            10
+        VI_TRIG_ECL3            11
+        VI_TRIG_ECL4            12
+        VI_TRIG_ECL5            13
+        VI_TRIG_STAR_SLOT1      14
+        VI_TRIG_STAR_SLOT2      15
+        VI_TRIG_STAR_SLOT3      16
+        VI_TRIG_STAR_SLOT4      17
+        VI_TRIG_STAR_SLOT5      18
+        VI_TRIG_STAR_SLOT6      19
+        VI_TRIG_STAR_SLOT7      20
+        VI_TRIG_STAR_SLOT8      21
+        VI_TRIG_STAR_SLOT9      22
+        VI_TRIG_STAR_SLOT10     23
+        VI_TRIG_STAR_SLOT11     24
+        VI_TRIG_STAR_SLOT12     25
+        VI_TRIG_STAR_INSTR      26  "The STAR line of the slot the instrument is in."
+        VI_TRIG_PANEL_IN        27  "Front panel trigger input of the controller."
+        VI_TRIG_PANEL_OUT       28  "Front panel trigger output of the controller."
+        VI_TRIG_STAR_VXI0       29
+        VI_TRIG_STAR_VXI1       30
+        VI_TRIG_STAR_VXI2       31
+        VI_TRIG_TTL8            32
+        VI_TRIG_TTL9            33
+        VI_TRIG_TTL10           34
+        VI_TRIG_TTL11           35
+    }
+}
+
+consts_to_enum! {
+    #[format=dbg]
+    #[repr(ViUInt16)]
+    /// How to map a trigger line, VISA defines no other mode than the default one.
+    ///
+    /// See [`map_trigger`](crate::Instrument::map_trigger)
+    ///
+    pub enum TrigMapMode {
+        VI_NULL     0   "Route the source line to the destination line."
+    }
+}
@@ -0,0 +1,28 @@
+#![allow(overflowing_literals)]
+#![allow(non_upper_case_globals)]
+
+// pub const VI_VXI_CMD16: ViUInt32 = 512;
+// pub const VI_VXI_CMD16_RESP16: ViUInt32 = 514;
+// pub const VI_VXI_RESP16: ViUInt32 = 2;
+// pub const VI_VXI_CMD32: ViUInt32 = 1024;
+// pub const VI_VXI_CMD32_RESP16: ViUInt32 = 1026;
+// pub const VI_VXI_CMD32_RESP32: ViUInt32 = 1028;
+// pub const VI_VXI_RESP32: ViUInt32 = 4;
+
+consts_to_enum! {
+    #[format=dbg]
+    #[repr(ViUInt16)]
+    /// Size of the Word Serial command sent and of the response read.
+    ///
+    /// See [`vxi_command_query`](crate::Instrument::vxi_command_query)
+    ///
+    pub enum VxiCmdMode {
+        VI_VXI_CMD16            512     "Send a 16-bit command, no response is read."
+        VI_VXI_CMD16_RESP16     514     "Send a 16-bit command and read a 16-bit response."
+        VI_VXI_RESP16           2       "Read a 16-bit response to a previous command, no command is sent."
+        VI_VXI_CMD32            1024    "Send a 32-bit command, no response is read."
+        VI_VXI_CMD32_RESP16     1026    "Send a 32-bit command and read a 16-bit response."
+        VI_VXI_CMD32_RESP32     1028    "Send a 32-bit command and read a 32-bit response."
+        VI_VXI_RESP32           4       "Read a 32-bit response to a previous command, no command is sent."
+    }
+}
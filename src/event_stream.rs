@@ -0,0 +1,233 @@
+use super::*;
+use enums::status::ErrorCode;
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    ffi::c_void,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Default)]
+struct Shared {
+    queue: VecDeque<Result<event::EventData>>,
+    waker: Option<Waker>,
+}
+
+type SharedState = Mutex<Shared>;
+
+fn user_data(state: &Arc<SharedState>) -> vs::ViAddr {
+    Arc::as_ptr(state) as _
+}
+
+/// Events of one kind delivered by VISA handlers, returned by [`Instrument::event_stream`].
+///
+/// VISA closes the event context as soon as the handler returns, so the stream yields the [`EventData`](event::EventData) decoded in the handler rather than the [`Event`](event::Event) itself.
+/// Dropping the stream uninstalls its handler, the event stays enabled while other streams or hooks of the session rely on it.
+pub struct EventStream<'a, B: Backend = Visa> {
+    instr: &'a Instrument<B>,
+    kind: event::EventKind,
+    state: Arc<SharedState>,
+}
+
+impl<B: Backend> std::fmt::Debug for EventStream<'_, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("instr", &self.instr)
+            .field("kind", &self.kind)
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe extern "system" fn trampoline<B: Backend>(
+    _instr: vs::ViSession,
+    event_type: vs::ViEventType,
+    event: vs::ViEvent,
+    user_data: *mut c_void,
+) -> vs::ViStatus {
+    let state = &*(user_data as *const SharedState);
+    // called from a thread of the VISA library, a panic must not unwind across it
+    let _ = std::panic::catch_unwind(|| {
+        let data = match event::EventKind::try_from(event_type) {
+            Ok(_) => {
+                let event = event::Event::<B>::new(event, event_type);
+                let data = event.data();
+                // closed by VISA when the handler returns
+                std::mem::forget(event);
+                data
+            }
+            Err(_) => Err(Error(ErrorCode::ErrorInvEvent)),
+        };
+        let waker = {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            state.queue.push_back(data);
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    });
+    SUCCESS
+}
+
+impl<B: Backend> EventStream<'_, B> {
+    /// Kind of the events yielded
+    pub fn kind(&self) -> event::EventKind {
+        self.kind
+    }
+}
+
+impl<B: Backend> Stream for EventStream<'_, B> {
+    type Item = Result<event::EventData>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.queue.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<B: Backend> Drop for EventStream<'_, B> {
+    fn drop(&mut self) {
+        if let Err(e) = handler::release_handler_enable(self.instr, self.kind) {
+            log::warn!("error disabling {:?} of event stream: {}", self.kind, e)
+        }
+        if let Err(e) = wrap_raw_error_in_unsafe!(B::uninstall_handler(
+            self.instr.as_raw_ss(),
+            self.kind as _,
+            Some(trampoline::<B>),
+            user_data(&self.state)
+        )) {
+            log::warn!("error uninstalling handler of event stream: {}", e)
+        }
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Streams occurrences of `event_kind`, e.g. [`EventServiceReq`](event::EventKind::EventServiceReq), to await them alongside other futures.
+    ///
+    /// A handler is installed and the event enabled for [`Mechanism::Handler`](event::Mechanism::Handler).
+    /// The handler is uninstalled when the stream is dropped, and the event disabled with the last stream or [exception hook](Self::install_exception_hook) relying on it,
+    /// unless it was enabled beforehand.
+    /// Since a session can't enable an event for both the queue and the handler mechanisms, don't [`wait_on_event`](Self::wait_on_event) for the same kind meanwhile.
+    ///
+    /// ```no_run
+    /// # async fn f(instr: &visa_rs::Instrument) -> visa_rs::Result<()> {
+    /// use futures::StreamExt;
+    /// use visa_rs::enums::event::EventKind;
+    ///
+    /// let mut srq = instr.event_stream(EventKind::EventServiceReq)?;
+    /// while let Some(data) = srq.next().await {
+    ///     data?;
+    ///     let stb = instr.read_stb()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn event_stream(&self, event_kind: event::EventKind) -> Result<EventStream<'_, B>> {
+        let state = Arc::default();
+        wrap_raw_error_in_unsafe!(B::install_handler(
+            self.as_raw_ss(),
+            event_kind as _,
+            Some(trampoline::<B>),
+            user_data(&state)
+        ))?;
+        if let Err(e) = handler::acquire_handler_enable(self, event_kind) {
+            unsafe {
+                B::uninstall_handler(
+                    self.as_raw_ss(),
+                    event_kind as _,
+                    Some(trampoline::<B>),
+                    user_data(&state),
+                );
+            }
+            return Err(e);
+        }
+        Ok(EventStream {
+            instr: self,
+            kind: event_kind,
+            state,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::MockResource,
+        enums::{
+            event::{self},
+            status::{CompletionCode, ErrorCode},
+        },
+        Error,
+    };
+
+    use anyhow::Result;
+
+    #[test]
+    fn event_stream() -> Result<()> {
+        use futures::{FutureExt, StreamExt};
+        let (dev, _rm, instr) = MockResource::new("PXI0::3-5.0::INSTR").open()?;
+        let kind = event::EventKind::EventServiceReq;
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        {
+            let mut srq = instr.event_stream(kind)?;
+            dev.raise_event(kind);
+            dev.raise_event(event::EventKind::EventTrig);
+            std::thread::scope(|s| -> Result<()> {
+                s.spawn(|| {
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    dev.raise_event(kind);
+                });
+                runtime.block_on(async {
+                    let srq_data = Some(event::EventData::ServiceReq);
+                    assert_eq!(srq.next().await.transpose()?, srq_data);
+                    assert_eq!(srq.next().await.transpose()?, srq_data);
+                    assert!(srq.next().now_or_never().is_none());
+                    Ok::<_, Error>(())
+                })?;
+                Ok(())
+            })?;
+        }
+        assert_eq!(
+            instr
+                .disable_event_outcome(kind, event::Mechanism::Handler)?
+                .code,
+            CompletionCode::SuccessEventDis
+        );
+        assert_eq!(
+            instr.enable_event(kind, event::Mechanism::Handler),
+            Err(Error(ErrorCode::ErrorHndlrNinstalled))
+        );
+        Ok(())
+    }
+    #[test]
+    fn event_streams_of_same_kind() -> Result<()> {
+        use futures::{FutureExt, StreamExt};
+        let (dev, _rm, instr) = MockResource::new("PXI0::3-6.0::INSTR").open()?;
+        let kind = event::EventKind::EventServiceReq;
+        let mut a = instr.event_stream(kind)?;
+        let b = instr.event_stream(kind)?;
+        drop(b);
+        // the event stays enabled for the remaining stream
+        dev.raise_event(kind);
+        assert_eq!(
+            a.next().now_or_never().flatten().transpose()?,
+            Some(event::EventData::ServiceReq)
+        );
+        drop(a);
+        assert_eq!(
+            instr
+                .disable_event_outcome(kind, event::Mechanism::Handler)?
+                .code,
+            CompletionCode::SuccessEventDis
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+use super::*;
+use handler::HandlerAction;
+use std::{cell::RefCell, ffi::c_void, sync::Mutex};
+
+/// A failing operation reported to the hook installed by [`Instrument::install_exception_hook`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExceptionInfo {
+    /// `VI_ATTR_STATUS`, error returned by the operation
+    pub error: Error,
+    /// `VI_ATTR_OPER_NAME`, e.g. `viRead`
+    pub operation: String,
+}
+
+type HookFn = Mutex<Box<dyn FnMut(&ExceptionInfo) -> HandlerAction + Send>>;
+
+/// Hook receiving every `VI_EVENT_EXCEPTION` of a session, returned by [`Instrument::install_exception_hook`].
+///
+/// Dropping it uninstalls its handler, the exception event stays enabled while other hooks of the session rely on it.
+pub struct ExceptionHook<'a, B: Backend = Visa> {
+    instr: &'a Instrument<B>,
+    hook: Box<HookFn>,
+}
+
+impl<B: Backend> std::fmt::Debug for ExceptionHook<'_, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExceptionHook")
+            .field("instr", &self.instr)
+            .finish_non_exhaustive()
+    }
+}
+
+fn user_data(hook: &HookFn) -> vs::ViAddr {
+    hook as *const HookFn as _
+}
+
+thread_local! {
+    /// Hooks running on this thread, an operation failing in a hook would lock it again
+    static RUNNING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks a hook as running on this thread until dropped
+struct Running(usize);
+
+impl Running {
+    /// `None` if the hook is already running on this thread
+    fn enter(hook: &HookFn) -> Option<Self> {
+        let id = user_data(hook) as usize;
+        RUNNING.with(|r| {
+            let mut r = r.borrow_mut();
+            (!r.contains(&id)).then(|| {
+                r.push(id);
+                Self(id)
+            })
+        })
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.with(|r| r.borrow_mut().retain(|&id| id != self.0));
+    }
+}
+
+unsafe extern "system" fn trampoline<B: Backend>(
+    _instr: vs::ViSession,
+    event_type: vs::ViEventType,
+    event: vs::ViEvent,
+    user_data: *mut c_void,
+) -> vs::ViStatus {
+    let hook = &*(user_data as *const HookFn);
+    // called from a thread of the VISA library, a panic must not unwind across it
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        // closed by VISA when the handler returns
+        let event = std::mem::ManuallyDrop::new(event::Event::<B>::new(event, event_type));
+        match event.data() {
+            Ok(event::EventData::Exception {
+                status: Err(error),
+                oper_name,
+            }) => {
+                let Some(_running) = Running::enter(hook) else {
+                    log::warn!("{oper_name} failed in the exception hook: {error}");
+                    return HandlerAction::Continue;
+                };
+                let mut hook = hook.lock().unwrap_or_else(|e| e.into_inner());
+                hook(&ExceptionInfo {
+                    error,
+                    operation: oper_name,
+                })
+            }
+            Ok(data) => {
+                log::warn!("unexpected event in exception hook: {:?}", data);
+                HandlerAction::Continue
+            }
+            Err(e) => {
+                log::warn!("error reading exception event: {}", e);
+                HandlerAction::Continue
+            }
+        }
+    }))
+    .unwrap_or_else(|_| {
+        log::error!("exception hook panicked");
+        HandlerAction::Continue
+    })
+    .status()
+}
+
+impl<B: Backend> Drop for ExceptionHook<'_, B> {
+    fn drop(&mut self) {
+        let kind = event::EventKind::EventException;
+        if let Err(e) = handler::release_handler_enable(self.instr, kind) {
+            log::warn!("error disabling exception event: {}", e)
+        }
+        if let Err(e) = wrap_raw_error_in_unsafe!(B::uninstall_handler(
+            self.instr.as_raw_ss(),
+            kind as _,
+            Some(trampoline::<B>),
+            user_data(&self.hook)
+        )) {
+            log::warn!("error uninstalling exception hook: {}", e)
+        }
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Calls `hook` with every operation failing on this session, until the returned [`ExceptionHook`] is dropped.
+    ///
+    /// The hook runs before the failing operation returns its error, possibly on a thread of the VISA library.
+    /// It returns [`HandlerAction::StopChain`] to keep the other exception handlers installed on the session from being invoked.
+    /// Operations failing within the hook itself are not reported to it again, but logged.
+    ///
+    /// The hook is `'static` as VISA keeps calling it if the [`ExceptionHook`] is leaked, share state with it through an [`Arc`](std::sync::Arc).
+    ///
+    /// ```no_run
+    /// # fn f(instr: &visa_rs::Instrument) -> visa_rs::Result<()> {
+    /// use visa_rs::handler::HandlerAction;
+    ///
+    /// let _hook = instr.install_exception_hook(|e| {
+    ///     eprintln!("{} failed: {}", e.operation, e.error);
+    ///     HandlerAction::Continue
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn install_exception_hook<'a>(
+        &'a self,
+        hook: impl FnMut(&ExceptionInfo) -> HandlerAction + Send + 'static,
+    ) -> Result<ExceptionHook<'a, B>> {
+        let kind = event::EventKind::EventException;
+        let hook: Box<HookFn> = Box::new(Mutex::new(Box::new(hook)));
+        wrap_raw_error_in_unsafe!(B::install_handler(
+            self.as_raw_ss(),
+            kind as _,
+            Some(trampoline::<B>),
+            user_data(&hook)
+        ))?;
+        if let Err(e) = handler::acquire_handler_enable(self, kind) {
+            unsafe {
+                B::uninstall_handler(
+                    self.as_raw_ss(),
+                    kind as _,
+                    Some(trampoline::<B>),
+                    user_data(&hook),
+                );
+            }
+            return Err(e);
+        }
+        Ok(ExceptionHook { instr: self, hook })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::MockResource,
+        enums::{
+            event::{self},
+            status::ErrorCode,
+        },
+        Error, TIMEOUT_IMMEDIATE,
+    };
+    use anyhow::Result;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn exception_hook() -> Result<()> {
+        use crate::{handler::HandlerAction, ExceptionInfo};
+        use std::sync::{Arc, Mutex};
+        let (dev, rm, instr) = MockResource::new("GPIB0::21::INSTR").open()?;
+        let other = dev.open(&rm)?;
+        assert_eq!(
+            instr.enable_event(event::EventKind::EventException, event::Mechanism::Queue),
+            Err(Error(ErrorCode::ErrorInvMech))
+        );
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let first_only = Arc::new(Mutex::new(0));
+        {
+            let logged = logged.clone();
+            let _log = instr.install_exception_hook(move |e: &ExceptionInfo| {
+                logged.lock().unwrap().push(e.clone());
+                HandlerAction::Continue
+            })?;
+            // installed last, invoked first
+            let first_only = first_only.clone();
+            let _filter = instr.install_exception_hook(move |e: &ExceptionInfo| {
+                *first_only.lock().unwrap() += 1;
+                if e.error == Error(ErrorCode::ErrorRsrcLocked) {
+                    HandlerAction::StopChain
+                } else {
+                    HandlerAction::Continue
+                }
+            })?;
+            let mut buf = [0u8; 4];
+            assert!((&instr).read(&mut buf).is_err());
+            other.lock_exclusive(TIMEOUT_IMMEDIATE)?;
+            assert!((&instr).write(b"*RST\n").is_err());
+            assert_eq!(
+                instr.lock_exclusive(TIMEOUT_IMMEDIATE),
+                Err(Error(ErrorCode::ErrorTmo))
+            );
+            other.unlock()?;
+            (&instr).write_all(b"*RST\n")?;
+        }
+        assert_eq!(
+            *logged.lock().unwrap(),
+            [
+                ExceptionInfo {
+                    error: Error(ErrorCode::ErrorTmo),
+                    operation: "viRead".into()
+                },
+                ExceptionInfo {
+                    error: Error(ErrorCode::ErrorTmo),
+                    operation: "viLock".into()
+                },
+            ]
+        );
+        assert_eq!(*first_only.lock().unwrap(), 3);
+        let mut buf = [0u8; 4];
+        assert!((&instr).read(&mut buf).is_err());
+        assert_eq!(logged.lock().unwrap().len(), 2);
+        Ok(())
+    }
+    #[test]
+    fn exception_hooks_of_same_session() -> Result<()> {
+        use crate::handler::HandlerAction;
+        use std::sync::{Arc, Mutex};
+        let (_dev, _rm, instr) = MockResource::new("GPIB0::22::INSTR").open()?;
+        let instr = Arc::new(instr);
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let (inner, c) = (instr.clone(), calls.clone());
+        let reentrant = instr.install_exception_hook(move |e| {
+            c.lock().unwrap().push(e.operation.clone());
+            // not reported to the hook running
+            assert!((&*inner).read(&mut [0u8; 4]).is_err());
+            HandlerAction::Continue
+        })?;
+        let c = calls.clone();
+        let other = instr.install_exception_hook(move |e| {
+            c.lock().unwrap().push(format!("other {}", e.operation));
+            HandlerAction::Continue
+        })?;
+        drop(other);
+        // the exception event stays enabled for the remaining hook
+        assert!((&*instr).read(&mut [0u8; 4]).is_err());
+        assert_eq!(*calls.lock().unwrap(), ["viRead"]);
+        drop(reentrant);
+        assert_eq!(
+            instr
+                .disable_event_outcome(event::EventKind::EventException, event::Mechanism::Handler)?
+                .code,
+            crate::enums::status::CompletionCode::SuccessEventDis
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,431 @@
+//!
+//! Formatted I/O, the Rust counterpart of viPrintf/viScanf/viQueryf.
+//!
+//! [`Instrument::printf`] formats with [`format_args!`] into the formatted I/O write buffer,
+//! [`Instrument::scanf`] parses a response read from the formatted I/O read buffer into any type implementing [`Scan`],
+//! and [`Instrument::queryf`] does both. Both buffers are sized by [`Instrument::set_buf`].
+//!
+//! | VISA | Rust |
+//! |------|------|
+//! | `%d`, `%f`, `%s` | integers, floats, [`String`] |
+//! | `%,d`, `%,f` | [`Vec<T>`] |
+//! | `%b` | [`Block`] |
+//! | `%d,%f` | tuples, e.g. `(i32, f64)` |
+//!
+//! ```no_run
+//! # fn main() -> visa_rs::Result<()> {
+//! use visa_rs::{formatted::Block, prelude::*};
+//!
+//! let rm = DefaultRM::new()?;
+//! let instr = rm.open(&rm.find_res(&std::ffi::CString::new("?*INSTR").unwrap().into())?, AccessMode::NO_LOCK, TIMEOUT_IMMEDIATE)?;
+//! instr.printf(format_args!("SOUR:VOLT {}\n", 1.5))?;
+//! let (volt, curr): (f64, f64) = instr.queryf(format_args!("MEAS:VOLT?;CURR?\n"))?;
+//! let trace: Vec<f32> = instr.queryf(format_args!("TRAC:DATA?\n"))?;
+//! let Block(raw) = instr.queryf(format_args!("CURV?\n"))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    backend::{Backend, Visa},
+    block::BlockReader,
+    enums::{
+        attribute::{self, SpecAttr},
+        status::{CompletionCode, ErrorCode},
+    },
+    flags,
+    query::invalid_response,
+    vs, wrap_raw_error_in_unsafe, AsRawSs, Error, Instrument, Result,
+};
+
+/// Read chunk size if `VI_ATTR_RD_BUF_SIZE` is not supported
+const DEFAULT_BUF_SIZE: usize = 4096;
+
+impl<B: Backend> Instrument<B> {
+    /// Formats `args` into the formatted I/O write buffer, like viPrintf().
+    ///
+    /// The buffer is sent when full, without END, and flushed with END (if `VI_ATTR_SEND_END_EN` is set)
+    /// when the formatted text ends with the termination character `VI_ATTR_TERMCHAR`,
+    /// or when `VI_ATTR_WR_BUF_OPER_MODE` is `VI_FLUSH_ON_ACCESS`.
+    /// Otherwise the text stays buffered until a later call or [`visa_flush`](Self::visa_flush) with [`WRITE_BUF`](flags::FlushMode::WRITE_BUF).
+    ///
+    /// ```no_run
+    /// # fn f(instr: &visa_rs::Instrument) -> visa_rs::Result<()> {
+    /// instr.printf(format_args!("SOUR:FREQ {}\n", 1e6))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn printf(&self, args: fmt::Arguments<'_>) -> Result<()> {
+        let text = args.to_string();
+        if !text.is_empty() {
+            self.buf_write(text.as_bytes())?;
+        }
+        let on_access = attribute::AttrWrBufOperMode::get_from(self)
+            .is_ok_and(|m| m.into_inner() as u32 == vs::VI_FLUSH_ON_ACCESS as u32);
+        if on_access || text.as_bytes().last() == Some(&self.termchar()) {
+            self.visa_flush(flags::FlushMode::WRITE_BUF)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a response from the formatted I/O read buffer and parses it as `T`, like viScanf().
+    ///
+    /// The response ends with END, or with the termination character if `VI_ATTR_TERMCHAR_EN` is set,
+    /// the part of it left unparsed by `T` is discarded, also when it doesn't match `T`.
+    ///
+    /// Returns [`ErrorInvFmt`](ErrorCode::ErrorInvFmt) if the response doesn't match `T`.
+    pub fn scanf<T: Scan>(&self) -> Result<T> {
+        let mut scanner = Scanner::new(self)?;
+        let ret = T::scan(&mut scanner);
+        // keep the next response in sync unless the read itself failed
+        if matches!(ret, Ok(_) | Err(Error(ErrorCode::ErrorInvFmt))) {
+            scanner.finish()?;
+        }
+        ret
+    }
+
+    /// Sends `args` flushing the write buffer, then reads and parses the response, like viQueryf().
+    pub fn queryf<T: Scan>(&self, args: fmt::Arguments<'_>) -> Result<T> {
+        self.printf(args)?;
+        self.visa_flush(flags::FlushMode::WRITE_BUF)?;
+        self.scanf()
+    }
+}
+
+/// Reads a response through the formatted I/O read buffer and splits it in fields, used by [`Scan`] implementations.
+///
+/// Text fields are separated by `,` or `;` and end with the response, binary data is read over the termination character up to END.
+#[derive(Debug)]
+pub struct Scanner<'a, B: Backend = Visa> {
+    instr: &'a Instrument<B>,
+    buf: Vec<u8>,
+    pos: usize,
+    chunk: usize,
+    termchar: u8,
+    /// the last read stopped at the termination character
+    term: bool,
+    /// the last read stopped at END
+    end: bool,
+}
+
+impl<'a, B: Backend> Scanner<'a, B> {
+    /// A scanner reading chunks of `VI_ATTR_RD_BUF_SIZE` bytes from `instr`
+    pub fn new(instr: &'a Instrument<B>) -> Result<Self> {
+        let chunk = attribute::AttrRdBufSize::get_from(instr)
+            .map(|s| s.into_inner() as usize)
+            .unwrap_or(DEFAULT_BUF_SIZE)
+            .max(1);
+        Ok(Self {
+            instr,
+            buf: Vec::new(),
+            pos: 0,
+            chunk,
+            termchar: instr.termchar(),
+            term: false,
+            end: false,
+        })
+    }
+
+    /// Reads one more chunk, returns `false` if END was already received
+    fn fill(&mut self) -> Result<bool> {
+        if self.end {
+            return Ok(false);
+        }
+        let len = self.buf.len();
+        self.buf.resize(len + self.chunk, 0);
+        let mut ret_cnt: vs::ViUInt32 = 0;
+        let status = wrap_raw_error_in_unsafe!(B::buf_read(
+            self.instr.as_raw_ss(),
+            self.buf[len..].as_mut_ptr(),
+            self.chunk as _,
+            &mut ret_cnt as _
+        ));
+        self.buf
+            .truncate(len + if status.is_ok() { ret_cnt as usize } else { 0 });
+        match status? {
+            CompletionCode::SuccessMaxCnt if ret_cnt > 0 => self.term = false,
+            CompletionCode::SuccessTermChar => self.term = true,
+            _ => self.end = true,
+        }
+        Ok(true)
+    }
+
+    /// The text response is complete
+    fn complete(&self) -> bool {
+        self.end || self.term
+    }
+
+    /// Next byte of the text response, without consuming it
+    pub fn peek(&mut self) -> Result<Option<u8>> {
+        while self.pos == self.buf.len() {
+            if self.complete() || !self.fill()? {
+                return Ok(None);
+            }
+        }
+        Ok(Some(self.buf[self.pos]))
+    }
+
+    fn skip_whitespace(&mut self) -> Result<()> {
+        while self.peek()?.is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    /// Whether the text response has no field left
+    pub fn at_end(&mut self) -> Result<bool> {
+        self.skip_whitespace()?;
+        Ok(match self.peek()? {
+            None => true,
+            Some(c) => c == self.termchar && self.complete() && self.pos + 1 == self.buf.len(),
+        })
+    }
+
+    /// Next text field without its separator, trailing termination character and surrounding whitespace.
+    ///
+    /// A field quoted with `"` may contain separators, with `""` standing for a quote.
+    pub fn field(&mut self) -> Result<String> {
+        self.skip_whitespace()?;
+        let mut field = Vec::new();
+        if self.peek()? == Some(b'"') {
+            self.pos += 1;
+            loop {
+                match self.peek()? {
+                    Some(b'"') => {
+                        self.pos += 1;
+                        if self.peek()? != Some(b'"') {
+                            break;
+                        }
+                    }
+                    None => return Err(invalid_response()),
+                    _ => {}
+                }
+                field.push(self.buf[self.pos]);
+                self.pos += 1;
+            }
+            self.skip_whitespace()?;
+        }
+        while let Some(c) = self.peek()? {
+            self.pos += 1;
+            if c == b',' || c == b';' {
+                break;
+            }
+            if c == self.termchar && self.complete() && self.pos == self.buf.len() {
+                break;
+            }
+            field.push(c);
+        }
+        String::from_utf8(field)
+            .map(|s| s.trim().to_owned())
+            .map_err(|_| invalid_response())
+    }
+
+    /// Next `len` bytes, read over the termination character
+    pub fn bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        while self.buf.len() - self.pos < len {
+            if !self.fill()? {
+                return Err(invalid_response());
+            }
+        }
+        let ret = self.buf[self.pos..self.pos + len].to_vec();
+        self.pos += len;
+        if self.pos == self.buf.len() {
+            // a termination character read as data doesn't end the response
+            self.term = false;
+        }
+        Ok(ret)
+    }
+
+    /// Up to `max` bytes, read over the termination character, and how the response stopped after them,
+    /// as the completion code of a read: [`SuccessMaxCnt`](CompletionCode::SuccessMaxCnt) if there is more to read
+    fn chunk(&mut self, max: usize) -> Result<(Vec<u8>, CompletionCode)> {
+        while self.pos == self.buf.len() && self.fill()? {}
+        let len = max.min(self.buf.len() - self.pos);
+        let ret = self.buf[self.pos..self.pos + len].to_vec();
+        self.pos += len;
+        let code = if self.pos < self.buf.len() {
+            CompletionCode::SuccessMaxCnt
+        } else if self.end {
+            CompletionCode::Success
+        } else if self.term {
+            CompletionCode::SuccessTermChar
+        } else {
+            CompletionCode::SuccessMaxCnt
+        };
+        Ok((ret, code))
+    }
+
+    /// All bytes up to END, read over the termination character
+    pub fn rest(&mut self) -> Result<Vec<u8>> {
+        while self.fill()? {}
+        let ret = self.buf[self.pos..].to_vec();
+        self.pos = self.buf.len();
+        Ok(ret)
+    }
+
+    /// Discards the rest of the text response
+    pub fn finish(mut self) -> Result<()> {
+        while !self.complete() {
+            self.fill()?;
+        }
+        Ok(())
+    }
+}
+
+/// Types parsed by [`Instrument::scanf`]
+pub trait Scan: Sized {
+    /// Parses a value from the fields of `scanner`
+    fn scan<B: Backend>(scanner: &mut Scanner<'_, B>) -> Result<Self>;
+}
+
+/// Parses an IEEE 488.2 non-decimal numeric (`#H`, `#Q` or `#B` prefixed) or a decimal integer
+fn parse_int<T: FromStr + TryFrom<i128>>(field: &str) -> Result<T> {
+    let radix = match field.get(..2).map(str::to_ascii_uppercase).as_deref() {
+        Some("#H") => 16,
+        Some("#Q") => 8,
+        Some("#B") => 2,
+        _ => return field.parse().map_err(|_| invalid_response()),
+    };
+    i128::from_str_radix(&field[2..], radix)
+        .ok()
+        .and_then(|v| T::try_from(v).ok())
+        .ok_or_else(invalid_response)
+}
+
+macro_rules! impl_scan {
+    (int $($t:ty)*) => {
+        $(
+            impl Scan for $t {
+                fn scan<B: Backend>(scanner: &mut Scanner<'_, B>) -> Result<Self> {
+                    parse_int(&scanner.field()?)
+                }
+            }
+        )*
+    };
+    (float $($t:ty)*) => {
+        $(
+            impl Scan for $t {
+                fn scan<B: Backend>(scanner: &mut Scanner<'_, B>) -> Result<Self> {
+                    scanner.field()?.parse().map_err(|_| invalid_response())
+                }
+            }
+        )*
+    };
+    (tuple $(($($n:ident)*))*) => {
+        $(
+            impl<$($n: Scan),*> Scan for ($($n,)*) {
+                fn scan<B: Backend>(scanner: &mut Scanner<'_, B>) -> Result<Self> {
+                    Ok(($($n::scan(scanner)?,)*))
+                }
+            }
+        )*
+    };
+}
+
+impl_scan!(int i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+impl_scan!(float f32 f64);
+impl_scan!(tuple (T1 T2) (T1 T2 T3) (T1 T2 T3 T4));
+
+impl Scan for bool {
+    /// `1`/`0` or `ON`/`OFF`
+    fn scan<B: Backend>(scanner: &mut Scanner<'_, B>) -> Result<Self> {
+        match scanner.field()?.to_ascii_uppercase().as_str() {
+            "1" | "ON" => Ok(true),
+            "0" | "OFF" => Ok(false),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+impl Scan for String {
+    /// One field, unquoted
+    fn scan<B: Backend>(scanner: &mut Scanner<'_, B>) -> Result<Self> {
+        scanner.field()
+    }
+}
+
+impl<T: Scan> Scan for Vec<T> {
+    /// Every field left in the response
+    fn scan<B: Backend>(scanner: &mut Scanner<'_, B>) -> Result<Self> {
+        let mut ret = Vec::new();
+        while !scanner.at_end()? {
+            ret.push(T::scan(scanner)?);
+        }
+        Ok(ret)
+    }
+}
+
+/// Bytes of an IEEE 488.2 arbitrary block, definite (`#<n><len><data>`) or indefinite (`#0<data>` up to END or the termination character),
+/// parsed as by [`Instrument::read_binary_block`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Block(pub Vec<u8>);
+
+impl Scan for Block {
+    fn scan<B: Backend>(scanner: &mut Scanner<'_, B>) -> Result<Self> {
+        scanner.skip_whitespace()?;
+        // fields may follow a definite length block, so its trailer is left to the scanner
+        let mut reader = BlockReader::new(scanner.instr).without_trailer();
+        while let Some(want) = reader.want() {
+            let (bytes, code) = scanner.chunk(want)?;
+            reader.feed(&bytes, code)?;
+        }
+        if !reader.is_indefinite() && scanner.pos == scanner.buf.len() {
+            // a termination character read as data doesn't end the response
+            scanner.term = false;
+        }
+        Ok(Block(reader.data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::MockResource,
+        enums::{
+            attribute::{self, HasAttribute},
+            status::ErrorCode,
+        },
+        flags::BufMask,
+        Error,
+    };
+
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn formatted_io() -> Result<()> {
+        let (dev, _rm, instr) = MockResource::new("USB0::0x1234::0x5678::FMT::INSTR")
+            .respond("MEAS:VOLT?;CURR?", "+1.5E+0;-2E-3\n")
+            .respond("TRAC:DATA?", "1, 2,3.5\n")
+            .respond("*IDN?", "\"ACME, \"\"Inc\"\"\",#H1F\n")
+            .respond("CURV?", b"#15ab\ncd\n")
+            .open()?;
+
+        instr.set_buf(BufMask::WRITE_BUF, 4)?;
+        instr.printf(format_args!("SOUR:VOLT {}", 1.5))?;
+        assert_eq!(dev.written(), b"SOUR:VOLT 1.");
+        instr.printf(format_args!(";*OPC\n"))?;
+        assert_eq!(dev.take_written(), b"SOUR:VOLT 1.5;*OPC\n");
+
+        instr.set_buf(BufMask::READ_BUF | BufMask::WRITE_BUF, 3)?;
+        let (volt, curr): (f64, f64) = instr.queryf(format_args!("MEAS:VOLT?;CURR?\n"))?;
+        assert_eq!((volt, curr), (1.5, -2e-3));
+        instr.set_attr(attribute::AttrTermcharEn::VI_TRUE)?;
+        let trace: Vec<f32> = instr.queryf(format_args!("TRAC:DATA?\n"))?;
+        assert_eq!(trace, [1.0, 2.0, 3.5]);
+        let (name, code): (String, u8) = instr.queryf(format_args!("*IDN?\n"))?;
+        assert_eq!(name, "ACME, \"Inc\"");
+        assert_eq!(code, 0x1F);
+        let Block(data) = instr.queryf(format_args!("CURV?\n"))?;
+        assert_eq!(data, b"ab\ncd");
+        assert_eq!(
+            instr.queryf::<i32>(format_args!("*IDN?\n")),
+            Err(Error(ErrorCode::ErrorInvFmt))
+        );
+        assert_eq!(instr.queryf::<u32>(format_args!("TRAC:DATA?\n"))?, 1);
+        Ok(())
+    }
+}
@@ -1,59 +1,99 @@
 //!
 //! Defines [`Callback`] trait used in [`Instrument::install_handler`](crate::Instrument::install_handler),
-//! which returns a [`Handler`] to manage lifetime of data passed
+//! which returns a [`Handler`] to manage lifetime of data passed,
+//! or in [`Instrument::install_detached_handler`](crate::Instrument::install_detached_handler),
+//! which returns a [`DetachedHandler`] passing the [`HandlerAction`] of the callback on to VISA
 //!
 //!
 //!
 
 use std::{
+    any::TypeId,
+    marker::PhantomData,
     ptr::NonNull,
-    sync::mpsc::{Receiver, Sender},
+    sync::{
+        mpsc::{Receiver, Sender},
+        Mutex,
+    },
 };
 use visa_sys as vs;
 
 use crate::{
-    enums::event,
+    backend::{Backend, Visa},
+    enums::{event, status::CompletionCode},
     session::{AsRawSs, BorrowedSs, FromRawSs},
     Instrument, Result, SUCCESS,
 };
 
+/// Tells VISA whether to invoke the other handlers installed on the session for the same event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HandlerAction {
+    /// Invoke the next handler, `VI_SUCCESS`
+    #[default]
+    Continue,
+    /// Don't invoke any other handler on this session for this event, `VI_SUCCESS_NCHAIN`
+    StopChain,
+}
+
+impl HandlerAction {
+    pub(crate) fn status(self) -> vs::ViStatus {
+        match self {
+            Self::Continue => SUCCESS,
+            Self::StopChain => CompletionCode::SuccessNchain.into(),
+        }
+    }
+}
+
 /// Defines the ability for being passed to [`Instrument::install_handler`](crate::Instrument::install_handler)
-pub trait Callback {
-    type Output;
-    fn call(&mut self, instr: &Instrument, event: &event::Event) -> Self::Output;
+///
+/// VISA invokes the callback on threads of its own, hence the `Send` bounds.
+pub trait Callback<B: Backend = Visa>: Send {
+    type Output: Send;
+    fn call(&mut self, instr: &Instrument<B>, event: &event::Event<B>) -> Self::Output;
 }
 
-impl<F, Out> Callback for F
+impl<F, Out, B: Backend> Callback<B> for F
 where
-    F: FnMut(&Instrument, &event::Event) -> Out,
+    F: FnMut(&Instrument<B>, &event::Event<B>) -> Out + Send,
+    Out: Send,
 {
     type Output = Out;
-    fn call(&mut self, instr: &Instrument, event: &event::Event) -> Self::Output {
+    fn call(&mut self, instr: &Instrument<B>, event: &event::Event<B>) -> Self::Output {
         self(instr, event)
     }
 }
 
-struct CallbackPack<F: Callback> {
-    sender: Sender<F::Output>,
+/// Where the output of a callback goes
+enum Sink<T> {
+    /// Sent to the [`Handler`] with [`HandlerAction::Continue`] returned to VISA
+    Channel(Sender<T>),
+    /// Turned into the status returned to VISA
+    Detached(fn(T) -> HandlerAction),
+}
+
+struct CallbackPack<F: Callback<B>, B: Backend> {
+    sink: Sink<F::Output>,
     core: F,
+    _backend: PhantomData<B>,
 }
 
-impl<F: Callback> CallbackPack<F> {
-    fn from_callback(f: F) -> (Self, Receiver<F::Output>) {
-        let (sender, receiver) = std::sync::mpsc::channel();
-        (Self { sender, core: f }, receiver)
-    }
-    fn call(&mut self, instr: &Instrument, event: &event::Event) -> vs::ViStatus {
+impl<F: Callback<B>, B: Backend> CallbackPack<F, B> {
+    fn call(&mut self, instr: &Instrument<B>, event: &event::Event<B>) -> HandlerAction {
         //Normally, an application should always return VI_SUCCESS from all callback handlers. If a specific handler does not want other handlers to be invoked for the given event for the given session, it should return VI_SUCCESS_NCHAIN. No return value from a handler on one session will affect callbacks on other sessions. Future versions of VISA (or specific implementations of VISA) may take actions based on other return values, so a user should return VI_SUCCESS from handlers unless there is a specific reason to do otherwise.
-        self.sender
-            .send(self.core.call(instr, event))
-            .expect("receiver side should be valid");
-        SUCCESS
+        let out = self.core.call(instr, event);
+        match &self.sink {
+            Sink::Channel(sender) => {
+                // nobody listening any more, the output is discarded
+                let _ = sender.send(out);
+                HandlerAction::Continue
+            }
+            Sink::Detached(action) => action(out),
+        }
     }
 }
 
-struct CallbackWrapper<F: Callback> {
-    f: NonNull<CallbackPack<F>>,
+struct CallbackWrapper<F: Callback<B>, B: Backend> {
+    f: NonNull<CallbackPack<F, B>>,
     //? not sure if reproduce from F would get the same fn pointer, so better hold it
     hold: unsafe extern "system" fn(
         vs::ViSession,
@@ -62,10 +102,10 @@ struct CallbackWrapper<F: Callback> {
         *mut std::ffi::c_void,
     ) -> vs::ViStatus,
 }
-fn split_pack<C: Callback>(
-    pack: CallbackPack<C>,
+fn split_pack<C: Callback<B>, B: Backend>(
+    pack: CallbackPack<C, B>,
 ) -> (
-    std::ptr::NonNull<CallbackPack<C>>,
+    std::ptr::NonNull<CallbackPack<C, B>>,
     unsafe extern "system" fn(
         vs::ViSession,
         vs::ViEventType,
@@ -75,71 +115,80 @@ fn split_pack<C: Callback>(
 ) {
     use std::ffi::c_void;
     let data = Box::into_raw(Box::new(pack));
-    unsafe extern "system" fn trampoline<T: Callback>(
+    unsafe extern "system" fn trampoline<T: Callback<B>, B: Backend>(
         instr: vs::ViSession,
         event_type: vs::ViEventType,
         event: vs::ViEvent,
         user_data: *mut c_void,
     ) -> vs::ViStatus {
-        let pack: &mut CallbackPack<T> = &mut *(user_data as *mut CallbackPack<T>);
-        let instr = Instrument::from_raw_ss(instr);
-        let event = event::Event::new(event, event_type);
-        let ret = pack.call(&instr, &event);
-        std::mem::forget(event); // The VISA system automatically invokes the viClose() operation on the event context when a user handler returns. Because the event context must still be valid after the user handler returns (so that VISA can free it up), an application should not invoke the viClose() operation on an event context passed to a user handler.
-        std::mem::forget(instr); // ? no sure yet, in official example session not closed
-
-        ret
+        let pack: &mut CallbackPack<T, B> = &mut *(user_data as *mut CallbackPack<T, B>);
+        // called from a thread of the VISA library, a panic must not unwind across it
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            // ? no sure yet, in official example session not closed
+            let instr = std::mem::ManuallyDrop::new(Instrument::<B>::from_raw_ss(instr));
+            // The VISA system automatically invokes the viClose() operation on the event context when a user handler returns. Because the event context must still be valid after the user handler returns (so that VISA can free it up), an application should not invoke the viClose() operation on an event context passed to a user handler.
+            let event = std::mem::ManuallyDrop::new(event::Event::<B>::new(event, event_type));
+            pack.call(&instr, &event)
+        }))
+        .unwrap_or_else(|_| {
+            log::error!("event handler panicked");
+            HandlerAction::Continue
+        })
+        .status()
     }
 
     (
         NonNull::new(data).expect("impossible to pass in a null ptr"),
-        trampoline::<C>,
+        trampoline::<C, B>,
     )
 }
-impl<F: Callback> CallbackWrapper<F> {
-    pub(crate) fn new(f: F) -> (Self, Receiver<F::Output>) {
-        let (pack, receiver) = CallbackPack::from_callback(f);
-        let (data, fun) = split_pack(pack);
-        (Self { f: data, hold: fun }, receiver)
+impl<F: Callback<B>, B: Backend> CallbackWrapper<F, B> {
+    fn new(f: F, sink: Sink<F::Output>) -> Self {
+        let (data, fun) = split_pack(CallbackPack {
+            sink,
+            core: f,
+            _backend: PhantomData,
+        });
+        Self { f: data, hold: fun }
     }
 }
 
-/// Lifetime manager for [`Callback`], will uninstall the callback when dropped.
-///
-/// Internally hold a [`Receiver`] (accessed by [`Self::receiver`]) to receive output of callback from visa.
-pub struct Handler<'b, F: Callback> {
-    instr: BorrowedSs<'b>,
-    rec: Receiver<F::Output>,
+/// A callback installed on a session, uninstalled when dropped
+struct Installed<'b, F: Callback<B>, B: Backend> {
+    instr: BorrowedSs<'b, B>,
     event_kind: event::EventKind,
-    callback: CallbackWrapper<F>,
+    callback: CallbackWrapper<F, B>,
 }
 
-impl<'b, F: Callback> Handler<'b, F> {
-    pub(crate) fn new(
-        instr: BorrowedSs<'b>,
+impl<'b, F: Callback<B> + 'static, B: Backend> Installed<'b, F, B> {
+    fn new(
+        instr: BorrowedSs<'b, B>,
         event_kind: event::EventKind,
         callback: F,
+        sink: Sink<F::Output>,
     ) -> Result<Self> {
-        let (callback, rec) = CallbackWrapper::new(callback);
-        super::wrap_raw_error_in_unsafe!(vs::viInstallHandler(
+        let callback = CallbackWrapper::new(callback, sink);
+        if let Err(e) = super::wrap_raw_error_in_unsafe!(B::install_handler(
             instr.as_raw_ss(),
             event_kind as _,
             Some(callback.hold),
             callback.f.as_ptr() as _
-        ))?;
+        )) {
+            unsafe { drop(Box::from_raw(callback.f.as_ptr())) };
+            return Err(e);
+        }
         Ok(Self {
             instr,
-            rec,
             event_kind,
             callback,
         })
     }
 }
 
-impl<'b, F: Callback> Drop for Handler<'b, F> {
+impl<'b, F: Callback<B>, B: Backend> Drop for Installed<'b, F, B> {
     fn drop(&mut self) {
         unsafe {
-            vs::viUninstallHandler(
+            B::uninstall_handler(
                 self.instr.as_raw_ss(),
                 self.event_kind as _,
                 Some(self.callback.hold),
@@ -150,18 +199,235 @@ impl<'b, F: Callback> Drop for Handler<'b, F> {
     }
 }
 
-impl<'b, F: Callback> Handler<'b, F> {
+/// Lifetime manager for [`Callback`], will uninstall the callback when dropped.
+///
+/// Internally hold a [`Receiver`] (accessed by [`Self::receiver`]) to receive output of callback from visa.
+pub struct Handler<'b, F: Callback<B>, B: Backend = Visa> {
+    rec: Receiver<F::Output>,
+    installed: Installed<'b, F, B>,
+}
+
+impl<'b, F: Callback<B> + 'static, B: Backend> Handler<'b, F, B> {
+    pub(crate) fn new(
+        instr: BorrowedSs<'b, B>,
+        event_kind: event::EventKind,
+        callback: F,
+    ) -> Result<Self> {
+        let (sender, rec) = std::sync::mpsc::channel();
+        let installed = Installed::new(instr, event_kind, callback, Sink::Channel(sender))?;
+        Ok(Self { rec, installed })
+    }
+}
+
+impl<'b, F: Callback<B>, B: Backend> Handler<'b, F, B> {
     pub fn uninstall(self) {}
 }
 
-impl<'b, F: Callback> AsRef<Receiver<F::Output>> for Handler<'b, F> {
+impl<'b, F: Callback<B>, B: Backend> AsRef<Receiver<F::Output>> for Handler<'b, F, B> {
     fn as_ref(&self) -> &Receiver<F::Output> {
         &self.rec
     }
 }
 
-impl<'b, F: Callback> Handler<'b, F> {
+impl<'b, F: Callback<B>, B: Backend> Handler<'b, F, B> {
     pub fn receiver(&self) -> &Receiver<F::Output> {
         self.as_ref()
     }
+
+    /// Kind of the event the callback is installed for
+    pub fn event_kind(&self) -> event::EventKind {
+        self.installed.event_kind
+    }
+}
+
+/// Lifetime manager for a fire-and-forget [`Callback`] returned by [`Instrument::install_detached_handler`](crate::Instrument::install_detached_handler),
+/// will uninstall the callback when dropped.
+///
+/// No channel is involved, the [`HandlerAction`] returned by the callback is passed on to VISA.
+pub struct DetachedHandler<'b, F: Callback<B, Output = HandlerAction>, B: Backend = Visa> {
+    installed: Installed<'b, F, B>,
+}
+
+impl<'b, F: Callback<B, Output = HandlerAction> + 'static, B: Backend> DetachedHandler<'b, F, B> {
+    pub(crate) fn new(
+        instr: BorrowedSs<'b, B>,
+        event_kind: event::EventKind,
+        callback: F,
+    ) -> Result<Self> {
+        let installed = Installed::new(instr, event_kind, callback, Sink::Detached(|a| a))?;
+        Ok(Self { installed })
+    }
+}
+
+impl<'b, F: Callback<B, Output = HandlerAction>, B: Backend> DetachedHandler<'b, F, B> {
+    pub fn uninstall(self) {}
+
+    /// Kind of the event the callback is installed for
+    pub fn event_kind(&self) -> event::EventKind {
+        self.installed.event_kind
+    }
+}
+
+/// An event kind of a session enabled for the handler mechanism by this crate
+struct HandlerEnable {
+    backend: TypeId,
+    vi: vs::ViSession,
+    kind: event::EventKind,
+    /// Event streams and exception hooks relying on it
+    users: usize,
+    /// Whether it was enabled by this crate rather than by the application
+    owned: bool,
+}
+
+static HANDLER_ENABLES: Mutex<Vec<HandlerEnable>> = Mutex::new(Vec::new());
+
+/// Enables `kind` on `instr` for the handler mechanism, shared by the handlers installed by this crate.
+///
+/// Only the first user enables the event, see [`release_handler_enable`].
+pub(crate) fn acquire_handler_enable<B: Backend>(
+    instr: &Instrument<B>,
+    kind: event::EventKind,
+) -> Result<()> {
+    let mut enables = HANDLER_ENABLES.lock().unwrap_or_else(|e| e.into_inner());
+    let (backend, vi) = (TypeId::of::<B>(), instr.as_raw_ss());
+    match enables
+        .iter_mut()
+        .find(|e| e.backend == backend && e.vi == vi && e.kind == kind)
+    {
+        Some(enable) => enable.users += 1,
+        None => {
+            let code = instr.enable_event_outcome(kind, event::Mechanism::Handler)?;
+            enables.push(HandlerEnable {
+                backend,
+                vi,
+                kind,
+                users: 1,
+                owned: code.code != CompletionCode::SuccessEventEn,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Releases an [`acquire_handler_enable`], the last user disables the event unless the application enabled it beforehand.
+pub(crate) fn release_handler_enable<B: Backend>(
+    instr: &Instrument<B>,
+    kind: event::EventKind,
+) -> Result<()> {
+    let mut enables = HANDLER_ENABLES.lock().unwrap_or_else(|e| e.into_inner());
+    let (backend, vi) = (TypeId::of::<B>(), instr.as_raw_ss());
+    let Some(i) = enables
+        .iter()
+        .position(|e| e.backend == backend && e.vi == vi && e.kind == kind)
+    else {
+        return Ok(());
+    };
+    enables[i].users -= 1;
+    if enables[i].users == 0 {
+        let enable = enables.swap_remove(i);
+        if enable.owned {
+            instr.disable_event(kind, event::Mechanism::Handler)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::{Mock, MockResource},
+        enums::{
+            attribute::{self, SpecAttr},
+            event::{self, Event},
+            status::ErrorCode,
+        },
+        Error, Instrument, TIMEOUT_IMMEDIATE,
+    };
+
+    use anyhow::Result;
+
+    #[test]
+    fn queued_and_handled_events() -> Result<()> {
+        let (dev, _rm, instr) = MockResource::new("PXI0::3-4.0::INSTR").open()?;
+        assert_eq!(
+            instr.wait_on_event(event::EventKind::EventServiceReq, TIMEOUT_IMMEDIATE),
+            Err(Error(ErrorCode::ErrorNenabled))
+        );
+        instr.enable_event(event::EventKind::EventServiceReq, event::Mechanism::Queue)?;
+        dev.raise_event(event::EventKind::EventServiceReq);
+        let ev = instr.wait_on_event(event::EventKind::EventServiceReq, TIMEOUT_IMMEDIATE)?;
+        assert_eq!(ev.kind(), event::EventKind::EventServiceReq);
+        assert_eq!(
+            instr
+                .wait_on_event(event::EventKind::EventServiceReq, TIMEOUT_IMMEDIATE)
+                .map(|_| ()),
+            Err(Error(ErrorCode::ErrorTmo))
+        );
+
+        let handler = instr.install_handler(
+            event::EventKind::EventTrig,
+            |_: &Instrument<Mock>, e: &Event<Mock>| {
+                attribute::AttrRecvTrigId::get_from(e).map(|x| x.into_inner())
+            },
+        )?;
+        instr.enable_event(event::EventKind::EventTrig, event::Mechanism::Handler)?;
+        dev.raise_event_with(
+            event::EventKind::EventTrig,
+            [attribute::AttrRecvTrigId::VI_TRIG_SW.into()],
+        );
+        assert_eq!(
+            handler.receiver().try_recv()?,
+            Ok(attribute::AttrRecvTrigId::VI_TRIG_SW.into_inner())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn detached_handlers() -> Result<()> {
+        use crate::handler::HandlerAction;
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+        let (dev, _rm, instr) = MockResource::new("PXI0::5-4.0::INSTR").open()?;
+        let kind = event::EventKind::EventTrig;
+        let chained = Arc::new(AtomicUsize::new(0));
+        let counter = chained.clone();
+        let _last = instr.install_detached_handler(
+            kind,
+            move |_: &Instrument<Mock>, _: &Event<Mock>| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                HandlerAction::Continue
+            },
+        )?;
+        let sent = instr.install_handler(kind, |_: &Instrument<Mock>, _: &Event<Mock>| 1)?;
+        let stop = Arc::new(AtomicUsize::new(1));
+        let flag = stop.clone();
+        let first = instr.install_detached_handler(
+            kind,
+            move |_: &Instrument<Mock>, _: &Event<Mock>| {
+                if flag.load(Ordering::SeqCst) == 1 {
+                    HandlerAction::StopChain
+                } else {
+                    panic!("unwinding must not reach VISA")
+                }
+            },
+        )?;
+        assert_eq!(first.event_kind(), kind);
+        instr.enable_event(kind, event::Mechanism::Handler)?;
+
+        dev.raise_event(kind);
+        assert_eq!(chained.load(Ordering::SeqCst), 0);
+        assert!(sent.receiver().try_recv().is_err());
+
+        stop.store(0, Ordering::SeqCst);
+        dev.raise_event(kind);
+        assert_eq!(chained.load(Ordering::SeqCst), 1);
+        assert_eq!(sent.receiver().try_recv()?, 1);
+
+        first.uninstall();
+        dev.raise_event(kind);
+        assert_eq!(chained.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
 }
@@ -1,69 +1,107 @@
 use super::*;
 /// Session to a specified resource
 #[derive(Debug, PartialEq, Eq, Hash)]
-pub struct Instrument(pub(crate) OwnedSs);
+pub struct Instrument<B: Backend = Visa>(pub(crate) OwnedSs<B>);
 
-impl std::io::Write for Instrument {
+impl<B: Backend> std::io::Write for Instrument<B> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        <&Instrument>::write(&mut &*self, buf)
+        <&Instrument<B>>::write(&mut &*self, buf)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        <&Instrument>::flush(&mut &*self)
+        <&Instrument<B>>::flush(&mut &*self)
     }
 }
 
-impl std::io::Read for Instrument {
+impl<B: Backend> std::io::Read for Instrument<B> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        <&Instrument>::read(&mut &*self, buf)
+        <&Instrument<B>>::read(&mut &*self, buf)
     }
 }
 
-impl std::io::Write for &Instrument {
+impl<B: Backend> std::io::Write for &Instrument<B> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let mut ret_cnt: vs::ViUInt32 = 0;
-        wrap_raw_error_in_unsafe!(vs::viWrite(
-            self.as_raw_ss(),
-            buf.as_ptr(),
-            buf.len() as _,
-            &mut ret_cnt as _
-        ))
-        .map_err(vs_to_io_err)?;
-
-        Ok(ret_cnt as _)
+        Ok(self.write_raw(buf)?)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        self.visa_flush(flags::FlushMode::IO_OUT_BUF)
-            .map_err(vs_to_io_err)
         // Flush the low-level I/O buffer used by viWrite
+        Ok(self.visa_flush(flags::FlushMode::IO_OUT_BUF)?)
     }
 }
 
-impl std::io::Read for &Instrument {
+impl<B: Backend> std::io::Read for &Instrument<B> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(self.read_outcome(buf)?.into_inner())
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Reads data from device or interface synchronously, returning the number of bytes read and how the read stopped.
+    ///
+    /// Unlike [`Read`](std::io::Read), tells whether the message is complete: see [`Outcome::end_received`], [`Outcome::term_char_received`] and [`Outcome::max_count_reached`].
+    pub fn read_outcome(&self, buf: &mut [u8]) -> Result<Outcome<usize>> {
         let mut ret_cnt: vs::ViUInt32 = 0;
-        wrap_raw_error_in_unsafe!(vs::viRead(
+        let code = wrap_raw_error_in_unsafe!(B::read(
             self.as_raw_ss(),
             buf.as_mut_ptr(),
             buf.len() as _,
             &mut ret_cnt as _
-        ))
-        .map_err(vs_to_io_err)?;
-        Ok(ret_cnt as _)
+        ))?;
+        Ok(Outcome::new(ret_cnt as _, code))
+    }
+
+    /// Writes data to device or interface synchronously, returning the number of bytes written and the completion code.
+    pub fn write_outcome(&self, buf: &[u8]) -> Result<Outcome<usize>> {
+        let mut ret_cnt: vs::ViUInt32 = 0;
+        let code = wrap_raw_error_in_unsafe!(B::write(
+            self.as_raw_ss(),
+            buf.as_ptr(),
+            buf.len() as _,
+            &mut ret_cnt as _
+        ))?;
+        Ok(Outcome::new(ret_cnt as _, code))
+    }
+
+    /// viWrite() of `buf`, returning the number of bytes written
+    pub(crate) fn write_raw(&self, buf: &[u8]) -> Result<usize> {
+        self.write_outcome(buf).map(Outcome::into_inner)
+    }
+
+    /// viWrite() of the whole `buf`
+    pub(crate) fn write_all_raw(&self, mut buf: &[u8]) -> Result<()> {
+        while !buf.is_empty() {
+            buf = &buf[self.write_raw(buf)?.min(buf.len())..];
+        }
+        Ok(())
+    }
+
+    /// `VI_ATTR_TERMCHAR`, or `\n` if the session doesn't support it
+    pub(crate) fn termchar(&self) -> u8 {
+        use enums::attribute::SpecAttr;
+        enums::attribute::AttrTermchar::get_from(self)
+            .map(|t| t.into_inner())
+            .unwrap_or(b'\n')
+    }
+
+    /// Reads never stop at END, because `VI_ATTR_SUPPRESS_END_EN` is set or the serial port is configured without END,
+    /// so only the termination character ends a response
+    pub(crate) fn end_disabled(&self) -> bool {
+        use enums::attribute::{AttrAsrlEndIn, AttrSuppressEndEn, SpecAttr};
+        AttrSuppressEndEn::get_from(self).is_ok_and(|x| x.into_inner() != vs::VI_FALSE as _)
+            || AttrAsrlEndIn::get_from(self)
+                .is_ok_and(|x| x.into_inner() as vs::ViUInt32 == vs::VI_ASRL_END_NONE)
     }
-}
 
-impl Instrument {
     ///Manually flushes the specified buffers associated with formatted I/O operations and/or serial communication.
     pub fn visa_flush(&self, mode: flags::FlushMode) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viFlush(self.as_raw_ss(), mode.bits()))?;
+        wrap_raw_error_in_unsafe!(B::flush(self.as_raw_ss(), mode.bits()))?;
         Ok(())
     }
     /// Returns a user-readable description of the status code passed to the operation.
     pub fn status_desc(&self, error: Error) -> Result<VisaString> {
         let mut desc: VisaBuf = new_visa_buf();
-        wrap_raw_error_in_unsafe!(vs::viStatusDesc(
+        wrap_raw_error_in_unsafe!(B::status_desc(
             self.as_raw_ss(),
             error.into(),
             desc.as_mut_ptr() as _
@@ -90,31 +128,43 @@ impl Instrument {
         timeout: Duration,
         key: Option<AccessKey>,
     ) -> Result<Option<AccessKey>> {
+        self.lock_outcome(mode, timeout, key)
+            .map(Outcome::into_inner)
+    }
+
+    /// Same as [`Self::lock`], returning the completion code, [`SuccessNestedExclusive`](crate::enums::status::CompletionCode::SuccessNestedExclusive) or [`SuccessNestedShared`](crate::enums::status::CompletionCode::SuccessNestedShared) if the session already held the lock
+    pub fn lock_outcome(
+        &self,
+        mode: flags::AccessMode,
+        timeout: Duration,
+        key: Option<AccessKey>,
+    ) -> Result<Outcome<Option<AccessKey>>> {
         if (mode & flags::AccessMode::SHARED_LOCK).is_empty() {
-            wrap_raw_error_in_unsafe!(vs::viLock(
+            let code = wrap_raw_error_in_unsafe!(B::lock(
                 self.as_raw_ss(),
                 mode.bits(),
                 timeout.as_millis() as _,
                 vs::VI_NULL as _,
                 vs::VI_NULL as _
             ))?;
-            Ok(None)
+            Ok(Outcome::new(None, code))
         } else {
             let mut ak = new_visa_buf();
-            wrap_raw_error_in_unsafe!(vs::viLock(
+            let code = wrap_raw_error_in_unsafe!(B::lock(
                 self.as_raw_ss(),
                 mode.bits(),
                 timeout.as_millis() as _,
-                key.map(|x| x.as_vi_const_string())
+                key.as_ref()
+                    .map(|x| x.as_vi_const_string())
                     .unwrap_or(vs::VI_NULL as _),
                 ak.as_mut_ptr() as _
             ))?;
-            Ok(Some(ak.try_into().unwrap()))
+            Ok(Outcome::new(Some(ak.try_into().unwrap()), code))
         }
     }
 
     pub fn lock_exclusive(&self, timeout: Duration) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viLock(
+        wrap_raw_error_in_unsafe!(B::lock(
             self.as_raw_ss(),
             flags::AccessMode::EXCLUSIVE_LOCK.bits(),
             timeout.as_millis() as _,
@@ -126,9 +176,9 @@ impl Instrument {
 
     pub fn lock_shared(&self, timeout: Duration) -> Result<AccessKey> {
         let mut ak = new_visa_buf();
-        wrap_raw_error_in_unsafe!(vs::viLock(
+        wrap_raw_error_in_unsafe!(B::lock(
             self.as_raw_ss(),
-            flags::AccessMode::EXCLUSIVE_LOCK.bits(),
+            flags::AccessMode::SHARED_LOCK.bits(),
             timeout.as_millis() as _,
             vs::VI_NULL as _,
             ak.as_mut_ptr() as _
@@ -138,9 +188,9 @@ impl Instrument {
 
     pub fn lock_shared_with_key(&self, timeout: Duration, key: AccessKey) -> Result<AccessKey> {
         let mut ak = new_visa_buf();
-        wrap_raw_error_in_unsafe!(vs::viLock(
+        wrap_raw_error_in_unsafe!(B::lock(
             self.as_raw_ss(),
-            flags::AccessMode::EXCLUSIVE_LOCK.bits(),
+            flags::AccessMode::SHARED_LOCK.bits(),
             timeout.as_millis() as _,
             key.as_vi_const_string() as _,
             ak.as_mut_ptr() as _
@@ -150,8 +200,13 @@ impl Instrument {
 
     ///Relinquishes a lock for the specified resource.
     pub fn unlock(&self) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viUnlock(self.as_raw_ss()))?;
-        Ok(())
+        self.unlock_outcome().map(Outcome::into_inner)
+    }
+
+    /// Same as [`Self::unlock`], returning the completion code, [`SuccessNestedExclusive`](crate::enums::status::CompletionCode::SuccessNestedExclusive) or [`SuccessNestedShared`](crate::enums::status::CompletionCode::SuccessNestedShared) if the session still holds the lock
+    pub fn unlock_outcome(&self) -> Result<Outcome<()>> {
+        let code = wrap_raw_error_in_unsafe!(B::unlock(self.as_raw_ss()))?;
+        Ok(Outcome::new((), code))
     }
 
     ///Enables notification of a specified event.
@@ -166,13 +221,23 @@ impl Instrument {
         event_kind: event::EventKind,
         mechanism: event::Mechanism,
     ) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viEnableEvent(
+        self.enable_event_outcome(event_kind, mechanism)
+            .map(Outcome::into_inner)
+    }
+
+    /// Same as [`Self::enable_event`], returning the completion code, [`SuccessEventEn`](crate::enums::status::CompletionCode::SuccessEventEn) if the event was already enabled
+    pub fn enable_event_outcome(
+        &self,
+        event_kind: event::EventKind,
+        mechanism: event::Mechanism,
+    ) -> Result<Outcome<()>> {
+        let code = wrap_raw_error_in_unsafe!(B::enable_event(
             self.as_raw_ss(),
             event_kind as _,
             mechanism as _,
             event::EventFilter::Null as _
         ))?;
-        Ok(())
+        Ok(Outcome::new((), code))
     }
 
     /// Disables notification of the specified event type(s) via the specified mechanism(s).
@@ -186,12 +251,22 @@ impl Instrument {
         event_kind: event::EventKind,
         mechanism: event::Mechanism,
     ) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viDisableEvent(
+        self.disable_event_outcome(event_kind, mechanism)
+            .map(Outcome::into_inner)
+    }
+
+    /// Same as [`Self::disable_event`], returning the completion code, [`SuccessEventDis`](crate::enums::status::CompletionCode::SuccessEventDis) if the event was already disabled
+    pub fn disable_event_outcome(
+        &self,
+        event_kind: event::EventKind,
+        mechanism: event::Mechanism,
+    ) -> Result<Outcome<()>> {
+        let code = wrap_raw_error_in_unsafe!(B::disable_event(
             self.as_raw_ss(),
             event_kind as _,
             mechanism as _,
         ))?;
-        Ok(())
+        Ok(Outcome::new((), code))
     }
     /// Discards event occurrences for specified event types and mechanisms in a session.
     ///
@@ -205,7 +280,7 @@ impl Instrument {
         event: event::EventKind,
         mechanism: event::Mechanism,
     ) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viDiscardEvents(
+        wrap_raw_error_in_unsafe!(B::discard_events(
             self.as_raw_ss(),
             event as _,
             mechanism as _,
@@ -223,18 +298,95 @@ impl Instrument {
         &self,
         event_kind: event::EventKind,
         timeout: Duration,
-    ) -> Result<event::Event> {
+    ) -> Result<event::Event<B>> {
+        self.wait_on_event_outcome(event_kind, timeout)
+            .map(Outcome::into_inner)
+    }
+
+    /// Same as [`Self::wait_on_event`], returning the completion code, [`SuccessQueueNempty`](crate::enums::status::CompletionCode::SuccessQueueNempty) if more events are queued
+    pub fn wait_on_event_outcome(
+        &self,
+        event_kind: event::EventKind,
+        timeout: Duration,
+    ) -> Result<Outcome<event::Event<B>>> {
         let mut handler: vs::ViEvent = 0;
         let mut out_kind: vs::ViEventType = 0;
-        wrap_raw_error_in_unsafe!(vs::viWaitOnEvent(
+        let code = wrap_raw_error_in_unsafe!(B::wait_on_event(
             self.as_raw_ss(),
             event_kind as _,
             timeout.as_millis() as _,
             &mut out_kind as _,
             &mut handler as _
         ))?;
-        let kind = event::EventKind::try_from(out_kind).expect("should be valid event type");
-        Ok(event::Event { handler, kind })
+        Ok(Outcome::new(event::Event::new(handler, out_kind), code))
+    }
+
+    /// Dequeues an occurrence of the specified event without waiting, `Ok(None)` if none is queued.
+    ///
+    /// Same as [`Self::wait_on_event`] with [`TIMEOUT_IMMEDIATE`], mapping [`ErrorTmo`](crate::enums::status::ErrorCode::ErrorTmo) to `Ok(None)`.
+    pub fn poll_event(&self, event_kind: event::EventKind) -> Result<Option<event::Event<B>>> {
+        match self.wait_on_event(event_kind, TIMEOUT_IMMEDIATE) {
+            Ok(event) => Ok(Some(event)),
+            Err(Error(enums::status::ErrorCode::ErrorTmo)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Waits for an occurrence of any of the specified events, [`Event::kind`](event::Event::kind) tells which one fired.
+    ///
+    /// The kinds are enabled for [`Mechanism::Queue`](event::Mechanism::Queue) if they are not yet, and stay enabled.
+    /// Occurrences already queued are dequeued first, kinds earlier in `event_kinds` first,
+    /// then viWaitOnEvent() blocks on [`AllEnabledEvents`](event::EventKind::AllEnabledEvents) and the kinds are filtered on return,
+    /// as VISA can't wait on several kinds at once.
+    /// Occurrences of other kinds enabled for queuing on the session, dequeued meanwhile, are discarded.
+    ///
+    /// A single kind, or a set containing [`AllEnabledEvents`](event::EventKind::AllEnabledEvents), is waited on by viWaitOnEvent() directly.
+    ///
+    /// Returns [`ErrorTmo`](crate::enums::status::ErrorCode::ErrorTmo) on timeout and [`ErrorInvEvent`](crate::enums::status::ErrorCode::ErrorInvEvent) if `event_kinds` is empty.
+    pub fn wait_on_events(
+        &self,
+        event_kinds: &[event::EventKind],
+        timeout: Duration,
+    ) -> Result<event::Event<B>> {
+        use enums::status::ErrorCode;
+        let all = event::EventKind::AllEnabledEvents;
+        if event_kinds.is_empty() {
+            return Err(ErrorCode::ErrorInvEvent.into());
+        }
+        if event_kinds.contains(&all) {
+            return self.wait_on_event(all, timeout);
+        }
+        for kind in event_kinds {
+            self.enable_event(*kind, event::Mechanism::Queue)?;
+        }
+        if let [kind] = event_kinds {
+            return self.wait_on_event(*kind, timeout);
+        }
+        for kind in event_kinds {
+            if let Some(event) = self.poll_event(*kind)? {
+                return Ok(event);
+            }
+        }
+        // too far away to be represented is as good as infinite
+        let deadline = (timeout != TIMEOUT_INFINITE)
+            .then(|| std::time::Instant::now().checked_add(timeout))
+            .flatten();
+        loop {
+            let left = deadline.map_or(TIMEOUT_INFINITE, |deadline| {
+                deadline
+                    .saturating_duration_since(std::time::Instant::now())
+                    .min(TIMEOUT_INFINITE)
+            });
+            let event = self.wait_on_event(all, left)?;
+            if event_kinds.contains(&event.kind()) {
+                return Ok(event);
+            }
+            log::debug!(
+                "discarding {:?} while waiting on {:?}",
+                event.kind(),
+                event_kinds
+            );
+        }
     }
 
     /// Installs handlers for event callbacks.
@@ -245,27 +397,44 @@ impl Instrument {
     ///
     /// *Note*: for some reason pass a closure with type `|instr, event|{...}` may get compile error.
     /// Instead, use `|instr: & Instrument, event: & Event|{...}`.
-    pub fn install_handler<F: handler::Callback>(
+    ///
+    /// Outputs of the callback are sent to [`Handler::receiver`](handler::Handler::receiver), discarded once the handler is dropped.
+    /// The other handlers installed for the event are always invoked, see [`Self::install_detached_handler`] to stop the chain.
+    pub fn install_handler<F: handler::Callback<B> + 'static>(
         &self,
         event_kind: event::EventKind,
         callback: F,
-    ) -> Result<handler::Handler<'_, F>> {
+    ) -> Result<handler::Handler<'_, F, B>> {
         handler::Handler::new(self.as_ss(), event_kind, callback)
     }
 
+    /// Installs a fire-and-forget handler for event callbacks.
+    ///
+    /// Same as [`Self::install_handler`], but without a channel: the [`HandlerAction`](handler::HandlerAction) returned by the callback is returned to VISA,
+    /// [`HandlerAction::StopChain`](handler::HandlerAction::StopChain) keeps the handlers installed before it on this session from being invoked for the event.
+    pub fn install_detached_handler<
+        F: handler::Callback<B, Output = handler::HandlerAction> + 'static,
+    >(
+        &self,
+        event_kind: event::EventKind,
+        callback: F,
+    ) -> Result<handler::DetachedHandler<'_, F, B>> {
+        handler::DetachedHandler::new(self.as_ss(), event_kind, callback)
+    }
+
     /// Reads a status byte of the service request.
     ///
     /// The IEEE 488.2 standard defines several bit assignments in the status byte. For example, if bit 6 of the status is set, the device is requesting service. In addition to setting bit 6 when requesting service, 488.2 devices also use two other bits to specify their status. Bit 4, the Message Available bit (MAV), is set when the device is ready to send previously queried data. Bit 5, the Event Status bit (ESB), is set if one or more of the enabled 488.2 events occurs. These events include power-on, user request, command error, execution error, device dependent error, query error, request control, and operation complete. The device can assert SRQ when ESB or MAV are set, or when a manufacturer-defined condition occurs. Manufacturers of 488.2 devices use the remaining lower-order bits to communicate the reason for the service request or to summarize the device state.
     ///
     pub fn read_stb(&self) -> Result<u16> {
         let mut stb = 0;
-        wrap_raw_error_in_unsafe!(vs::viReadSTB(self.as_raw_ss(), &mut stb as *mut _))?;
+        wrap_raw_error_in_unsafe!(B::read_stb(self.as_raw_ss(), &mut stb as *mut _))?;
         Ok(stb)
     }
 
     /// The viClear() operation clears the device input and output buffers.
     pub fn clear(&self) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viClear(self.as_raw_ss()))?;
+        wrap_raw_error_in_unsafe!(B::clear(self.as_raw_ss()))?;
         Ok(())
     }
 
@@ -281,7 +450,7 @@ impl Instrument {
         how: enums::assert::AssertIntrHow,
         status_id: vs::ViUInt32,
     ) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viAssertIntrSignal(
+        wrap_raw_error_in_unsafe!(B::assert_intr_signal(
             self.as_raw_ss(),
             how as _,
             status_id as _
@@ -308,7 +477,7 @@ impl Instrument {
     /// # Trigger Reservation for PXI
     /// For PXI instruments, this operation reserves or releases (unreserves) a trigger line for use in external triggering. For PXI triggers, VI_TRIG_PROT_RESERVE and VI_TRIG_PROT_UNRESERVE are the only valid protocols.
     pub fn assert_trigger(&self, protocol: enums::assert::AssertTrigPro) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viAssertTrigger(self.as_raw_ss(), protocol as _))?;
+        wrap_raw_error_in_unsafe!(B::assert_trigger(self.as_raw_ss(), protocol as _))?;
         Ok(())
     }
 
@@ -318,7 +487,7 @@ impl Instrument {
     ///
     /// Asserting SYSRESET (also known as HARD RESET in the VXI specification) should be used only when it is necessary to promptly terminate operation of all devices in a VXIbus system. This is a serious action that always affects the entire VXIbus system.
     pub fn assert_util_signal(&self, line: enums::assert::AssertBusSignal) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viAssertUtilSignal(self.as_raw_ss(), line as _))?;
+        wrap_raw_error_in_unsafe!(B::assert_util_signal(self.as_raw_ss(), line as _))?;
         Ok(())
     }
 
@@ -329,7 +498,7 @@ impl Instrument {
     /// * Note: If `buf` is empty, the `retCount` in [viBufRead](vs::viBufRead) is set to [VI_NULL](vs::VI_NULL), the number of bytes transferred is not returned. You may find this useful if you need to know only whether the operation succeeded or failed.
     pub fn buf_read(&self, buf: &mut [u8]) -> Result<usize> {
         let mut ret_cnt: vs::ViUInt32 = 0;
-        wrap_raw_error_in_unsafe!(vs::viBufRead(
+        wrap_raw_error_in_unsafe!(B::buf_read(
             self.as_raw_ss(),
             if !buf.is_empty() {
                 buf.as_mut_ptr()
@@ -351,7 +520,7 @@ impl Instrument {
     /// * Note: If `buf` is empty, the `retCount` in [viBufWrite](vs::viBufWrite) is set to [VI_NULL](vs::VI_NULL), the number of bytes transferred is not returned. You may find this useful if you need to know only whether the operation succeeded or failed.
     pub fn buf_write(&self, buf: &[u8]) -> Result<usize> {
         let mut ret_cnt: vs::ViUInt32 = 0;
-        wrap_raw_error_in_unsafe!(vs::viBufWrite(
+        wrap_raw_error_in_unsafe!(B::buf_write(
             self.as_raw_ss(),
             if !buf.is_empty() {
                 buf.as_ptr()
@@ -366,12 +535,12 @@ impl Instrument {
 
     /// Sets the size for the formatted I/O and/or low-level I/O communication buffer(s).
     pub fn set_buf(&self, mask: flags::BufMask, size: usize) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viSetBuf(self.as_raw_ss(), mask.bits(), size as _))?;
+        wrap_raw_error_in_unsafe!(B::set_buf(self.as_raw_ss(), mask.bits(), size as _))?;
         Ok(())
     }
 }
 
-impl Instrument {
+impl<B: Backend> Instrument<B> {
     /// Reads data from device or interface asynchronously.
     ///
     /// The viReadAsync() operation asynchronously transfers data. The data read is to be stored in the buffer represented by buf. This operation normally returns before the transfer terminates.
@@ -387,7 +556,7 @@ impl Instrument {
     pub unsafe fn visa_read_async(&self, buf: &mut [u8]) -> Result<JobID> {
         let mut id: vs::ViJobId = 0;
         #[allow(unused_unsafe)]
-        wrap_raw_error_in_unsafe!(vs::viReadAsync(
+        wrap_raw_error_in_unsafe!(B::read_async(
             self.as_raw_ss(),
             buf.as_mut_ptr(),
             buf.len() as _,
@@ -409,7 +578,7 @@ impl Instrument {
     pub unsafe fn visa_write_async(&self, buf: &[u8]) -> Result<JobID> {
         let mut id: vs::ViJobId = 0;
         #[allow(unused_unsafe)]
-        wrap_raw_error_in_unsafe!(vs::viWriteAsync(
+        wrap_raw_error_in_unsafe!(B::write_async(
             self.as_raw_ss(),
             buf.as_ptr(),
             buf.len() as _,
@@ -425,27 +594,23 @@ impl Instrument {
     /// If a user passes VI_NULL as the jobId value to viTerminate(), VISA will abort any calls in the current process executing on the specified vi. Any call that is terminated this way should return VI_ERROR_ABORT. Due to the nature of multi-threaded systems, for example where operations in other threads may complete normally before the operation viTerminate() has any effect, the specified return value is not guaranteed.
     ///
     pub fn terminate(&self, job_id: JobID) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viTerminate(
-            self.as_raw_ss(),
-            vs::VI_NULL as _,
-            job_id.0
-        ))?;
+        wrap_raw_error_in_unsafe!(B::terminate(self.as_raw_ss(), vs::VI_NULL as _, job_id.0))?;
         Ok(())
     }
     /// Wraps the instrument with async I/O support.
-    pub fn into_async(self) -> Result<async_io::AsyncInstrument> {
+    pub fn into_async(self) -> Result<async_io::AsyncInstrument<B>> {
         async_io::AsyncInstrument::new(self)
     }
 
     /// Tokio async IO adapter
     #[cfg(feature = "tokio")]
-    pub fn into_tokio_async(self) -> Result<async_tokio::InstrumentTokioAdapter> {
+    pub fn into_tokio_async(self) -> Result<async_tokio::InstrumentTokioAdapter<B>> {
         self.try_into()
     }
 }
 
 // GPIB operations
-impl Instrument {
+impl<B: Backend> Instrument<B> {
     /// Write GPIB command bytes on the bus.
     ///
     /// This operation attempts to write count number of bytes of GPIB commands to the interface bus specified by vi. This operation is valid only on GPIB INTFC (interface) sessions. This operation returns only when the transfer terminates.
@@ -453,7 +618,7 @@ impl Instrument {
     /// * Note: If `buf` is empty, the `retCount` in [viGpibCommand](vs::viGpibCommand) is set to [VI_NULL](vs::VI_NULL), the number of bytes transferred is not returned. You may find this useful if you need to know only whether the operation succeeded or failed.
     pub fn gpib_command(&self, buf: &[u8]) -> Result<usize> {
         let mut ret_cnt: vs::ViUInt32 = 0;
-        wrap_raw_error_in_unsafe!(vs::viGpibCommand(
+        wrap_raw_error_in_unsafe!(B::gpib_command(
             self.as_raw_ss(),
             if !buf.is_empty() {
                 buf.as_ptr()
@@ -472,7 +637,7 @@ impl Instrument {
     ///
     /// It is generally not necessary to use the viGpibControlATN() operation in most applications. Other operations such as viGpibCommand() and viGpibPassControl() modify the ATN and/or CIC state automatically.
     pub fn gpib_control_atn(&self, mode: enums::gpib::AtnMode) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viGpibControlATN(self.as_raw_ss(), mode as _))?;
+        wrap_raw_error_in_unsafe!(B::gpib_control_atn(self.as_raw_ss(), mode as _))?;
         Ok(())
     }
 
@@ -480,7 +645,7 @@ impl Instrument {
     ///
     /// The viGpibControlREN() operation asserts or unasserts the GPIB REN interface line according to the specified mode. The mode can also specify whether the device associated with this session should be placed in local state (before deasserting REN) or remote state (after asserting REN). This operation is valid only if the GPIB interface associated with the session specified by vi is currently the system controller.
     pub fn gpib_control_ren(&self, mode: enums::gpib::RenMode) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viGpibControlREN(self.as_raw_ss(), mode as _))?;
+        wrap_raw_error_in_unsafe!(B::gpib_control_ren(self.as_raw_ss(), mode as _))?;
         Ok(())
     }
 
@@ -497,7 +662,7 @@ impl Instrument {
         prim_addr: vs::ViUInt16,
         sec_addr: impl Into<Option<vs::ViUInt16>>,
     ) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viGpibPassControl(
+        wrap_raw_error_in_unsafe!(B::gpib_pass_control(
             self.as_raw_ss(),
             prim_addr as _,
             sec_addr.into().unwrap_or(vs::VI_NO_SEC_ADDR as _) as _
@@ -509,7 +674,275 @@ impl Instrument {
     /// This operation asserts the IFC line and becomes controller in charge (CIC). The local board must be the system controller. This operation is valid only on GPIB INTFC (interface) sessions.
     ///
     pub fn gpib_send_ifc(&self) -> Result<()> {
-        wrap_raw_error_in_unsafe!(vs::viGpibSendIFC(self.as_raw_ss(),))?;
+        wrap_raw_error_in_unsafe!(B::gpib_send_ifc(self.as_raw_ss(),))?;
+        Ok(())
+    }
+}
+
+// VXI operations
+impl<B: Backend> Instrument<B> {
+    /// Sends a Word Serial command or query to a VXI message-based device and returns the response.
+    ///
+    /// This operation can send a command or query, or receive a response to a query previously sent to the device. The `mode` parameter specifies whether the command is 16 or 32 bits, and whether a 16-bit or 32-bit response is read. This operation is valid only on VXI INSTR sessions to message-based devices.
+    ///
+    /// Only the low 16 bits of `cmd` are sent for 16-bit commands, and `cmd` is ignored if `mode` only reads a response. The returned value is 0 if `mode` doesn't read a response.
+    pub fn vxi_command_query(&self, mode: enums::vxi::VxiCmdMode, cmd: u32) -> Result<u32> {
+        let mut response: vs::ViUInt32 = 0;
+        wrap_raw_error_in_unsafe!(B::vxi_command_query(
+            self.as_raw_ss(),
+            mode as _,
+            cmd as _,
+            &mut response as _
+        ))?;
+        Ok(response as _)
+    }
+}
+
+macro_rules! impl_register_ops {
+    ($($bits:literal $ty:ty: $in:ident $out:ident $move_in:ident $move_out:ident => $b_in:ident $b_out:ident $b_move_in:ident $b_move_out:ident;)*) => {
+        // Register-based operations
+        impl<B: Backend> Instrument<B> {
+            $(
+                #[doc = concat!("Reads in a ", $bits, "-bit value from the specified memory space and offset.")]
+                ///
+                /// This operation reads a single value from the address space pointed to by `space` and `offset`, using the byte order of `VI_ATTR_SRC_BYTE_ORDER`.
+                /// The offset is relative to the base of the device registers (INSTR sessions) or to the start of the address space (MEMACC sessions).
+                ///
+                /// If the value is not directly accessible, e.g. in a register of a device not in the local address space, a bus error `VI_ERROR_BERR` is returned.
+                pub fn $in(&self, space: enums::memory::AddressSpace, offset: u64) -> Result<$ty> {
+                    let mut val = 0;
+                    wrap_raw_error_in_unsafe!(B::$b_in(
+                        self.as_raw_ss(),
+                        space as _,
+                        offset as _,
+                        &mut val as *mut $ty as *mut _
+                    ))?;
+                    Ok(val)
+                }
+
+                #[doc = concat!("Writes a ", $bits, "-bit value to the specified memory space and offset.")]
+                ///
+                /// This operation writes a single value to the address space pointed to by `space` and `offset`, using the byte order of `VI_ATTR_DEST_BYTE_ORDER`.
+                pub fn $out(&self, space: enums::memory::AddressSpace, offset: u64, val: $ty) -> Result<()> {
+                    wrap_raw_error_in_unsafe!(B::$b_out(
+                        self.as_raw_ss(),
+                        space as _,
+                        offset as _,
+                        val as _
+                    ))?;
+                    Ok(())
+                }
+
+                #[doc = concat!("Moves a block of ", $bits, "-bit elements from the specified memory space and offset to `buf`.")]
+                ///
+                /// `buf.len()` elements are transferred, the source offset being incremented after each element by `VI_ATTR_SRC_INCREMENT` elements,
+                /// set it to 0 to read a FIFO register. The byte order is that of `VI_ATTR_SRC_BYTE_ORDER`.
+                pub fn $move_in(
+                    &self,
+                    space: enums::memory::AddressSpace,
+                    offset: u64,
+                    buf: &mut [$ty],
+                ) -> Result<()> {
+                    wrap_raw_error_in_unsafe!(B::$b_move_in(
+                        self.as_raw_ss(),
+                        space as _,
+                        offset as _,
+                        buf.len() as _,
+                        buf.as_mut_ptr() as *mut _
+                    ))?;
+                    Ok(())
+                }
+
+                #[doc = concat!("Moves a block of ", $bits, "-bit elements from `buf` to the specified memory space and offset.")]
+                ///
+                /// `buf.len()` elements are transferred, the destination offset being incremented after each element by `VI_ATTR_DEST_INCREMENT` elements,
+                /// set it to 0 to write a FIFO register. The byte order is that of `VI_ATTR_DEST_BYTE_ORDER`.
+                pub fn $move_out(
+                    &self,
+                    space: enums::memory::AddressSpace,
+                    offset: u64,
+                    buf: &[$ty],
+                ) -> Result<()> {
+                    wrap_raw_error_in_unsafe!(B::$b_move_out(
+                        self.as_raw_ss(),
+                        space as _,
+                        offset as _,
+                        buf.len() as _,
+                        // VISA never writes to the source buffer
+                        buf.as_ptr() as *mut _
+                    ))?;
+                    Ok(())
+                }
+            )*
+
+            /// Moves a block of data from one address to another.
+            ///
+            /// This operation moves `len` elements of `width` from `src_space`/`src_offset` to `dest_space`/`dest_offset`, without going through user memory.
+            /// The source and destination may be in different address spaces, `VI_ATTR_SRC_INCREMENT`, `VI_ATTR_DEST_INCREMENT`,
+            /// `VI_ATTR_SRC_BYTE_ORDER` and `VI_ATTR_DEST_BYTE_ORDER` apply as for the move in and move out operations.
+            pub fn visa_move(
+                &self,
+                src_space: enums::memory::AddressSpace,
+                src_offset: u64,
+                dest_space: enums::memory::AddressSpace,
+                dest_offset: u64,
+                width: enums::memory::DataWidth,
+                len: usize,
+            ) -> Result<()> {
+                wrap_raw_error_in_unsafe!(B::move_ex(
+                    self.as_raw_ss(),
+                    src_space as _,
+                    src_offset as _,
+                    width as _,
+                    dest_space as _,
+                    dest_offset as _,
+                    width as _,
+                    len as _
+                ))?;
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_register_ops! {
+    "8" u8: in_8 out_8 move_in_8 move_out_8 => in8_ex out8_ex move_in8_ex move_out8_ex;
+    "16" u16: in_16 out_16 move_in_16 move_out_16 => in16_ex out16_ex move_in16_ex move_out16_ex;
+    "32" u32: in_32 out_32 move_in_32 move_out_32 => in32_ex out32_ex move_in32_ex move_out32_ex;
+    "64" u64: in_64 out_64 move_in_64 move_out_64 => in64_ex out64_ex move_in64_ex move_out64_ex;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::MockResource,
+        enums::{
+            attribute::{self, HasAttribute},
+            event::{self},
+            status::ErrorCode,
+        },
+        Error, TIMEOUT_IMMEDIATE,
+    };
+
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn wait_on_several_events() -> Result<()> {
+        let (dev, _rm, instr) = MockResource::new("VXI0::7::INSTR").open()?;
+        let (srq, trig, clear) = (
+            event::EventKind::EventServiceReq,
+            event::EventKind::EventTrig,
+            event::EventKind::EventClear,
+        );
+        instr.enable_event(srq, event::Mechanism::Queue)?;
+        instr.enable_event(trig, event::Mechanism::Queue)?;
+        instr.enable_event(clear, event::Mechanism::Queue)?;
+        assert!(instr.poll_event(srq)?.is_none());
+        assert_eq!(
+            instr.wait_on_events(&[], TIMEOUT_IMMEDIATE).map(|_| ()),
+            Err(Error(ErrorCode::ErrorInvEvent))
+        );
+        assert_eq!(
+            instr
+                .wait_on_events(&[srq, trig], Duration::from_millis(5))
+                .map(|_| ()),
+            Err(Error(ErrorCode::ErrorTmo))
+        );
+
+        dev.raise_event(clear);
+        dev.raise_event(trig);
+        dev.raise_event(srq);
+        assert_eq!(instr.wait_on_events(&[srq, trig], TIMEOUT_IMMEDIATE)?, srq);
+        assert_eq!(instr.wait_on_events(&[srq, trig], TIMEOUT_IMMEDIATE)?, trig);
+        // other kinds dequeued while waiting are discarded
+        assert_eq!(
+            instr
+                .wait_on_events(&[srq, trig], TIMEOUT_IMMEDIATE)
+                .map(|_| ()),
+            Err(Error(ErrorCode::ErrorTmo))
+        );
+        assert!(instr.poll_event(clear)?.is_none());
+        dev.raise_event(clear);
+        assert_eq!(
+            instr.wait_on_events(
+                &[srq, event::EventKind::AllEnabledEvents],
+                TIMEOUT_IMMEDIATE
+            )?,
+            clear
+        );
+
+        // kinds are enabled as needed
+        let io = event::EventKind::EventIoCompletion;
+        assert_eq!(
+            instr.poll_event(io).map(|_| ()),
+            Err(Error(ErrorCode::ErrorNenabled))
+        );
+        assert_eq!(
+            instr
+                .wait_on_events(&[srq, io], TIMEOUT_IMMEDIATE)
+                .map(|_| ()),
+            Err(Error(ErrorCode::ErrorTmo))
+        );
+        assert!(instr.poll_event(io)?.is_none());
+
+        // blocks until an event arrives, a timeout past the range of `Instant` is infinite
+        std::thread::scope(|s| -> Result<()> {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                dev.raise_event(clear);
+                dev.raise_event(trig);
+            });
+            assert_eq!(instr.wait_on_events(&[srq, trig], Duration::MAX)?, trig);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn register_io() -> Result<()> {
+        use crate::enums::memory::{AddressSpace, DataWidth};
+        let (dev, _rm, instr) = MockResource::new("VXI0::6::INSTR")
+            .with_memory(AddressSpace::A16Space, 0xC000, [0x12, 0x34, 0x56, 0x78])
+            .with_memory(AddressSpace::A24Space, 0x20_0000, [0; 16])
+            .open()?;
+
+        assert_eq!(instr.in_8(AddressSpace::A16Space, 0xC001)?, 0x34);
+        assert_eq!(instr.in_16(AddressSpace::A16Space, 0xC000)?, 0x1234);
+        assert_eq!(instr.in_32(AddressSpace::A16Space, 0xC000)?, 0x12345678);
+        assert_eq!(
+            instr.in_64(AddressSpace::A16Space, 0xC000),
+            Err(Error(ErrorCode::ErrorBerr))
+        );
+        instr.set_attr(attribute::AttrSrcByteOrder::VI_LITTLE_ENDIAN)?;
+        assert_eq!(instr.in_16(AddressSpace::A16Space, 0xC002)?, 0x7856);
+
+        instr.out_32(AddressSpace::A24Space, 0x20_0000, 0xDEADBEEF)?;
+        assert_eq!(
+            dev.memory(AddressSpace::A24Space, 0x20_0000, 4),
+            Some(vec![0xDE, 0xAD, 0xBE, 0xEF])
+        );
+        instr.move_out_16(AddressSpace::A24Space, 0x20_0004, &[1, 2, 3])?;
+        let mut words = [0u16; 3];
+        instr.move_in_16(AddressSpace::A24Space, 0x20_0004, &mut words)?;
+        assert_eq!(words, [0x0100, 0x0200, 0x0300]);
+
+        instr.set_attr(attribute::AttrSrcIncrement::new_checked(0).unwrap())?;
+        instr.move_in_16(AddressSpace::A24Space, 0x20_0004, &mut words)?;
+        assert_eq!(words, [0x0100; 3]);
+
+        instr.visa_move(
+            AddressSpace::A16Space,
+            0xC000,
+            AddressSpace::A24Space,
+            0x20_000C,
+            DataWidth::Width8,
+            1,
+        )?;
+        assert_eq!(
+            dev.memory(AddressSpace::A24Space, 0x20_000C, 1),
+            Some(vec![0x12])
+        );
         Ok(())
     }
 }
@@ -10,6 +10,8 @@
 //!
 //! On Windows, the default installation path will be added if no path is specified.
 //!
+//! With feature `dynamic-load` nothing is linked at build time, the library is opened at runtime instead, see module `library`.
+//!
 //! # Example
 //!
 //! Codes below will find the first Keysight instrument in your environment and print out its `*IDN?` response.
@@ -17,7 +19,6 @@
 //! ```no_run
 //! fn main() -> visa_rs::Result<()>{
 //!     use std::ffi::CString;
-//!     use std::io::{BufRead, BufReader, Read, Write};
 //!     use visa_rs::prelude::*;
 //!
 //!     // open default resource manager
@@ -32,19 +33,15 @@
 //!     // open a session to the resource, the session will be closed when rm is dropped
 //!     let instr: Instrument = rm.open(&rsc, AccessMode::NO_LOCK, TIMEOUT_IMMEDIATE)?;
 //!
-//!     // write message
-//!     (&instr).write_all(b"*IDN?\n").map_err(io_to_vs_err)?;
-//!
-//!     // read response
-//!     let mut buf_reader = BufReader::new(&instr);
-//!     let mut buf = String::new();
-//!     buf_reader.read_line(&mut buf).map_err(io_to_vs_err)?;
+//!     // write message and read response, terminated according to the session settings
+//!     let idn = instr.query("*IDN?")?;
 //!
-//!     eprintln!("{}", buf);
+//!     eprintln!("{}", idn);
 //!     Ok(())
 //! }
 //! ```
 
+use backend::{Backend, Visa};
 use enums::{attribute, event};
 use std::ffi::CStr;
 use std::{borrow::Cow, ffi::CString, fmt::Display, time::Duration};
@@ -53,16 +50,38 @@ pub use visa_sys as vs;
 mod async_io;
 #[cfg(feature = "tokio")]
 mod async_tokio;
+pub mod backend;
+pub mod block;
+pub mod detailed;
 pub mod enums;
+mod event_stream;
+mod exception;
 pub mod flags;
+pub mod formatted;
 pub mod handler;
 mod instrument;
+#[cfg(feature = "dynamic-load")]
+pub mod library;
+mod lock;
 pub mod prelude;
+mod query;
+pub mod serial;
 pub mod session;
+mod snapshot;
+pub mod tcpip;
+mod trigger;
+pub mod usb;
+mod window;
 
 #[cfg(feature = "tokio")]
 pub use async_tokio::InstrumentTokioAdapter;
+pub use event_stream::EventStream;
+pub use exception::{ExceptionHook, ExceptionInfo};
 pub use instrument::Instrument;
+pub use lock::{LockGuard, SharedLockGuard};
+pub use snapshot::Snapshot;
+pub use trigger::TriggerMapping;
+pub use window::MappedWindow;
 
 use session::{AsRawSs, AsSs, FromRawSs, IntoRawSs, OwnedSs};
 
@@ -71,25 +90,26 @@ pub const TIMEOUT_INFINITE: Duration = Duration::from_millis(vs::VI_TMO_INFINITE
 macro_rules! impl_session_traits {
     ($($id:ident),* $(,)?) => {
         $(
-            impl IntoRawSs for $id {
+            impl<B: Backend> IntoRawSs for $id<B> {
                 fn into_raw_ss(self) -> session::RawSs {
                     self.0.into_raw_ss()
                 }
             }
 
-            impl AsRawSs for $id {
+            impl<B: Backend> AsRawSs for $id<B> {
+                type Backend = B;
                 fn as_raw_ss(&self) -> session::RawSs {
                     self.0.as_raw_ss()
                 }
             }
 
-            impl AsSs for $id {
-                fn as_ss(&self) -> session::BorrowedSs<'_> {
+            impl<B: Backend> AsSs for $id<B> {
+                fn as_ss(&self) -> session::BorrowedSs<'_, B> {
                     self.0.as_ss()
                 }
             }
 
-            impl FromRawSs for $id {
+            impl<B: Backend> FromRawSs for $id<B> {
                 unsafe fn from_raw_ss(s: session::RawSs) -> Self {
                     Self(FromRawSs::from_raw_ss(s))
                 }
@@ -101,14 +121,15 @@ macro_rules! impl_session_traits {
 macro_rules! impl_session_traits_for_borrowed {
     ($($id:ident),* $(,)?) => {
         $(
-            impl AsRawSs for $id<'_> {
+            impl<B: Backend> AsRawSs for $id<'_, B> {
+                type Backend = B;
                 fn as_raw_ss(&self) -> session::RawSs {
                     self.0.as_raw_ss()
                 }
             }
 
-            impl AsSs for $id<'_> {
-                fn as_ss(&self) -> session::BorrowedSs<'_> {
+            impl<B: Backend> AsSs for $id<'_, B> {
+                fn as_ss(&self) -> session::BorrowedSs<'_, B> {
                     self.0.as_ss()
                 }
             }
@@ -167,44 +188,68 @@ impl TryFrom<std::io::Error> for Error {
     }
 }
 
-/// Quickly convert [std::io::Error].
-///
-///  # Panics
+/// Recovers the [`Error`] carried by an [`std::io::Error`] returned by the [`Read`](std::io::Read)/[`Write`](std::io::Write) implementations of [`Instrument`].
 ///
-/// Panic if the input Error is not converted from [visa_rs::Error](Error), use [TryInto] to perform conversion,
-/// the io error must be generated from [Instrument] IO ops
-pub fn io_to_vs_err(e: std::io::Error) -> Error {
-    e.try_into().unwrap()
-}
-
-fn vs_to_io_err(err: Error) -> std::io::Error {
-    use enums::status::ErrorCode::*;
-    use std::io::ErrorKind::*;
-    std::io::Error::new(
-        match err.0 {
-            ErrorInvObject => AddrNotAvailable,
-            ErrorNsupOper => Unsupported,
-            ErrorRsrcLocked => ConnectionRefused,
-            ErrorTmo => TimedOut,
-            ErrorRawWrProtViol | ErrorRawRdProtViol => InvalidData,
-            ErrorInpProtViol | ErrorOutpProtViol => BrokenPipe,
-            ErrorBerr => BrokenPipe,
-            ErrorInvSetup => InvalidInput,
-            ErrorNcic => PermissionDenied,
-            ErrorNlisteners => Other,
-            ErrorAsrlParity | ErrorAsrlFraming => Other,
-            ErrorAsrlOverrun => Other,
-            ErrorConnLost => BrokenPipe,
-            ErrorInvMask => InvalidInput,
-            ErrorIo => std::io::Error::last_os_error().kind(),
-            _ => unreachable!(),
-        },
-        err,
-    )
+/// Returns the input back if it doesn't carry one, e.g. an error from another I/O source.
+pub fn io_to_vs_err(e: std::io::Error) -> std::result::Result<Error, std::io::Error> {
+    e.try_into()
+}
+
+impl From<Error> for std::io::Error {
+    /// The kind is given by [`ErrorCode::io_error_kind`](enums::status::ErrorCode::io_error_kind), the [`Error`] is kept as the inner error
+    fn from(err: Error) -> Self {
+        std::io::Error::new(err.0.io_error_kind(), err)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Value of a successful operation along with the [`CompletionCode`](enums::status::CompletionCode) it returned.
+///
+/// Returned by the `*_outcome` variants of methods, for when the code matters, e.g. [`SuccessNestedExclusive`](enums::status::CompletionCode::SuccessNestedExclusive) from [`Instrument::lock_outcome`]
+/// or [`WarnNsupAttrState`](enums::status::CompletionCode::WarnNsupAttrState) from [`HasAttribute::set_attr_outcome`](enums::attribute::HasAttribute::set_attr_outcome).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Outcome<T> {
+    pub value: T,
+    pub code: enums::status::CompletionCode,
+}
+
+impl<T> Outcome<T> {
+    pub fn new(value: T, code: enums::status::CompletionCode) -> Self {
+        Self { value, code }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Outcome<U> {
+        Outcome::new(f(self.value), self.code)
+    }
+
+    /// see [`CompletionCode::is_warning`](enums::status::CompletionCode::is_warning)
+    pub fn is_warning(&self) -> bool {
+        self.code.is_warning()
+    }
+}
+
+impl Outcome<usize> {
+    /// Whether a read stopped because END was received, `VI_SUCCESS`
+    pub fn end_received(&self) -> bool {
+        self.code == enums::status::CompletionCode::Success
+    }
+
+    /// Whether a read stopped on the termination character `VI_ATTR_TERMCHAR`, `VI_SUCCESS_TERM_CHAR`
+    pub fn term_char_received(&self) -> bool {
+        self.code == enums::status::CompletionCode::SuccessTermChar
+    }
+
+    /// Whether a read stopped because the buffer is full, `VI_SUCCESS_MAX_CNT`, so more data may be pending
+    pub fn max_count_reached(&self) -> bool {
+        self.code == enums::status::CompletionCode::SuccessMaxCnt
+    }
+}
+
 impl From<enums::attribute::AttrStatus> for Result<enums::status::CompletionCode> {
     fn from(a: enums::attribute::AttrStatus) -> Self {
         match a.into_inner() {
@@ -228,10 +273,13 @@ macro_rules! wrap_raw_error_in_unsafe {
                     )),
                 )
             }
-            e => $crate::Result::<$crate::enums::status::CompletionCode>::Err(
-                e.try_into()
-                    .expect(&format!("Converting `{e}({e:#0X})` to ErrorCode failed")),
-            ),
+            e => {
+                $crate::detailed::record_failure(e, stringify!($s));
+                $crate::Result::<$crate::enums::status::CompletionCode>::Err(
+                    e.try_into()
+                        .expect(&format!("Converting `{e}({e:#0X})` to ErrorCode failed")),
+                )
+            }
         }
     };
 }
@@ -279,11 +327,11 @@ pub trait AsResourceManager: AsRawSs {
     ///
     /// see also [official doc](https://www.ni.com/docs/en-US/bundle/ni-visa-20.0/page/ni-visa/vifindrsrc.html)
     ///
-    fn find_res_list(&self, expr: &ResID) -> Result<ResList> {
+    fn find_res_list(&self, expr: &ResID) -> Result<ResList<Self::Backend>> {
         let mut list: vs::ViFindList = 0;
         let mut cnt: vs::ViUInt32 = 0;
         let mut instr_desc = new_visa_buf();
-        wrap_raw_error_in_unsafe!(vs::viFindRsrc(
+        wrap_raw_error_in_unsafe!(Self::Backend::find_rsrc(
             self.as_raw_ss(),
             expr.as_vi_const_string(),
             &mut list,
@@ -291,8 +339,10 @@ pub trait AsResourceManager: AsRawSs {
             instr_desc.as_mut_ptr() as _,
         ))?;
         Ok(ResList {
-            list,
-            cnt: cnt as _,
+            list: unsafe { OwnedSs::from_raw_ss(list) },
+            total: cnt as _,
+            remaining: cnt as _,
+            desc_ready: true,
             instr_desc,
         })
     }
@@ -322,7 +372,7 @@ pub trait AsResourceManager: AsRawSs {
     fn parse_res(&self, res: &ResID) -> Result<(attribute::AttrIntfType, attribute::AttrIntfNum)> {
         let mut ty = 0;
         let mut num = 0;
-        wrap_raw_error_in_unsafe!(vs::viParseRsrc(
+        wrap_raw_error_in_unsafe!(Self::Backend::parse_rsrc(
             self.as_raw_ss(),
             res.as_vi_const_string(),
             &mut ty as *mut _,
@@ -360,7 +410,7 @@ pub trait AsResourceManager: AsRawSs {
         let mut str1 = new_visa_buf();
         let mut str2 = new_visa_buf();
         let mut str3 = new_visa_buf();
-        wrap_raw_error_in_unsafe!(vs::viParseRsrcEx(
+        wrap_raw_error_in_unsafe!(Self::Backend::parse_rsrc_ex(
             self.as_raw_ss(),
             res.as_vi_const_string(),
             &mut ty as *mut _,
@@ -396,9 +446,9 @@ pub trait AsResourceManager: AsRawSs {
         res_name: &ResID,
         access_mode: flags::AccessMode,
         open_timeout: Duration,
-    ) -> Result<Instrument> {
+    ) -> Result<Instrument<Self::Backend>> {
         let mut instr: vs::ViSession = 0;
-        wrap_raw_error_in_unsafe!(vs::viOpen(
+        wrap_raw_error_in_unsafe!(Self::Backend::open(
             self.as_raw_ss(),
             res_name.as_vi_const_string(),
             access_mode.bits(),
@@ -410,24 +460,24 @@ pub trait AsResourceManager: AsRawSs {
 
     /// Close this session and all find lists and device sessions.
     fn close_all(&self) {
-        std::mem::drop(unsafe { DefaultRM::from_raw_ss(self.as_raw_ss()) })
+        std::mem::drop(unsafe { DefaultRM::<Self::Backend>::from_raw_ss(self.as_raw_ss()) })
     }
 }
 
-impl<'a> AsResourceManager for WeakRM<'a> {}
-impl AsResourceManager for DefaultRM {}
+impl<'a, B: Backend> AsResourceManager for WeakRM<'a, B> {}
+impl<B: Backend> AsResourceManager for DefaultRM<B> {}
 
 /// A [`ResourceManager`](AsResourceManager) which is [`Clone`] and doesn't close everything on drop
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub struct WeakRM<'a>(session::BorrowedSs<'a>);
+pub struct WeakRM<'a, B: Backend = Visa>(session::BorrowedSs<'a, B>);
 
-impl<'a> From<&'a attribute::AttrRmSession> for WeakRM<'a> {
+impl<'a, B: Backend> From<&'a attribute::AttrRmSession> for WeakRM<'a, B> {
     fn from(value: &'a attribute::AttrRmSession) -> Self {
         Self(unsafe { session::BorrowedSs::borrow_raw(value.clone().into_inner()) })
     }
 }
 
-impl From<attribute::AttrRmSession> for WeakRM<'static> {
+impl<B: Backend> From<attribute::AttrRmSession> for WeakRM<'static, B> {
     fn from(value: attribute::AttrRmSession) -> Self {
         Self(unsafe { session::BorrowedSs::borrow_raw(value.into_inner()) })
     }
@@ -435,14 +485,14 @@ impl From<attribute::AttrRmSession> for WeakRM<'static> {
 
 /// A [`ResourceManager`](AsResourceManager) which close everything on drop
 #[derive(Debug, PartialEq, Eq, Hash)]
-pub struct DefaultRM(session::OwnedSs);
+pub struct DefaultRM<B: Backend = Visa>(session::OwnedSs<B>);
 
-impl DefaultRM {
+impl<B: Backend> DefaultRM<B> {
     /// [`DefaultRM`] will close everything opened by it on drop.
     /// By converting to a [`WeakRM`], such behavior can be avoided.
     ///
     /// *Note*: Sessions opened by another resource manager (get from another call to [`Self::new`]) won't be influenced.
-    pub fn leak(self) -> WeakRM<'static> {
+    pub fn leak(self) -> WeakRM<'static, B> {
         unsafe { WeakRM(session::BorrowedSs::borrow_raw(self.into_raw_ss())) }
     }
 
@@ -450,32 +500,48 @@ impl DefaultRM {
     /// By converting to a [`WeakRM`], such behavior can be avoided.
     ///
     /// *Note*: Sessions opened by another resource manager (get from another call to [`Self::new`]) won't be influenced.
-    pub fn borrow(&'_ self) -> WeakRM<'_> {
+    pub fn borrow(&'_ self) -> WeakRM<'_, B> {
         WeakRM(self.as_ss())
     }
 
+    /// Returns a session to the Default Resource Manager resource of backend `B`.
+    ///
+    /// Same as [`DefaultRM::new`] for other [`Backend`]s, e.g. `DefaultRM::<Mock>::with_backend()`.
+    pub fn with_backend() -> Result<Self> {
+        let mut new: vs::ViSession = 0;
+        wrap_raw_error_in_unsafe!(B::open_default_rm(&mut new as _))?;
+        Ok(Self(unsafe { OwnedSs::from_raw_ss(new) }))
+    }
+}
+
+impl DefaultRM {
     /// Returns a session to the Default Resource Manager resource.
     ///
     /// The first call to this function initializes the VISA system, including the Default Resource Manager resource, and also returns a session to that resource. Subsequent calls to this function return unique sessions to the same Default Resource Manager resource.
     ///
     /// When a Resource Manager session is dropped, not only is that session closed, but also all find lists and device sessions (which that Resource Manager session was used to create) are closed.
     ///
+    /// With feature `dynamic-load`, the VISA library is loaded by the first call, returning [`ErrorLibraryNfound`](enums::status::ErrorCode::ErrorLibraryNfound) if no candidate is usable.
+    ///
     pub fn new() -> Result<Self> {
-        let mut new: vs::ViSession = 0;
-        wrap_raw_error_in_unsafe!(vs::viOpenDefaultRM(&mut new as _))?;
-        Ok(Self(unsafe { OwnedSs::from_raw_ss(new) }))
+        Self::with_backend()
     }
 }
 
 /// Returned by [`DefaultRM::find_res_list`], handler to iterator over matched resources
+///
+/// The find list is closed when dropped, its attributes such as `VI_ATTR_RM_SESSION` can be queried while it's alive.
 #[derive(Debug)]
-pub struct ResList {
-    list: vs::ViFindList,
-    cnt: i32,
+pub struct ResList<B: Backend = Visa> {
+    list: OwnedSs<B>,
+    total: usize,
+    remaining: usize,
+    /// `instr_desc` holds a resource not returned yet
+    desc_ready: bool,
     instr_desc: VisaBuf,
 }
 
-impl Iterator for ResList {
+impl<B: Backend> Iterator for ResList<B> {
     type Item = Result<ResID>;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -484,23 +550,55 @@ impl Iterator for ResList {
             Err(e) => Some(Err(e)),
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+
+    /// Number of resources not returned yet, without calling viFindNext
+    fn count(self) -> usize {
+        self.remaining
+    }
 }
 
-impl ResList {
+impl<B: Backend> ExactSizeIterator for ResList<B> {}
+
+impl<B: Backend> std::iter::FusedIterator for ResList<B> {}
+
+impl<B: Backend> ResList<B> {
     /// Returns the next resource from the list of resources found
+    ///
+    /// A failed viFindNext is returned in place of the resource it should have found, so the list always yields [`total`](Self::total) items.
     pub fn find_next(&mut self) -> Result<Option<ResID>> {
-        if self.cnt < 1 {
+        if self.remaining == 0 {
             return Ok(None);
         }
-        let next: ResID = self.instr_desc.try_into().unwrap();
-        if self.cnt > 1 {
-            wrap_raw_error_in_unsafe!(vs::viFindNext(
-                self.list,
+        self.remaining -= 1;
+        if !std::mem::take(&mut self.desc_ready) {
+            wrap_raw_error_in_unsafe!(B::find_next(
+                self.list.as_raw_ss(),
                 self.instr_desc.as_mut_ptr() as _
             ))?;
         }
-        self.cnt -= 1;
-        Ok(Some(next))
+        Ok(Some(self.instr_desc.try_into().unwrap()))
+    }
+
+    /// Total number of resources matched, as reported by viFindRsrc, including those already returned
+    pub fn total(&self) -> usize {
+        self.total
+    }
+}
+
+impl<B: Backend> AsRawSs for ResList<B> {
+    type Backend = B;
+    fn as_raw_ss(&self) -> session::RawSs {
+        self.list.as_raw_ss()
+    }
+}
+
+impl<B: Backend> AsSs for ResList<B> {
+    fn as_ss(&self) -> session::BorrowedSs<'_, B> {
+        self.list.as_ss()
     }
 }
 
@@ -610,7 +708,10 @@ impl PartialEq<enums::attribute::AttrJobId> for JobID {
 
 #[cfg(test)]
 mod test {
+    use crate::backend::mock::MockResource;
+    use crate::enums::attribute::HasAttribute;
     use crate::enums::status::{CompletionCode, ErrorCode};
+    use crate::flags::AccessMode;
     use crate::*;
 
     #[test]
@@ -648,6 +749,18 @@ mod test {
     fn convert_wrong_status2() {
         ErrorCode::try_from(CompletionCode::Success as vs::ViStatus).unwrap();
     }
+    #[test]
+    fn io_error_round_trip() {
+        let codes: Vec<_> = (0xBFFF0000u32..=0xBFFF0FFF)
+            .filter_map(|s| ErrorCode::try_from(s as vs::ViStatus).ok())
+            .collect();
+        assert!(codes.contains(&ErrorCode::ErrorTmo));
+        for code in codes {
+            let io_err: std::io::Error = Error(code).into();
+            assert_eq!(io_err.kind(), code.io_error_kind());
+            assert_eq!(io_to_vs_err(io_err).unwrap(), Error(code));
+        }
+    }
 
     use anyhow::{bail, Result};
     #[test]
@@ -672,7 +785,7 @@ mod test {
         assert_ne!(rm1, rm2);
         std::mem::drop(rm1);
         let expr = CString::new("?*").unwrap().into();
-        match unsafe { DefaultRM::from_raw_ss(r1).leak() }.find_res(&expr) {
+        match unsafe { <DefaultRM>::from_raw_ss(r1).leak() }.find_res(&expr) {
             Err(crate::Error(crate::enums::status::ErrorCode::ErrorInvObject)) => {
                 Ok::<_, crate::Error>(())
             }
@@ -696,9 +809,67 @@ mod test {
     #[test]
     fn convert_io_error() {
         let vs_error = Error(enums::status::ErrorCode::ErrorTmo);
-        let io_error = vs_to_io_err(vs_error);
+        let io_error: std::io::Error = vs_error.into();
+        assert_eq!(io_error.kind(), std::io::ErrorKind::TimedOut);
         assert_eq!(Error::try_from(io_error).unwrap(), vs_error);
         let no_vs_io_error = std::io::Error::other(FromBytesWithNulError);
         assert!(Error::try_from(no_vs_io_error).is_err());
+        let no_vs_io_error = std::io::Error::other(FromBytesWithNulError);
+        assert!(io_to_vs_err(no_vs_io_error).is_err());
+    }
+
+    #[test]
+    fn completion_codes() -> Result<()> {
+        let (dev, _rm, instr) = MockResource::new("ASRL8::INSTR").open()?;
+        let mut buf = [0u8; 4];
+        instr.set_attr(attribute::AttrTermcharEn::VI_TRUE)?;
+        dev.push_response("a\nbcdef");
+        let read = instr.read_outcome(&mut buf)?;
+        assert_eq!(read.value, 2);
+        assert!(read.term_char_received() && !read.end_received());
+        let read = instr.read_outcome(&mut buf)?;
+        assert!(read.max_count_reached());
+        assert_eq!(&buf, b"bcde");
+        let read = instr.read_outcome(&mut buf)?;
+        assert_eq!((read.value, read.end_received()), (1, true));
+        assert!(!read.is_warning());
+
+        let lock = instr.lock_outcome(AccessMode::EXCLUSIVE_LOCK, TIMEOUT_IMMEDIATE, None)?;
+        assert_eq!(lock.code, CompletionCode::Success);
+        let lock = instr.lock_outcome(AccessMode::EXCLUSIVE_LOCK, TIMEOUT_IMMEDIATE, None)?;
+        assert_eq!(lock.code, CompletionCode::SuccessNestedExclusive);
+        assert_eq!(
+            instr.unlock_outcome()?.code,
+            CompletionCode::SuccessNestedExclusive
+        );
+        assert_eq!(instr.unlock_outcome()?.code, CompletionCode::Success);
+
+        let kind = event::EventKind::EventServiceReq;
+        instr.enable_event(kind, event::Mechanism::Queue)?;
+        assert_eq!(
+            instr
+                .enable_event_outcome(kind, event::Mechanism::Queue)?
+                .code,
+            CompletionCode::SuccessEventEn
+        );
+        dev.raise_event(kind);
+        dev.raise_event(kind);
+        let ev = instr.wait_on_event_outcome(kind, TIMEOUT_IMMEDIATE)?;
+        assert_eq!(ev.code, CompletionCode::SuccessQueueNempty);
+        assert_eq!(ev.value.kind(), kind);
+        instr.disable_event(kind, event::Mechanism::Queue)?;
+        assert_eq!(
+            instr
+                .disable_event_outcome(kind, event::Mechanism::Queue)?
+                .code,
+            CompletionCode::SuccessEventDis
+        );
+        assert_eq!(
+            instr
+                .set_attr_outcome(attribute::AttrTermcharEn::VI_FALSE)?
+                .code,
+            CompletionCode::Success
+        );
+        Ok(())
     }
 }
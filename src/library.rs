@@ -0,0 +1,378 @@
+//!
+//! Runtime loading of the VISA library, available with feature `dynamic-load`.
+//!
+//! With this feature the VISA library is not linked at build time, it is opened the first time a [`DefaultRM`](crate::DefaultRM) is created,
+//! or explicitly by [`load`] / [`LibraryLoader::load`].
+//! Candidates are tried in order, the first library exporting every function used by [`Visa`](crate::backend::Visa) wins.
+//!
+//! If no candidate could be loaded, [`DefaultRM::new`](crate::DefaultRM::new) returns `VI_ERROR_LIBRARY_NFOUND`,
+//! call [`load`] beforehand to get a [`LibraryNotAvailable`] listing why each candidate was rejected.
+//!
+//! ```no_run
+//! use visa_rs::library::LibraryLoader;
+//!
+//! match LibraryLoader::new()
+//!     .path("/opt/keysight/iolibs/libktvisa32.so")
+//!     .default_paths()
+//!     .load()
+//! {
+//!     Ok(()) => println!("loaded {:?}", visa_rs::library::loaded_path()),
+//!     Err(e) => eprintln!("running without VISA: {}", e),
+//! }
+//! ```
+//!
+
+use std::{
+    ffi::{OsStr, OsString},
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+use visa_sys as vs;
+
+/// Environment variable holding the file name of the library, or the link name used at build time (e.g. `visa64`)
+pub const LIB_VISA_NAME: &str = "LIB_VISA_NAME";
+/// Environment variable holding the directory containing the library, or the full path of it
+pub const LIB_VISA_PATH: &str = "LIB_VISA_PATH";
+
+static LOADED: OnceLock<PathBuf> = OnceLock::new();
+static LOADING: Mutex<()> = Mutex::new(());
+
+macro_rules! missing_symbols {
+    ($lib:expr, $($f:ident),* $(,)?) => {{
+        let mut missing = Vec::new();
+        $(
+            if $lib.$f.is_err() {
+                missing.push(stringify!($f));
+            }
+        )*
+        missing
+    }};
+}
+
+/// Returns the name of every function used by [`Visa`](crate::backend::Visa) that the library doesn't export
+fn missing_functions(lib: &vs::LibVisa) -> Vec<&'static str> {
+    missing_symbols!(
+        lib,
+        viOpenDefaultRM,
+        viFindRsrc,
+        viFindNext,
+        viParseRsrc,
+        viParseRsrcEx,
+        viOpen,
+        viClose,
+        viSetAttribute,
+        viGetAttribute,
+        viStatusDesc,
+        viTerminate,
+        viLock,
+        viUnlock,
+        viEnableEvent,
+        viDisableEvent,
+        viDiscardEvents,
+        viWaitOnEvent,
+        viInstallHandler,
+        viUninstallHandler,
+        viRead,
+        viReadAsync,
+        viWrite,
+        viWriteAsync,
+        viAssertTrigger,
+        viReadSTB,
+        viClear,
+        viSetBuf,
+        viFlush,
+        viBufWrite,
+        viBufRead,
+        viGpibControlREN,
+        viGpibControlATN,
+        viGpibSendIFC,
+        viGpibCommand,
+        viGpibPassControl,
+        viAssertUtilSignal,
+        viAssertIntrSignal,
+        viIn8Ex,
+        viOut8Ex,
+        viIn16Ex,
+        viOut16Ex,
+        viIn32Ex,
+        viOut32Ex,
+        viIn64Ex,
+        viOut64Ex,
+        viMoveIn8Ex,
+        viMoveOut8Ex,
+        viMoveIn16Ex,
+        viMoveOut16Ex,
+        viMoveIn32Ex,
+        viMoveOut32Ex,
+        viMoveIn64Ex,
+        viMoveOut64Ex,
+        viMoveEx,
+        viMapAddressEx,
+        viUnmapAddress,
+        viPeek8,
+        viPoke8,
+        viPeek16,
+        viPoke16,
+        viPeek32,
+        viPoke32,
+        viPeek64,
+        viPoke64,
+        viUsbControlIn,
+        viUsbControlOut,
+        viVxiCommandQuery,
+        viMapTrigger,
+        viUnmapTrigger,
+    )
+}
+
+/// Platform default locations of VISA implementations, the VISA shared components first
+pub fn default_paths() -> &'static [&'static str] {
+    if cfg!(target_os = "windows") {
+        if cfg!(target_pointer_width = "64") {
+            &["visa64.dll", "RsVisa64.dll", "ktvisa64.dll"]
+        } else {
+            &["visa32.dll", "RsVisa32.dll", "ktvisa32.dll"]
+        }
+    } else if cfg!(target_os = "macos") {
+        &[
+            "/Library/Frameworks/VISA.framework/VISA",
+            "/Library/Frameworks/RsVisa.framework/RsVisa",
+        ]
+    } else {
+        &[
+            "libvisa.so",
+            "libvisa.so.0",
+            "librsvisa.so",
+            "/usr/lib/librsvisa.so",
+            "/opt/keysight/iolibs/libktvisa32.so",
+        ]
+    }
+}
+
+/// Candidate built from [`LIB_VISA_NAME`] and [`LIB_VISA_PATH`], `None` if neither is set
+fn env_path() -> Option<PathBuf> {
+    let name = std::env::var_os(LIB_VISA_NAME).map(|n| {
+        let as_path = Path::new(&n);
+        if as_path.extension().is_some() || as_path.components().count() > 1 {
+            n
+        } else {
+            libloading::library_filename(n)
+        }
+    });
+    let dir = std::env::var_os(LIB_VISA_PATH).map(PathBuf::from);
+    match (dir, name) {
+        (Some(dir), name) if dir.is_dir() => Some(dir.join(name.unwrap_or_else(|| {
+            default_paths()
+                .first()
+                .map(|p| Path::new(p).file_name().unwrap_or_default().to_owned())
+                .unwrap_or_default()
+        }))),
+        (Some(file), _) => Some(file),
+        (None, name) => name.map(PathBuf::from),
+    }
+}
+
+/// Why a candidate was rejected
+#[derive(Debug)]
+pub enum LoadFailure {
+    /// The library could not be opened
+    Open(libloading::Error),
+    /// The library was opened but lacks some VISA functions
+    MissingFunctions(Vec<&'static str>),
+}
+
+impl Display for LoadFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadFailure::Open(e) => e.fmt(f),
+            LoadFailure::MissingFunctions(m) => write!(f, "missing functions {}", m.join(", ")),
+        }
+    }
+}
+
+/// No VISA library could be loaded, see [`tried`](Self::tried) for the reason of each candidate
+#[derive(Debug)]
+pub struct LibraryNotAvailable {
+    tried: Vec<(PathBuf, LoadFailure)>,
+}
+
+impl LibraryNotAvailable {
+    /// Candidates tried in order with the reason each one was rejected
+    pub fn tried(&self) -> &[(PathBuf, LoadFailure)] {
+        &self.tried
+    }
+}
+
+impl Display for LibraryNotAvailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "VISA library not available")?;
+        if self.tried.is_empty() {
+            return write!(f, ", no candidate path");
+        }
+        for (path, e) in &self.tried {
+            write!(f, "\n  {}: {}", path.display(), e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LibraryNotAvailable {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.tried.iter().find_map(|(_, e)| match e {
+            LoadFailure::Open(e) => Some(e as _),
+            LoadFailure::MissingFunctions(_) => None,
+        })
+    }
+}
+
+impl From<LibraryNotAvailable> for crate::Error {
+    fn from(_: LibraryNotAvailable) -> Self {
+        Self(crate::enums::status::ErrorCode::ErrorLibraryNfound)
+    }
+}
+
+/// Configures the candidates tried when loading the VISA library.
+///
+/// [`LIB_VISA_NAME`] and [`LIB_VISA_PATH`] are read at runtime and tried before any added path, unless [`ignore_env`](Self::ignore_env) is called.
+#[derive(Debug, Clone)]
+pub struct LibraryLoader {
+    paths: Vec<OsString>,
+    env: bool,
+}
+
+impl Default for LibraryLoader {
+    /// Environment variables, then [`default_paths`]
+    fn default() -> Self {
+        Self::new().default_paths()
+    }
+}
+
+impl LibraryLoader {
+    /// A loader trying only the environment variables
+    pub fn new() -> Self {
+        Self {
+            paths: Vec::new(),
+            env: true,
+        }
+    }
+
+    /// Don't read [`LIB_VISA_NAME`] and [`LIB_VISA_PATH`]
+    pub fn ignore_env(mut self) -> Self {
+        self.env = false;
+        self
+    }
+
+    /// Append a candidate, either a file name searched by the platform loader or a full path
+    pub fn path(mut self, path: impl AsRef<OsStr>) -> Self {
+        self.paths.push(path.as_ref().to_owned());
+        self
+    }
+
+    /// Append several candidates
+    pub fn paths(mut self, paths: impl IntoIterator<Item = impl AsRef<OsStr>>) -> Self {
+        self.paths
+            .extend(paths.into_iter().map(|p| p.as_ref().to_owned()));
+        self
+    }
+
+    /// Append [`default_paths`]
+    pub fn default_paths(self) -> Self {
+        self.paths(default_paths())
+    }
+
+    /// Candidates in the order they will be tried
+    pub fn candidates(&self) -> Vec<PathBuf> {
+        self.env
+            .then(env_path)
+            .flatten()
+            .into_iter()
+            .chain(self.paths.iter().map(PathBuf::from))
+            .collect()
+    }
+
+    /// Load the first usable candidate.
+    ///
+    /// The first library loaded in the process is kept,
+    /// if one is already loaded this returns `Ok(())` without trying any candidate.
+    pub fn load(&self) -> Result<(), LibraryNotAvailable> {
+        if LOADED.get().is_some() {
+            return Ok(());
+        }
+        let _guard = LOADING.lock().unwrap_or_else(|e| e.into_inner());
+        if LOADED.get().is_some() {
+            return Ok(());
+        }
+        let mut tried = Vec::new();
+        for path in self.candidates() {
+            match try_open(&path) {
+                Ok(()) => match vs::load_visa_library_from_path(&path) {
+                    Ok(()) => {
+                        log::debug!("loaded VISA library {}", path.display());
+                        let _ = LOADED.set(path);
+                        return Ok(());
+                    }
+                    Err(e) => tried.push((path, LoadFailure::Open(e))),
+                },
+                Err(e) => {
+                    log::debug!("skipped VISA library {}: {}", path.display(), e);
+                    tried.push((path, e))
+                }
+            }
+        }
+        Err(LibraryNotAvailable { tried })
+    }
+}
+
+fn try_open(path: &Path) -> Result<(), LoadFailure> {
+    let lib = unsafe { vs::LibVisa::new(path) }.map_err(LoadFailure::Open)?;
+    let missing = missing_functions(&lib);
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(LoadFailure::MissingFunctions(missing))
+    }
+}
+
+/// Load the VISA library from the environment variables or [`default_paths`], see [`LibraryLoader::load`]
+pub fn load() -> Result<(), LibraryNotAvailable> {
+    LibraryLoader::default().load()
+}
+
+/// Path of the loaded VISA library, `None` if not loaded yet
+pub fn loaded_path() -> Option<&'static Path> {
+    LOADED.get().map(PathBuf::as_path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_library() {
+        let e = try_open(Path::new("nonexistent-visa-library-for-test")).unwrap_err();
+        assert!(matches!(e, LoadFailure::Open(_)));
+        let err = LibraryNotAvailable {
+            tried: vec![("nonexistent-visa-library-for-test".into(), e)],
+        };
+        assert!(err
+            .to_string()
+            .contains("nonexistent-visa-library-for-test"));
+        assert_eq!(
+            crate::Error::from(err),
+            crate::Error(crate::enums::status::ErrorCode::ErrorLibraryNfound)
+        );
+    }
+
+    #[test]
+    fn candidates_order() {
+        let loader = LibraryLoader::new()
+            .ignore_env()
+            .path("a")
+            .paths(["b", "c"]);
+        assert_eq!(
+            loader.candidates(),
+            ["a", "b", "c"].map(PathBuf::from).to_vec()
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use super::*;
+use enums::status::CompletionCode;
+
+/// An exclusive lock acquired by [`Instrument::lock_exclusive_guard`], released on drop.
+///
+/// VISA counts locks per session, so a guard acquired while the session already holds the lock is nested:
+/// dropping it only decrements the count, the resource stays locked until the outermost guard is dropped.
+#[derive(Debug)]
+#[must_use = "the lock is released as soon as the guard is dropped"]
+pub struct LockGuard<'a, B: Backend = Visa> {
+    instr: &'a Instrument<B>,
+    nested: bool,
+}
+
+/// A shared lock acquired by [`Instrument::lock_shared_guard`], released on drop.
+///
+/// The [`access_key`](Self::access_key) can be passed to other sessions to join the lock, nesting works as for [`LockGuard`].
+#[derive(Debug)]
+#[must_use = "the lock is released as soon as the guard is dropped"]
+pub struct SharedLockGuard<'a, B: Backend = Visa> {
+    instr: &'a Instrument<B>,
+    key: AccessKey,
+    nested: bool,
+}
+
+impl<'a, B: Backend> LockGuard<'a, B> {
+    /// Instrument the lock is held on
+    pub fn instrument(&self) -> &'a Instrument<B> {
+        self.instr
+    }
+
+    /// Whether the session already held an exclusive lock when this guard was acquired
+    pub fn is_nested(&self) -> bool {
+        self.nested
+    }
+
+    /// Releases the lock, reporting the error dropping would ignore
+    pub fn unlock(self) -> Result<()> {
+        let instr = self.instr;
+        std::mem::forget(self);
+        instr.unlock()
+    }
+}
+
+impl<'a, B: Backend> SharedLockGuard<'a, B> {
+    /// Instrument the lock is held on
+    pub fn instrument(&self) -> &'a Instrument<B> {
+        self.instr
+    }
+
+    /// Key other sessions can pass to [`Instrument::lock_shared_guard`] to share the lock
+    pub fn access_key(&self) -> &AccessKey {
+        &self.key
+    }
+
+    /// Whether the session already held a shared lock when this guard was acquired
+    pub fn is_nested(&self) -> bool {
+        self.nested
+    }
+
+    /// Releases the lock, reporting the error dropping would ignore
+    pub fn unlock(self) -> Result<()> {
+        let instr = self.instr;
+        std::mem::forget(self);
+        instr.unlock()
+    }
+}
+
+impl<B: Backend> Drop for LockGuard<'_, B> {
+    fn drop(&mut self) {
+        let _ = self.instr.unlock();
+    }
+}
+
+impl<B: Backend> Drop for SharedLockGuard<'_, B> {
+    fn drop(&mut self) {
+        let _ = self.instr.unlock();
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Acquires an exclusive lock, see [`Self::lock`], released when the returned guard is dropped.
+    ///
+    /// Locking again a session that holds the lock succeeds with a nested guard, see [`LockGuard::is_nested`].
+    pub fn lock_exclusive_guard(&self, timeout: Duration) -> Result<LockGuard<'_, B>> {
+        let code = self
+            .lock_outcome(flags::AccessMode::EXCLUSIVE_LOCK, timeout, None)?
+            .code;
+        Ok(LockGuard {
+            instr: self,
+            nested: code == CompletionCode::SuccessNestedExclusive,
+        })
+    }
+
+    /// Acquires a shared lock, see [`Self::lock`], released when the returned guard is dropped.
+    ///
+    /// Pass the [`access_key`](SharedLockGuard::access_key) of another guard as `key` to join its lock, or `None` to create a new one.
+    /// Locking again a session that holds the lock succeeds with a nested guard, see [`SharedLockGuard::is_nested`].
+    pub fn lock_shared_guard(
+        &self,
+        timeout: Duration,
+        key: Option<&AccessKey>,
+    ) -> Result<SharedLockGuard<'_, B>> {
+        let outcome = self.lock_outcome(flags::AccessMode::SHARED_LOCK, timeout, key.cloned())?;
+        let nested = outcome.code == CompletionCode::SuccessNestedShared;
+        let key = outcome
+            .into_inner()
+            .expect("a shared lock always returns an access key");
+        Ok(SharedLockGuard {
+            instr: self,
+            key,
+            nested,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{backend::mock::MockResource, enums::status::ErrorCode, Error, TIMEOUT_IMMEDIATE};
+    use anyhow::Result;
+    use std::io::Write;
+
+    #[test]
+    fn locking() -> Result<()> {
+        let (dev, rm, a) = MockResource::new("USB0::0x1234::0x5678::MOCKLOCK::INSTR").open()?;
+        let b = dev.open(&rm)?;
+        a.lock_exclusive(TIMEOUT_IMMEDIATE)?;
+        a.lock_exclusive(TIMEOUT_IMMEDIATE)?;
+        assert_eq!(
+            (&b).write(b"x").map_err(|e| Error::try_from(e).unwrap()),
+            Err(Error(ErrorCode::ErrorRsrcLocked))
+        );
+        a.unlock()?;
+        assert!((&b).write(b"x").is_err());
+        a.unlock()?;
+        assert_eq!(a.unlock(), Err(Error(ErrorCode::ErrorSesnNlocked)));
+        (&b).write_all(b"x")?;
+        Ok(())
+    }
+
+    #[test]
+    fn lock_guards() -> Result<()> {
+        let (dev, rm, a) = MockResource::new("USB0::0x1234::0x5678::MOCKGUARD::INSTR").open()?;
+        let b = dev.open(&rm)?;
+        let c = dev.open(&rm)?;
+        {
+            let outer = a.lock_exclusive_guard(TIMEOUT_IMMEDIATE)?;
+            assert!(!outer.is_nested());
+            {
+                let inner = a.lock_exclusive_guard(TIMEOUT_IMMEDIATE)?;
+                assert!(inner.is_nested());
+            }
+            assert_eq!(
+                (&b).write(b"x").map_err(|e| Error::try_from(e).unwrap()),
+                Err(Error(ErrorCode::ErrorRsrcLocked))
+            );
+            outer.unlock()?;
+        }
+        (&b).write_all(b"x")?;
+        assert_eq!(a.unlock(), Err(Error(ErrorCode::ErrorSesnNlocked)));
+
+        let key = {
+            let shared = a.lock_shared_guard(TIMEOUT_IMMEDIATE, None)?;
+            let joined = b.lock_shared_guard(TIMEOUT_IMMEDIATE, Some(shared.access_key()))?;
+            assert_eq!(joined.access_key(), shared.access_key());
+            assert!(!joined.is_nested());
+            assert!(a.lock_shared_guard(TIMEOUT_IMMEDIATE, None)?.is_nested());
+            assert!(c.lock_exclusive_guard(TIMEOUT_IMMEDIATE).is_err());
+            (&b).write_all(b"x")?;
+            shared.access_key().clone()
+        };
+        c.lock_exclusive_guard(TIMEOUT_IMMEDIATE)?.unlock()?;
+        let key2 = a.lock_shared(TIMEOUT_IMMEDIATE)?;
+        assert_ne!(key2, key);
+        assert_eq!(
+            b.lock_shared_with_key(TIMEOUT_IMMEDIATE, key2.clone())?,
+            key2
+        );
+        a.unlock()?;
+        b.unlock()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use super::*;
+use block::{BlockElement, ByteOrder};
+use enums::status::{CompletionCode, ErrorCode};
+use std::str::FromStr;
+
+/// Bytes requested by each viRead() of a response
+const CHUNK_SIZE: usize = 1024;
+
+/// Error for a response that can't be parsed, shared with [`formatted`](crate::formatted)
+pub(crate) fn invalid_response() -> Error {
+    Error(ErrorCode::ErrorInvFmt)
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Writes `command`, appending the termination character `VI_ATTR_TERMCHAR` if missing
+    fn write_command(&self, command: &str) -> Result<()> {
+        let termchar = self.termchar();
+        let mut msg = command.as_bytes().to_vec();
+        if msg.last() != Some(&termchar) {
+            msg.push(termchar);
+        }
+        self.write_all_raw(&msg)
+    }
+
+    /// Reads until END, or the termination character if `VI_ATTR_TERMCHAR_EN` is set, and removes the terminator
+    fn read_response(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        loop {
+            let len = buf.len();
+            buf.resize(len + CHUNK_SIZE, 0);
+            let mut ret_cnt: vs::ViUInt32 = 0;
+            let code = wrap_raw_error_in_unsafe!(B::read(
+                self.as_raw_ss(),
+                buf[len..].as_mut_ptr(),
+                CHUNK_SIZE as _,
+                &mut ret_cnt as _
+            ));
+            buf.truncate(len + ret_cnt as usize);
+            if code? != CompletionCode::SuccessMaxCnt {
+                break;
+            }
+        }
+        let termchar = self.termchar();
+        if buf.last() == Some(&termchar) {
+            buf.pop();
+            if termchar == b'\n' && buf.last() == Some(&b'\r') {
+                buf.pop();
+            }
+        }
+        Ok(buf)
+    }
+
+    /// Writes `command` and reads the response, without its terminator.
+    ///
+    /// The termination character `VI_ATTR_TERMCHAR` is appended to `command` if missing, and the write is completed with END if `VI_ATTR_SEND_END_EN` is set.
+    /// The response is read up to END, or up to the termination character if `VI_ATTR_TERMCHAR_EN` is set.
+    /// Bytes that are not UTF-8 are replaced by `U+FFFD`.
+    ///
+    /// ```no_run
+    /// # fn f(instr: &visa_rs::Instrument) -> visa_rs::Result<()> {
+    /// let idn = instr.query("*IDN?")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query(&self, command: &str) -> Result<String> {
+        self.write_command(command)?;
+        Ok(String::from_utf8_lossy(&self.read_response()?).into_owned())
+    }
+
+    /// Queries `command` and parses the response, trimmed of whitespace, as `T`.
+    ///
+    /// Returns [`ErrorInvFmt`](ErrorCode::ErrorInvFmt) if the response can't be parsed.
+    pub fn query_as<T: FromStr>(&self, command: &str) -> Result<T> {
+        self.query(command)?
+            .trim()
+            .parse()
+            .map_err(|_| invalid_response())
+    }
+
+    /// Queries `command` and parses the response as values separated by `sep`, e.g. `','`.
+    ///
+    /// Whitespace around values is ignored, an empty response gives no value.
+    /// Returns [`ErrorInvFmt`](ErrorCode::ErrorInvFmt) if a value can't be parsed.
+    pub fn query_ascii_values<T: FromStr>(&self, command: &str, sep: char) -> Result<Vec<T>> {
+        let response = self.query(command)?;
+        let response = response.trim();
+        if response.is_empty() {
+            return Ok(Vec::new());
+        }
+        response
+            .split(sep)
+            .map(|v| v.trim().parse().map_err(|_| invalid_response()))
+            .collect()
+    }
+
+    /// Queries `command` and reads the response as an IEEE 488.2 block, see [`read_binary_block`](Self::read_binary_block).
+    ///
+    /// Returns [`ErrorInvFmt`](ErrorCode::ErrorInvFmt) if the block is malformed, call [`read_binary_block`](Self::read_binary_block) to know why.
+    pub fn query_binary_values<T: BlockElement>(
+        &self,
+        command: &str,
+        order: ByteOrder,
+    ) -> Result<Vec<T>> {
+        self.write_command(command)?;
+        Ok(self.read_binary_block(order)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::MockResource,
+        enums::{
+            attribute::{self, HasAttribute},
+            status::ErrorCode,
+        },
+        Error,
+    };
+
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn query_api() -> Result<()> {
+        let (_dev, _rm, instr) = MockResource::new("GPIB0::21::INSTR")
+            .respond("*IDN?", "ACME,Model 1,0,1.0\r\n")
+            .respond("MEAS?", "+1.5E+0\n")
+            .respond("TRAC?", "1, 2,3\n")
+            .respond("CURV?", b"#14\x00\x01\x00\x02\n")
+            .respond("LIST?", "a;b\nc;d\n")
+            .open()?;
+        assert_eq!(instr.query("*IDN?")?, "ACME,Model 1,0,1.0");
+        assert_eq!(instr.query_as::<f64>("MEAS?\n")?, 1.5);
+        assert_eq!(
+            instr.query_as::<u32>("MEAS?"),
+            Err(Error(ErrorCode::ErrorInvFmt))
+        );
+        assert_eq!(instr.query_ascii_values::<u8>("TRAC?", ',')?, [1, 2, 3]);
+        assert_eq!(
+            instr.query_binary_values::<u16>("CURV?", ByteOrder::Big)?,
+            [1, 2]
+        );
+        assert_eq!(instr.query("LIST?")?, "a;b\nc;d");
+        instr.set_attr(attribute::AttrTermcharEn::VI_TRUE)?;
+        assert_eq!(
+            instr.query_ascii_values::<String>("LIST?", ';')?,
+            ["a", "b"]
+        );
+        Ok(())
+    }
+}
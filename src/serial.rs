@@ -0,0 +1,461 @@
+//! Configuration of serial (ASRL) sessions, see [`SerialConfig`]
+//!
+//! ```no_run
+//! # fn main() -> visa_rs::Result<()> {
+//! use visa_rs::{flags::AccessMode, serial::SerialConfig, AsResourceManager, DefaultRM, TIMEOUT_IMMEDIATE};
+//! let rm = DefaultRM::new()?;
+//! let instr = rm.open(
+//!     &std::ffi::CString::new("ASRL1::INSTR").unwrap().into(),
+//!     AccessMode::NO_LOCK,
+//!     TIMEOUT_IMMEDIATE,
+//! )?;
+//! let config: SerialConfig = "115200,8N1,rtscts".parse()?;
+//! instr.apply_serial_config(&config)?;
+//! assert_eq!(instr.serial_config()?, config);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    backend::Backend,
+    enums::{
+        attribute::{
+            AsrlEndIn, AsrlEndOut, AsrlFlowCntrl, AsrlParity, AsrlStopBits, AttrAsrlBaud,
+            AttrAsrlCtsState, AttrAsrlDataBits, AttrAsrlDcdState, AttrAsrlDsrState,
+            AttrAsrlDtrState, AttrAsrlEndIn, AttrAsrlEndOut, AttrAsrlFlowCntrl, AttrAsrlParity,
+            AttrAsrlRiState, AttrAsrlRtsState, AttrAsrlStopBits, AttrAsrlXoffChar, AttrAsrlXonChar,
+            HasAttribute, SpecAttr,
+        },
+        status::ErrorCode,
+    },
+    Error, Instrument,
+};
+use visa_sys as vs;
+
+/// Settings of a serial port, applied by [`Instrument::apply_serial_config`] and read by [`Instrument::serial_config`].
+///
+/// Built from the [`Default`] (the VISA defaults, `9600,8N1`) or [`new`](Self::new), or parsed from strings like `"115200,8N1,rtscts"`:
+/// the baud rate, then optionally the data bits, parity (`N`, `O`, `E`, `M` or `S`) and stop bits (`1`, `1.5` or `2`),
+/// then any of the flow controls `none`, `xonxoff`, `rtscts` and `dtrdsr`.
+/// [`Display`](fmt::Display) writes the same format, without the termination methods and XON/XOFF characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SerialConfig {
+    pub baud: u32,
+    /// From 5 to 8
+    pub data_bits: u16,
+    pub parity: AsrlParity,
+    pub stop_bits: AsrlStopBits,
+    pub flow_control: AsrlFlowCntrl,
+    /// How reads terminate, `VI_ATTR_ASRL_END_IN`
+    pub end_in: AsrlEndIn,
+    /// How writes terminate, `VI_ATTR_ASRL_END_OUT`, [`AsrlEndOut::Break`] sends a break after each write
+    pub end_out: AsrlEndOut,
+    pub xon_char: u8,
+    pub xoff_char: u8,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            baud: 9600,
+            data_bits: 8,
+            parity: AsrlParity::None,
+            stop_bits: AsrlStopBits::One,
+            flow_control: AsrlFlowCntrl::empty(),
+            end_in: AsrlEndIn::Termchar,
+            end_out: AsrlEndOut::None,
+            xon_char: 0x11,
+            xoff_char: 0x13,
+        }
+    }
+}
+
+impl SerialConfig {
+    /// `baud` with the other settings [`Default`]
+    pub fn new(baud: u32) -> Self {
+        Self {
+            baud,
+            ..Self::default()
+        }
+    }
+
+    pub fn data_bits(mut self, data_bits: u16) -> Self {
+        self.data_bits = data_bits;
+        self
+    }
+
+    pub fn parity(mut self, parity: AsrlParity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    pub fn stop_bits(mut self, stop_bits: AsrlStopBits) -> Self {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    pub fn flow_control(mut self, flow_control: AsrlFlowCntrl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    pub fn end_in(mut self, end_in: AsrlEndIn) -> Self {
+        self.end_in = end_in;
+        self
+    }
+
+    pub fn end_out(mut self, end_out: AsrlEndOut) -> Self {
+        self.end_out = end_out;
+        self
+    }
+
+    pub fn xon_xoff_chars(mut self, xon: u8, xoff: u8) -> Self {
+        self.xon_char = xon;
+        self.xoff_char = xoff;
+        self
+    }
+
+    /// Checks the settings can be used together
+    pub fn validate(&self) -> Result<(), SerialConfigError> {
+        if self.baud == 0 {
+            return Err(SerialConfigError::ZeroBaud);
+        }
+        if !(5..=8).contains(&self.data_bits) {
+            return Err(SerialConfigError::DataBits(self.data_bits));
+        }
+        // UARTs send 1.5 stop bits only with 5 data bits, and 2 with more
+        let stop_bits_valid = match self.stop_bits {
+            AsrlStopBits::One => true,
+            AsrlStopBits::One5 => self.data_bits == 5,
+            AsrlStopBits::Two => self.data_bits != 5,
+        };
+        if !stop_bits_valid {
+            return Err(SerialConfigError::StopBits {
+                data_bits: self.data_bits,
+                stop_bits: self.stop_bits,
+            });
+        }
+        if self.flow_control.contains(AsrlFlowCntrl::XON_XOFF) && self.xon_char == self.xoff_char {
+            return Err(SerialConfigError::SameXonXoff(self.xon_char));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SerialConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parity = match self.parity {
+            AsrlParity::None => 'N',
+            AsrlParity::Odd => 'O',
+            AsrlParity::Even => 'E',
+            AsrlParity::Mark => 'M',
+            AsrlParity::Space => 'S',
+        };
+        let stop_bits = match self.stop_bits {
+            AsrlStopBits::One => "1",
+            AsrlStopBits::One5 => "1.5",
+            AsrlStopBits::Two => "2",
+        };
+        write!(f, "{},{}{parity}{stop_bits}", self.baud, self.data_bits)?;
+        for (flow, name) in FLOW_NAMES {
+            if self.flow_control.contains(flow) {
+                write!(f, ",{name}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+const FLOW_NAMES: [(AsrlFlowCntrl, &str); 3] = [
+    (AsrlFlowCntrl::XON_XOFF, "xonxoff"),
+    (AsrlFlowCntrl::RTS_CTS, "rtscts"),
+    (AsrlFlowCntrl::DTR_DSR, "dtrdsr"),
+];
+
+impl FromStr for SerialConfig {
+    type Err = SerialConfigError;
+
+    /// Parses the format described in [`SerialConfig`], then [`validate`](Self::validate)s it
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let syntax = |x: &str| SerialConfigError::Syntax(x.to_owned());
+        let mut fields = s.split(',').map(str::trim);
+        let baud = fields.next().unwrap_or_default();
+        let mut config = Self::new(baud.parse().map_err(|_| syntax(baud))?);
+        for field in fields {
+            let lower = field.to_ascii_lowercase();
+            if let Some((flow, _)) = FLOW_NAMES.iter().find(|(_, n)| *n == lower) {
+                config.flow_control |= *flow;
+            } else if lower == "none" {
+                config.flow_control = AsrlFlowCntrl::empty();
+            } else {
+                let (data_bits, rest) = field.split_at_checked(1).ok_or_else(|| syntax(field))?;
+                let (parity, stop_bits) = rest.split_at_checked(1).ok_or_else(|| syntax(field))?;
+                config.data_bits = data_bits.parse().map_err(|_| syntax(field))?;
+                config.parity = match parity.to_ascii_uppercase().as_str() {
+                    "N" => AsrlParity::None,
+                    "O" => AsrlParity::Odd,
+                    "E" => AsrlParity::Even,
+                    "M" => AsrlParity::Mark,
+                    "S" => AsrlParity::Space,
+                    _ => return Err(syntax(field)),
+                };
+                config.stop_bits = match stop_bits {
+                    "1" => AsrlStopBits::One,
+                    "1.5" => AsrlStopBits::One5,
+                    "2" => AsrlStopBits::Two,
+                    _ => return Err(syntax(field)),
+                };
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Error applying or parsing a [`SerialConfig`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerialConfigError {
+    /// A field of the string can't be parsed, holds the field
+    Syntax(String),
+    ZeroBaud,
+    /// The data bits are not from 5 to 8
+    DataBits(u16),
+    /// 1.5 stop bits are only valid with 5 data bits, and 2 stop bits with 6 or more
+    StopBits {
+        data_bits: u16,
+        stop_bits: AsrlStopBits,
+    },
+    /// XON/XOFF flow control with the same character for both
+    SameXonXoff(u8),
+    /// Setting the attributes failed, those set before have been restored if they could be read beforehand
+    Visa(Error),
+}
+
+impl fmt::Display for SerialConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerialConfigError::Syntax(s) => write!(f, "invalid serial setting '{s}'"),
+            SerialConfigError::ZeroBaud => write!(f, "baud rate of 0"),
+            SerialConfigError::DataBits(n) => write!(f, "{n} data bits, expected 5 to 8"),
+            SerialConfigError::StopBits {
+                data_bits,
+                stop_bits,
+            } => write!(f, "{stop_bits} can't be used with {data_bits} data bits"),
+            SerialConfigError::SameXonXoff(c) => {
+                write!(f, "XON and XOFF are both {c:#04x}")
+            }
+            SerialConfigError::Visa(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SerialConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SerialConfigError::Visa(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for SerialConfigError {
+    fn from(e: Error) -> Self {
+        Self::Visa(e)
+    }
+}
+
+impl From<SerialConfigError> for Error {
+    /// Invalid settings become [`ErrorNsupAttrState`](ErrorCode::ErrorNsupAttrState)
+    fn from(e: SerialConfigError) -> Self {
+        match e {
+            SerialConfigError::Visa(e) => e,
+            _ => Error(ErrorCode::ErrorNsupAttrState),
+        }
+    }
+}
+
+/// Modem control lines of a serial port, see [`Instrument::modem_line`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModemLine {
+    /// Clear To Send, input
+    Cts,
+    /// Data Set Ready, input
+    Dsr,
+    /// Data Terminal Ready, output, read only with DTR/DSR flow control
+    Dtr,
+    /// Request To Send, output, read only with RTS/CTS flow control
+    Rts,
+    /// Ring Indicator, input unless the port is in DCE mode
+    Ri,
+    /// Data Carrier Detect, input unless the port is in DCE mode
+    Dcd,
+}
+
+/// State of a [`ModemLine`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineState {
+    Asserted,
+    Unasserted,
+    /// e.g. an output driven by the flow control
+    Unknown,
+}
+
+impl LineState {
+    fn from_raw(v: vs::ViInt16) -> Self {
+        match v as vs::ViUInt32 {
+            vs::VI_STATE_ASSERTED => Self::Asserted,
+            vs::VI_STATE_UNASSERTED => Self::Unasserted,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Reads the current settings of the serial port
+    pub fn serial_config(&self) -> crate::Result<SerialConfig> {
+        Ok(SerialConfig {
+            baud: AttrAsrlBaud::get_from(self)?.into_inner() as _,
+            data_bits: AttrAsrlDataBits::get_from(self)?.into_inner(),
+            parity: AttrAsrlParity::get_from(self)?.try_into()?,
+            stop_bits: AttrAsrlStopBits::get_from(self)?.try_into()?,
+            flow_control: AttrAsrlFlowCntrl::get_from(self)?.try_into()?,
+            end_in: AttrAsrlEndIn::get_from(self)?.try_into()?,
+            end_out: AttrAsrlEndOut::get_from(self)?.try_into()?,
+            xon_char: AttrAsrlXonChar::get_from(self)?.into_inner(),
+            xoff_char: AttrAsrlXoffChar::get_from(self)?.into_inner(),
+        })
+    }
+
+    /// Validates `config` and sets all of it.
+    ///
+    /// VISA sets attributes one at a time, if one fails the settings are restored to what [`Self::serial_config`] read before,
+    /// unless reading them failed too.
+    pub fn apply_serial_config(&self, config: &SerialConfig) -> Result<(), SerialConfigError> {
+        config.validate()?;
+        let previous = self.serial_config().ok();
+        self.set_serial_config(config).map_err(|e| {
+            if let Some(previous) = previous {
+                if let Err(e) = self.set_serial_config(&previous) {
+                    log::warn!("failed to restore serial settings: {e}");
+                }
+            }
+            e.into()
+        })
+    }
+
+    fn set_serial_config(&self, config: &SerialConfig) -> crate::Result<()> {
+        // all in range once validated
+        unsafe {
+            self.set_attr(AttrAsrlBaud::new_unchecked(config.baud as _))?;
+            self.set_attr(AttrAsrlDataBits::new_unchecked(config.data_bits))?;
+        }
+        self.set_attr(config.parity)?;
+        self.set_attr(config.stop_bits)?;
+        self.set_attr(config.flow_control)?;
+        self.set_attr(config.end_in)?;
+        self.set_attr(config.end_out)?;
+        unsafe {
+            self.set_attr(AttrAsrlXonChar::new_unchecked(config.xon_char))?;
+            self.set_attr(AttrAsrlXoffChar::new_unchecked(config.xoff_char))?;
+        }
+        Ok(())
+    }
+
+    /// Reads the state of a modem control line
+    pub fn modem_line(&self, line: ModemLine) -> crate::Result<LineState> {
+        let v = match line {
+            ModemLine::Cts => AttrAsrlCtsState::get_from(self)?.into_inner(),
+            ModemLine::Dsr => AttrAsrlDsrState::get_from(self)?.into_inner(),
+            ModemLine::Dtr => AttrAsrlDtrState::get_from(self)?.into_inner(),
+            ModemLine::Rts => AttrAsrlRtsState::get_from(self)?.into_inner(),
+            ModemLine::Ri => AttrAsrlRiState::get_from(self)?.into_inner(),
+            ModemLine::Dcd => AttrAsrlDcdState::get_from(self)?.into_inner(),
+        };
+        Ok(LineState::from_raw(v))
+    }
+
+    /// Asserts or unasserts a modem control line.
+    ///
+    /// Fails with [`ErrorAttrReadonly`](ErrorCode::ErrorAttrReadonly) for the inputs CTS and DSR,
+    /// VISA fails likewise for a line driven by the flow control, or RI and DCD when the port is not in DCE mode.
+    pub fn set_modem_line(&self, line: ModemLine, asserted: bool) -> crate::Result<()> {
+        let state = if asserted {
+            vs::VI_STATE_ASSERTED
+        } else {
+            vs::VI_STATE_UNASSERTED
+        } as vs::ViInt16;
+        unsafe {
+            match line {
+                ModemLine::Cts | ModemLine::Dsr => Err(Error(ErrorCode::ErrorAttrReadonly)),
+                ModemLine::Dtr => self.set_attr(AttrAsrlDtrState::new_unchecked(state)),
+                ModemLine::Rts => self.set_attr(AttrAsrlRtsState::new_unchecked(state)),
+                ModemLine::Ri => self.set_attr(AttrAsrlRiState::new_unchecked(state)),
+                ModemLine::Dcd => self.set_attr(AttrAsrlDcdState::new_unchecked(state)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::MockResource,
+        enums::{
+            attribute::{self},
+            status::ErrorCode,
+        },
+        Error,
+    };
+
+    use anyhow::Result;
+
+    #[test]
+    fn serial_config() -> Result<()> {
+        use crate::serial::{LineState, ModemLine, SerialConfig, SerialConfigError};
+        use attribute::{AsrlFlowCntrl, AsrlParity, AsrlStopBits, AttrAsrlCtsState};
+        let (_dev, _rm, instr) = MockResource::new("ASRL13::INSTR")
+            .with_attr(AttrAsrlCtsState::VI_STATE_ASSERTED)
+            .open()?;
+
+        let config: SerialConfig = "115200,7E2,rtscts,xonxoff".parse()?;
+        assert_eq!(
+            config,
+            SerialConfig::new(115200)
+                .data_bits(7)
+                .parity(AsrlParity::Even)
+                .stop_bits(AsrlStopBits::Two)
+                .flow_control(AsrlFlowCntrl::RTS_CTS | AsrlFlowCntrl::XON_XOFF)
+        );
+        assert_eq!(config.to_string(), "115200,7E2,xonxoff,rtscts");
+        assert_eq!("19200".parse(), Ok(SerialConfig::new(19200)));
+        assert_eq!(
+            "9600,8X1".parse::<SerialConfig>(),
+            Err(SerialConfigError::Syntax("8X1".into()))
+        );
+        assert_eq!(
+            "9600,5N2".parse::<SerialConfig>(),
+            Err(SerialConfigError::StopBits {
+                data_bits: 5,
+                stop_bits: AsrlStopBits::Two
+            })
+        );
+        assert_eq!(
+            instr.apply_serial_config(&config.xon_xoff_chars(0x11, 0x11)),
+            Err(SerialConfigError::SameXonXoff(0x11))
+        );
+
+        instr.apply_serial_config(&config)?;
+        assert_eq!(instr.serial_config()?, config);
+
+        assert_eq!(instr.modem_line(ModemLine::Cts)?, LineState::Asserted);
+        assert_eq!(
+            instr.set_modem_line(ModemLine::Cts, false),
+            Err(Error(ErrorCode::ErrorAttrReadonly))
+        );
+        instr.set_modem_line(ModemLine::Dtr, false)?;
+        assert_eq!(instr.modem_line(ModemLine::Dtr)?, LineState::Unasserted);
+        Ok(())
+    }
+}
@@ -2,31 +2,34 @@ use std::marker::PhantomData;
 
 use visa_sys as vs;
 
+use crate::backend::{Backend, Visa};
+
 /// Raw visa session.
 pub type RawSs = vs::ViSession;
 
 /// An owned visa session.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct OwnedSs {
+pub struct OwnedSs<B: Backend = Visa> {
     s: RawSs,
+    _backend: PhantomData<B>,
 }
 
 /// A borrowed visa session.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct BorrowedSs<'b> {
+pub struct BorrowedSs<'b, B: Backend = Visa> {
     s: RawSs,
-    _phantom: PhantomData<&'b RawSs>,
+    _phantom: PhantomData<(&'b RawSs, B)>,
 }
 
-impl Drop for OwnedSs {
+impl<B: Backend> Drop for OwnedSs<B> {
     fn drop(&mut self) {
         unsafe {
-            vs::viClose(self.s);
+            B::close(self.s);
         }
     }
 }
 
-impl BorrowedSs<'_> {
+impl<B: Backend> BorrowedSs<'_, B> {
     /// # Safety
     ///
     /// The `ss` passed in must be a valid VISA session.
@@ -40,6 +43,8 @@ impl BorrowedSs<'_> {
 
 /// A trait to extract the raw visa session from an underlying object.
 pub trait AsRawSs {
+    /// [`Backend`] the session belongs to.
+    type Backend: Backend;
     fn as_raw_ss(&self) -> RawSs;
 }
 
@@ -57,23 +62,41 @@ pub trait IntoRawSs {
 }
 
 /// A trait to borrow the visa session from an underlying object.
-pub trait AsSs {
-    fn as_ss(&self) -> BorrowedSs<'_>;
+pub trait AsSs: AsRawSs {
+    fn as_ss(&self) -> BorrowedSs<'_, Self::Backend>;
 }
 
-impl AsRawSs for BorrowedSs<'_> {
+impl<B: Backend> AsRawSs for BorrowedSs<'_, B> {
+    type Backend = B;
     fn as_raw_ss(&self) -> RawSs {
         self.s
     }
 }
 
-impl AsRawSs for OwnedSs {
+impl<B: Backend> AsRawSs for OwnedSs<B> {
+    type Backend = B;
     fn as_raw_ss(&self) -> RawSs {
         self.s
     }
 }
 
-impl IntoRawSs for OwnedSs {
+impl<T: AsRawSs> AsRawSs for &T {
+    type Backend = T::Backend;
+    #[inline]
+    fn as_raw_ss(&self) -> RawSs {
+        T::as_raw_ss(self)
+    }
+}
+
+impl<T: AsRawSs> AsRawSs for &mut T {
+    type Backend = T::Backend;
+    #[inline]
+    fn as_raw_ss(&self) -> RawSs {
+        T::as_raw_ss(self)
+    }
+}
+
+impl<B: Backend> IntoRawSs for OwnedSs<B> {
     fn into_raw_ss(self) -> RawSs {
         let ss = self.s;
         std::mem::forget(self);
@@ -81,36 +104,39 @@ impl IntoRawSs for OwnedSs {
     }
 }
 
-impl FromRawSs for OwnedSs {
+impl<B: Backend> FromRawSs for OwnedSs<B> {
     unsafe fn from_raw_ss(s: RawSs) -> Self {
-        Self { s }
+        Self {
+            s,
+            _backend: PhantomData,
+        }
     }
 }
 
 impl<T: AsSs> AsSs for &T {
     #[inline]
-    fn as_ss(&self) -> BorrowedSs<'_> {
+    fn as_ss(&self) -> BorrowedSs<'_, Self::Backend> {
         T::as_ss(self)
     }
 }
 
 impl<T: AsSs> AsSs for &mut T {
     #[inline]
-    fn as_ss(&self) -> BorrowedSs<'_> {
+    fn as_ss(&self) -> BorrowedSs<'_, Self::Backend> {
         T::as_ss(self)
     }
 }
 
-impl AsSs for BorrowedSs<'_> {
+impl<B: Backend> AsSs for BorrowedSs<'_, B> {
     #[inline]
-    fn as_ss(&self) -> BorrowedSs<'_> {
+    fn as_ss(&self) -> BorrowedSs<'_, B> {
         *self
     }
 }
 
-impl AsSs for OwnedSs {
+impl<B: Backend> AsSs for OwnedSs<B> {
     #[inline]
-    fn as_ss(&self) -> BorrowedSs<'_> {
+    fn as_ss(&self) -> BorrowedSs<'_, B> {
         unsafe { BorrowedSs::borrow_raw(self.s) }
     }
 }
@@ -0,0 +1,180 @@
+use super::*;
+use enums::{
+    attribute::{AttrKind, Attribute, HasAttribute},
+    status::ErrorCode,
+};
+
+/// Values of the writable attributes of a session, taken by [`Instrument::snapshot_attrs`] and restored by [`Instrument::apply_attrs`].
+///
+/// With the `serde` feature, it can be saved to and loaded from any format serde supports, e.g. in TOML:
+///
+/// ```toml
+/// [[attrs]]
+/// AttrTmoValue = 2000
+///
+/// [[attrs]]
+/// AttrAsrlBaud = 115200
+///
+/// [[attrs]]
+/// AttrAsrlParity = "VI_ASRL_PAR_EVEN"
+/// ```
+///
+/// Enumerated attributes are saved by the VISA name of their value, and every value is checked when loaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    attrs: Vec<Attribute>,
+}
+
+impl Snapshot {
+    /// Attributes in the order they are applied
+    pub fn attrs(&self) -> &[Attribute] {
+        &self.attrs
+    }
+
+    pub fn get(&self, kind: AttrKind) -> Option<&Attribute> {
+        self.attrs.iter().find(|x| x.kind() == kind)
+    }
+
+    /// Replaces the attribute of the same kind, or appends it if there is none
+    pub fn set(&mut self, attr: impl Into<Attribute>) {
+        let attr = attr.into();
+        match self.attrs.iter_mut().find(|x| x.kind() == attr.kind()) {
+            Some(x) => *x = attr,
+            None => self.attrs.push(attr),
+        }
+    }
+
+    pub fn remove(&mut self, kind: AttrKind) -> Option<Attribute> {
+        let i = self.attrs.iter().position(|x| x.kind() == kind)?;
+        Some(self.attrs.remove(i))
+    }
+}
+
+impl FromIterator<Attribute> for Snapshot {
+    fn from_iter<T: IntoIterator<Item = Attribute>>(iter: T) -> Self {
+        let mut ret = Self::default();
+        iter.into_iter().for_each(|x| ret.set(x));
+        ret
+    }
+}
+
+impl IntoIterator for Snapshot {
+    type Item = Attribute;
+    type IntoIter = std::vec::IntoIter<Attribute>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.attrs.into_iter()
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Reads every writable session attribute listed in the NI-VISA document,
+    /// skipping those the resource doesn't support, i.e. failing with [`ErrorNsupAttr`](ErrorCode::ErrorNsupAttr).
+    pub fn snapshot_attrs(&self) -> Result<Snapshot> {
+        let mut attrs = Vec::new();
+        for kind in AttrKind::listed() {
+            // event attributes have no scope, and user data is an address in this process
+            if !kind
+                .meta()
+                .is_some_and(|m| m.writable && m.scope.is_some() && m.c_type != "ViAddr")
+            {
+                continue;
+            }
+            match self.get_attr(kind) {
+                Ok(attr) => attrs.push(attr),
+                Err(Error(ErrorCode::ErrorNsupAttr)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Snapshot { attrs })
+    }
+
+    /// Sets the attributes in `snapshot` in order,
+    /// skipping those the resource doesn't support, i.e. failing with [`ErrorNsupAttr`](ErrorCode::ErrorNsupAttr),
+    /// so a snapshot taken from another resource class can be applied.
+    ///
+    /// Stops at the first other error, leaving the attributes before it set.
+    pub fn apply_attrs(&self, snapshot: &Snapshot) -> Result<()> {
+        for attr in snapshot.attrs() {
+            match self.set_attr(attr.clone()) {
+                Ok(()) => {}
+                Err(Error(ErrorCode::ErrorNsupAttr)) => {
+                    log::debug!("skipping unsupported attribute {:?}", attr.kind());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        backend::mock::{Mock, MockResource},
+        enums::attribute::{AttrAsrlBaud, AttrTmoValue, SpecAttr},
+        DefaultRM,
+    };
+    use anyhow::Result;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        use enums::attribute::AsrlParity;
+        let snapshot: Snapshot = [
+            AttrTmoValue::new_checked(5000).unwrap().into(),
+            AsrlParity::Even.into(),
+        ]
+        .into_iter()
+        .collect();
+        let config = toml::to_string(&snapshot).unwrap();
+        assert_eq!(
+            config,
+            "[[attrs]]\nAttrTmoValue = 5000\n\n[[attrs]]\nAttrAsrlParity = \"VI_ASRL_PAR_EVEN\"\n"
+        );
+        assert_eq!(toml::from_str::<Snapshot>(&config).unwrap(), snapshot);
+        // values are checked like by `new_checked`
+        assert_eq!(
+            toml::from_str::<Attribute>("AttrAsrlParity = 2").unwrap(),
+            AsrlParity::Even.into()
+        );
+        assert!(toml::from_str::<Attribute>("AttrAsrlParity = 7").is_err());
+        assert!(toml::from_str::<Attribute>("AttrAsrlParity = \"VI_ASRL_PAR_ODDS\"").is_err());
+
+        let config = "AttrRsrcClass = \"INSTR\"\n";
+        let class: Attribute = toml::from_str(config).unwrap();
+        assert_eq!(class.kind(), AttrKind::AttrRsrcClass);
+        assert_eq!(toml::to_string(&class).unwrap(), config);
+        assert!(toml::from_str::<Attribute>("AttrRsrcClass = \"A\\u0000\"").is_err());
+    }
+
+    #[test]
+    fn attr_snapshot() -> Result<()> {
+        let a_dev = MockResource::new("ASRL11::INSTR")
+            .with_attr(AttrAsrlBaud::new_checked(115200).unwrap())
+            .register();
+        let b_dev = MockResource::new("ASRL12::INSTR")
+            .with_attr(AttrAsrlBaud::new_checked(9600).unwrap())
+            .register();
+        let rm = DefaultRM::<Mock>::with_backend()?;
+        let a = a_dev.open(&rm)?;
+        a.set_attr(AttrTmoValue::new_checked(5000).unwrap())?;
+        let snapshot = a.snapshot_attrs()?;
+        assert_eq!(
+            snapshot.get(AttrKind::AttrAsrlBaud),
+            Some(&AttrAsrlBaud::new_checked(115200).unwrap().into())
+        );
+        // read only and unsupported attributes are left out
+        assert!(snapshot.get(AttrKind::AttrRsrcName).is_none());
+        assert!(snapshot.get(AttrKind::AttrAsrlParity).is_none());
+
+        let b = b_dev.open(&rm)?;
+        b.apply_attrs(&snapshot)?;
+        assert_eq!(AttrAsrlBaud::get_from(&b)?.into_inner(), 115200);
+        assert_eq!(AttrTmoValue::get_from(&b)?.into_inner(), 5000);
+        assert_eq!(b.snapshot_attrs()?, snapshot);
+        Ok(())
+    }
+}
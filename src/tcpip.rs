@@ -0,0 +1,255 @@
+//! Inspection and tuning of TCPIP sessions, see [`Instrument::tcpip_info`] and [`Instrument::tls_info`]
+//!
+//! Which `VI_ATTR_TCPIP_*` attributes apply depends on the resource: VXI-11 and HiSLIP INSTR or SOCKET,
+//! attributes the resource doesn't support are `None` instead of failing with [`ErrorNsupAttr`](ErrorCode::ErrorNsupAttr).
+
+use crate::{
+    backend::Backend,
+    enums::{
+        attribute::{
+            AttrTcpipAddr, AttrTcpipDeviceName, AttrTcpipHislipEncryptionEn,
+            AttrTcpipHislipMaxMessageKb, AttrTcpipHislipOverlapEn, AttrTcpipHislipVersion,
+            AttrTcpipHostname, AttrTcpipIsHislip, AttrTcpipKeepalive, AttrTcpipNodelay,
+            AttrTcpipPort, AttrTcpipSaslMechanism, AttrTcpipServerCertExpirationDate,
+            AttrTcpipServerCertIsPerpetual, AttrTcpipServerCertIssuerName,
+            AttrTcpipServerCertSubjectName, AttrTcpipTlsCipherSuite, HasAttribute, SpecAttr,
+        },
+        status::ErrorCode,
+    },
+    Error, Instrument, Result, VisaString,
+};
+use visa_sys as vs;
+
+/// Settings of a TCPIP session, read by [`Instrument::tcpip_info`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TcpipInfo {
+    /// Address of the device in dot notation
+    pub address: VisaString,
+    /// Empty if the device has no host name
+    pub hostname: VisaString,
+    pub port: Option<u16>,
+    /// LAN device name of VXI-11 and HiSLIP INSTR sessions, e.g. `inst0` or `hislip0`
+    pub device_name: Option<VisaString>,
+    /// Whether keep-alive packets detect dropped connections
+    pub keepalive: Option<bool>,
+    /// Whether the Nagle algorithm is disabled
+    pub nodelay: Option<bool>,
+    /// `None` unless the session uses HiSLIP
+    pub hislip: Option<HislipInfo>,
+}
+
+/// HiSLIP settings of a TCPIP session, see [`TcpipInfo::hislip`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HislipInfo {
+    /// Protocol version in the format of `ViVersion`, e.g. `0x00100000` for 1.0
+    pub version: u32,
+    /// Largest message accepted from the device in kilobytes
+    pub max_message_kb: u32,
+    /// Whether the connection is in overlapped mode rather than synchronous mode
+    pub overlap: bool,
+    /// Whether the connection is encrypted, `None` before HiSLIP 2.0
+    pub encrypted: Option<bool>,
+}
+
+impl HislipInfo {
+    /// Major and minor protocol version, e.g. `(1, 0)`
+    pub fn version(&self) -> (u16, u16) {
+        (
+            (self.version >> 20) as _,
+            ((self.version >> 8) & 0xFFF) as _,
+        )
+    }
+}
+
+/// TLS details of an encrypted HiSLIP connection, read by [`Instrument::tls_info`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TlsInfo {
+    pub cipher_suite: VisaString,
+    /// Mechanism the client authenticated with, `None` if not authenticated
+    pub sasl_mechanism: Option<VisaString>,
+    pub cert_subject: VisaString,
+    pub cert_issuer: VisaString,
+    /// Expiration date of the server certificate as reported by VISA, `None` if the certificate never expires
+    pub cert_expiration: Option<VisaString>,
+}
+
+/// `None` for attributes the resource doesn't support
+fn supported<T>(r: Result<T>) -> Result<Option<T>> {
+    match r {
+        Ok(v) => Ok(Some(v)),
+        Err(Error(ErrorCode::ErrorNsupAttr)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn bool_of(v: vs::ViBoolean) -> bool {
+    v != vs::VI_FALSE as vs::ViBoolean
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Reads the attributes of a TCPIP session in one call,
+    /// failing with [`ErrorNsupAttr`](ErrorCode::ErrorNsupAttr) if the session is not a TCPIP one
+    pub fn tcpip_info(&self) -> Result<TcpipInfo> {
+        let is_hislip =
+            supported(AttrTcpipIsHislip::get_from(self))?.is_some_and(|x| bool_of(x.into_inner()));
+        let hislip = if is_hislip {
+            Some(HislipInfo {
+                version: AttrTcpipHislipVersion::get_from(self)?.into_inner() as _,
+                max_message_kb: AttrTcpipHislipMaxMessageKb::get_from(self)?.into_inner() as _,
+                overlap: bool_of(AttrTcpipHislipOverlapEn::get_from(self)?.into_inner()),
+                encrypted: supported(AttrTcpipHislipEncryptionEn::get_from(self))?
+                    .map(|x| bool_of(x.into_inner())),
+            })
+        } else {
+            None
+        };
+        Ok(TcpipInfo {
+            address: AttrTcpipAddr::get_from(self)?.into_inner(),
+            hostname: AttrTcpipHostname::get_from(self)?.into_inner(),
+            port: supported(AttrTcpipPort::get_from(self))?.map(|x| x.into_inner() as _),
+            device_name: supported(AttrTcpipDeviceName::get_from(self))?.map(|x| x.into_inner()),
+            keepalive: supported(AttrTcpipKeepalive::get_from(self))?
+                .map(|x| bool_of(x.into_inner())),
+            nodelay: supported(AttrTcpipNodelay::get_from(self))?.map(|x| bool_of(x.into_inner())),
+            hislip,
+        })
+    }
+
+    /// Reads the TLS details of a HiSLIP connection, `None` if it is not encrypted
+    pub fn tls_info(&self) -> Result<Option<TlsInfo>> {
+        let encrypted = supported(AttrTcpipHislipEncryptionEn::get_from(self))?
+            .is_some_and(|x| bool_of(x.into_inner()));
+        if !encrypted {
+            return Ok(None);
+        }
+        let perpetual = supported(AttrTcpipServerCertIsPerpetual::get_from(self))?
+            .is_some_and(|x| bool_of(x.into_inner()));
+        Ok(Some(TlsInfo {
+            cipher_suite: AttrTcpipTlsCipherSuite::get_from(self)?.into_inner(),
+            sasl_mechanism: supported(AttrTcpipSaslMechanism::get_from(self))?
+                .map(|x| x.into_inner())
+                .filter(|x| !x.as_bytes().is_empty()),
+            cert_subject: AttrTcpipServerCertSubjectName::get_from(self)?.into_inner(),
+            cert_issuer: AttrTcpipServerCertIssuerName::get_from(self)?.into_inner(),
+            cert_expiration: if perpetual {
+                None
+            } else {
+                Some(AttrTcpipServerCertExpirationDate::get_from(self)?.into_inner())
+            },
+        }))
+    }
+
+    /// Enables keep-alive packets, so that I/O on a dropped connection fails with [`ErrorConnLost`](ErrorCode::ErrorConnLost)
+    pub fn set_tcpip_keepalive(&self, enable: bool) -> Result<()> {
+        self.set_attr(if enable {
+            AttrTcpipKeepalive::VI_TRUE
+        } else {
+            AttrTcpipKeepalive::VI_FALSE
+        })
+    }
+
+    /// Disables the Nagle algorithm, so that writes are sent immediately
+    pub fn set_tcpip_nodelay(&self, enable: bool) -> Result<()> {
+        self.set_attr(if enable {
+            AttrTcpipNodelay::VI_TRUE
+        } else {
+            AttrTcpipNodelay::VI_FALSE
+        })
+    }
+
+    /// Sets the largest HiSLIP message accepted from the device in kilobytes
+    pub fn set_hislip_max_message_kb(&self, kb: u32) -> Result<()> {
+        // the range is the whole u32
+        self.set_attr(unsafe { AttrTcpipHislipMaxMessageKb::new_unchecked(kb as _) })
+    }
+
+    /// Switches HiSLIP to overlapped or synchronous mode, VISA clears the device to change the mode
+    pub fn set_hislip_overlap(&self, enable: bool) -> Result<()> {
+        self.set_attr(if enable {
+            AttrTcpipHislipOverlapEn::VI_TRUE
+        } else {
+            AttrTcpipHislipOverlapEn::VI_FALSE
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::{Mock, MockResource},
+        enums::attribute::{self},
+        DefaultRM,
+    };
+
+    use anyhow::Result;
+
+    #[test]
+    fn tcpip_info() -> Result<()> {
+        use attribute::{
+            AttrKind, AttrTcpipHislipEncryptionEn, AttrTcpipHislipMaxMessageKb,
+            AttrTcpipHislipOverlapEn, AttrTcpipHislipVersion, AttrTcpipIsHislip, AttrTcpipNodelay,
+            AttrTcpipPort,
+        };
+        let socket_dev = MockResource::new("TCPIP0::10.0.0.9::5025::SOCKET")
+            .with_str_attr(AttrKind::AttrTcpipAddr, "10.0.0.9")
+            .with_str_attr(AttrKind::AttrTcpipHostname, "")
+            .with_attr(unsafe { AttrTcpipPort::new_unchecked(5025) })
+            .with_attr(AttrTcpipNodelay::VI_TRUE)
+            .register();
+        let hislip_dev = MockResource::new("TCPIP0::10.0.0.10::hislip0::INSTR")
+            .with_str_attr(AttrKind::AttrTcpipAddr, "10.0.0.10")
+            .with_str_attr(AttrKind::AttrTcpipHostname, "scope.lan")
+            .with_str_attr(AttrKind::AttrTcpipDeviceName, "hislip0")
+            .with_attr(AttrTcpipIsHislip::VI_TRUE)
+            .with_attr(unsafe { AttrTcpipHislipVersion::new_unchecked(0x00200000) })
+            .with_attr(AttrTcpipHislipMaxMessageKb::default())
+            .with_attr(AttrTcpipHislipOverlapEn::VI_FALSE)
+            .with_attr(AttrTcpipHislipEncryptionEn::VI_TRUE)
+            .with_str_attr(AttrKind::AttrTcpipTlsCipherSuite, "TLS_AES_256_GCM_SHA384")
+            .with_str_attr(AttrKind::AttrTcpipSaslMechanism, "")
+            .with_str_attr(AttrKind::AttrTcpipServerCertSubjectName, "CN=scope.lan")
+            .with_str_attr(AttrKind::AttrTcpipServerCertIssuerName, "CN=ACME CA")
+            .with_str_attr(
+                AttrKind::AttrTcpipServerCertExpirationDate,
+                "2030-01-01T00:00:00Z",
+            )
+            .register();
+        let rm = DefaultRM::<Mock>::with_backend()?;
+
+        let socket = socket_dev.open(&rm)?;
+        let info = socket.tcpip_info()?;
+        assert_eq!(info.address.to_string(), "10.0.0.9");
+        assert_eq!(info.port, Some(5025));
+        assert_eq!((info.device_name, info.keepalive), (None, None));
+        assert_eq!(info.nodelay, Some(true));
+        assert_eq!(info.hislip, None);
+        assert_eq!(socket.tls_info()?, None);
+        socket.set_tcpip_keepalive(true)?;
+        assert_eq!(socket.tcpip_info()?.keepalive, Some(true));
+
+        let hislip = hislip_dev.open(&rm)?;
+        let info = hislip.tcpip_info()?;
+        assert_eq!(info.hostname.to_string(), "scope.lan");
+        assert_eq!(info.device_name.unwrap().to_string(), "hislip0");
+        let hislip_info = info.hislip.unwrap();
+        assert_eq!(hislip_info.version(), (2, 0));
+        assert_eq!(hislip_info.max_message_kb, 1024);
+        assert!(!hislip_info.overlap);
+        assert_eq!(hislip_info.encrypted, Some(true));
+        hislip.set_hislip_overlap(true)?;
+        hislip.set_hislip_max_message_kb(4096)?;
+        let hislip_info = hislip.tcpip_info()?.hislip.unwrap();
+        assert!(hislip_info.overlap);
+        assert_eq!(hislip_info.max_message_kb, 4096);
+
+        let tls = hislip.tls_info()?.unwrap();
+        assert_eq!(tls.cipher_suite.to_string(), "TLS_AES_256_GCM_SHA384");
+        assert_eq!(tls.sasl_mechanism, None);
+        assert_eq!(tls.cert_issuer.to_string(), "CN=ACME CA");
+        assert_eq!(
+            tls.cert_expiration.unwrap().to_string(),
+            "2030-01-01T00:00:00Z"
+        );
+        Ok(())
+    }
+}
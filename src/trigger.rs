@@ -0,0 +1,171 @@
+use super::*;
+use enums::{
+    status::CompletionCode,
+    trigger::{TrigLine, TrigMapMode},
+};
+
+/// A route between two trigger lines, mapped by [`Instrument::map_trigger`] and unmapped on drop.
+///
+/// Mapping a route that is already mapped succeeds with `VI_SUCCESS_TRIG_MAPPED`,
+/// the guard then leaves the route in place on drop, see [`Self::was_mapped`].
+#[derive(Debug)]
+#[must_use = "the trigger lines are unmapped as soon as the guard is dropped"]
+pub struct TriggerMapping<'a, B: Backend = Visa> {
+    instr: &'a Instrument<B>,
+    src: TrigLine,
+    dest: TrigLine,
+    was_mapped: bool,
+}
+
+impl<'a, B: Backend> TriggerMapping<'a, B> {
+    /// Instrument the route is mapped on
+    pub fn instrument(&self) -> &'a Instrument<B> {
+        self.instr
+    }
+
+    pub fn source(&self) -> TrigLine {
+        self.src
+    }
+
+    pub fn destination(&self) -> TrigLine {
+        self.dest
+    }
+
+    /// Whether the route was already mapped when this guard was created
+    pub fn was_mapped(&self) -> bool {
+        self.was_mapped
+    }
+
+    /// Unmaps the route, even if it [was mapped](Self::was_mapped) before, reporting the error dropping would ignore
+    pub fn unmap(self) -> Result<()> {
+        let (instr, src, dest) = (self.instr, self.src, self.dest);
+        std::mem::forget(self);
+        instr.unmap_trigger(src, Some(dest))
+    }
+}
+
+impl<B: Backend> Drop for TriggerMapping<'_, B> {
+    fn drop(&mut self) {
+        if !self.was_mapped {
+            let _ = self.instr.unmap_trigger(self.src, Some(self.dest));
+        }
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Maps the specified trigger source line to the specified destination line.
+    ///
+    /// This operation can be used to map one trigger line to another, e.g. a front panel input to a backplane TTL line. This operation is valid only on BACKPLANE (mainframe) sessions. The route stays mapped until the returned guard is dropped.
+    ///
+    /// Fails with [`ErrorLineInUse`](enums::status::ErrorCode::ErrorLineInUse) if the destination line is already driven by another source.
+    pub fn map_trigger(
+        &self,
+        src: TrigLine,
+        dest: TrigLine,
+        mode: TrigMapMode,
+    ) -> Result<TriggerMapping<'_, B>> {
+        let code = wrap_raw_error_in_unsafe!(B::map_trigger(
+            self.as_raw_ss(),
+            src as _,
+            dest as _,
+            mode as _
+        ))?;
+        Ok(TriggerMapping {
+            instr: self,
+            src,
+            dest,
+            was_mapped: code == CompletionCode::SuccessTrigMapped,
+        })
+    }
+
+    /// Undoes a previous map from the specified trigger source line to the specified destination line, or to every line it is mapped to if `dest` is `None`.
+    ///
+    /// Fails with [`ErrorTrigNmapped`](enums::status::ErrorCode::ErrorTrigNmapped) if the route is not mapped.
+    pub fn unmap_trigger(&self, src: TrigLine, dest: Option<TrigLine>) -> Result<()> {
+        wrap_raw_error_in_unsafe!(B::unmap_trigger(
+            self.as_raw_ss(),
+            src as _,
+            dest.map_or(vs::VI_TRIG_ALL as _, |d| d as _)
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::{Mock, MockResource},
+        enums::status::ErrorCode,
+        DefaultRM, Error,
+    };
+
+    use anyhow::Result;
+
+    #[test]
+    fn vxi_command_and_trigger_mapping() -> Result<()> {
+        use crate::enums::{
+            trigger::{TrigLine, TrigMapMode},
+            vxi::VxiCmdMode,
+        };
+        // Read Protocol, answered with the protocol register
+        let dev = MockResource::new("VXI0::24::INSTR")
+            .with_vxi_response(0xCFFF, 0x0001_8FFF)
+            .register();
+        let backplane = MockResource::new("VXI0::0::BACKPLANE").register();
+        let rm = DefaultRM::<Mock>::with_backend()?;
+
+        let instr = dev.open(&rm)?;
+        assert_eq!(
+            instr.vxi_command_query(VxiCmdMode::VxiCmd16Resp16, 0xCFFF)?,
+            0x8FFF
+        );
+        assert_eq!(instr.vxi_command_query(VxiCmdMode::VxiCmd32, 0xCFFF)?, 0);
+        assert_eq!(
+            instr.vxi_command_query(VxiCmdMode::VxiResp32, 0)?,
+            0x0001_8FFF
+        );
+        assert_eq!(
+            instr.vxi_command_query(VxiCmdMode::VxiResp16, 0),
+            Err(Error(ErrorCode::ErrorTmo))
+        );
+        assert_eq!(dev.vxi_commands(), [0xCFFF, 0xCFFF]);
+
+        let sess = backplane.open(&rm)?;
+        {
+            let map =
+                sess.map_trigger(TrigLine::TrigPanelIn, TrigLine::TrigTtl3, TrigMapMode::Null)?;
+            assert!(!map.was_mapped());
+            let _ecl =
+                sess.map_trigger(TrigLine::TrigPanelIn, TrigLine::TrigEcl0, TrigMapMode::Null)?;
+            // mapping again leaves the route to the first guard
+            let again =
+                sess.map_trigger(TrigLine::TrigPanelIn, TrigLine::TrigTtl3, TrigMapMode::Null)?;
+            assert!(again.was_mapped());
+            drop(again);
+            assert_eq!(
+                backplane.trigger_mappings(),
+                [
+                    (TrigLine::TrigPanelIn, TrigLine::TrigTtl3),
+                    (TrigLine::TrigPanelIn, TrigLine::TrigEcl0),
+                ]
+            );
+        }
+        assert_eq!(backplane.trigger_mappings(), []);
+
+        let map = sess.map_trigger(TrigLine::TrigTtl0, TrigLine::TrigTtl1, TrigMapMode::Null)?;
+        std::mem::forget(sess.map_trigger(
+            TrigLine::TrigTtl0,
+            TrigLine::TrigTtl2,
+            TrigMapMode::Null,
+        )?);
+        sess.unmap_trigger(TrigLine::TrigTtl0, None)?;
+        assert_eq!(backplane.trigger_mappings(), []);
+        assert_eq!(map.unmap(), Err(Error(ErrorCode::ErrorTrigNmapped)));
+        assert_eq!(
+            sess.map_trigger(TrigLine::TrigTtl0, TrigLine::TrigTtl0, TrigMapMode::Null)
+                .err(),
+            Some(Error(ErrorCode::ErrorInvLine))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! USB control transfers and USB device details, see [`Instrument::usb_control_in`] and [`Instrument::usb_info`]
+//!
+//! Control transfers go through the default control pipe of the device, with the setup packet given field by field as in the USB specification.
+//! [`UsbtmcRequest`] lists the class requests of USBTMC, e.g. to blink the front panel indicator of a USBTMC instrument:
+//!
+//! ```no_run
+//! # fn main() -> visa_rs::Result<()> {
+//! use visa_rs::{flags::AccessMode, usb::{UsbtmcRequest, UsbtmcStatus}, AsResourceManager, DefaultRM, TIMEOUT_IMMEDIATE};
+//! let rm = DefaultRM::new()?;
+//! let instr = rm.open(
+//!     &std::ffi::CString::new("USB0::0x0957::0x1798::MY12345678::INSTR").unwrap().into(),
+//!     AccessMode::NO_LOCK,
+//!     TIMEOUT_IMMEDIATE,
+//! )?;
+//! let index = instr.usb_info()?.interface_number as _;
+//! let request = UsbtmcRequest::IndicatorPulse;
+//! let reply = instr.usb_control_in(request.request_type(), request as _, 0, index, 1)?;
+//! assert_eq!(UsbtmcStatus::try_from(reply[0]), Ok(UsbtmcStatus::Success));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    backend::Backend,
+    enums::{
+        attribute::{
+            Attr4882Compliant, AttrManfId, AttrManfName, AttrModelCode, AttrModelName,
+            AttrUsbIntfcNum, AttrUsbMaxIntrSize, AttrUsbProtocol, AttrUsbSerialNum, HasAttribute,
+            SpecAttr,
+        },
+        status::ErrorCode,
+    },
+    session::AsRawSs,
+    wrap_raw_error_in_unsafe, Error, Instrument, Result, VisaString,
+};
+use visa_sys as vs;
+
+/// `bmRequestType` of a device-to-host class request addressed to an interface
+pub const CLASS_INTERFACE_IN: u8 = 0xA1;
+/// `bmRequestType` of a device-to-host class request addressed to an endpoint
+pub const CLASS_ENDPOINT_IN: u8 = 0xA2;
+
+/// `bRequest` of the USBTMC class requests, all of them device-to-host.
+///
+/// The device answers with a [`UsbtmcStatus`] in the first byte, see the USBTMC specification for the rest of the reply.
+#[derive(
+    num_enum::TryFromPrimitive,
+    num_enum::IntoPrimitive,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    PartialOrd,
+    Eq,
+    Ord,
+    Hash,
+)]
+#[repr(u8)]
+pub enum UsbtmcRequest {
+    /// Aborts a Bulk-OUT transfer, `wValue` is the bTag of the transfer and `wIndex` the Bulk-OUT endpoint
+    InitiateAbortBulkOut = 1,
+    /// Status of a previous [`InitiateAbortBulkOut`](Self::InitiateAbortBulkOut)
+    CheckAbortBulkOutStatus = 2,
+    /// Aborts a Bulk-IN transfer, `wValue` is the bTag of the transfer and `wIndex` the Bulk-IN endpoint
+    InitiateAbortBulkIn = 3,
+    /// Status of a previous [`InitiateAbortBulkIn`](Self::InitiateAbortBulkIn)
+    CheckAbortBulkInStatus = 4,
+    /// Clears the input and output buffers of the device
+    InitiateClear = 5,
+    /// Status of a previous [`InitiateClear`](Self::InitiateClear)
+    CheckClearStatus = 6,
+    /// Reads the 24 bytes of USBTMC capabilities of the interface
+    GetCapabilities = 7,
+    /// Blinks the front panel indicator of the device, if it supports it
+    IndicatorPulse = 64,
+}
+
+impl UsbtmcRequest {
+    /// `bmRequestType` of the request, aborting bulk transfers is addressed to an endpoint and the others to the interface
+    pub fn request_type(self) -> u8 {
+        match self {
+            Self::InitiateAbortBulkOut
+            | Self::CheckAbortBulkOutStatus
+            | Self::InitiateAbortBulkIn
+            | Self::CheckAbortBulkInStatus => CLASS_ENDPOINT_IN,
+            _ => CLASS_INTERFACE_IN,
+        }
+    }
+}
+
+/// First byte of the reply to a [`UsbtmcRequest`]
+#[derive(
+    num_enum::TryFromPrimitive,
+    num_enum::IntoPrimitive,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    PartialOrd,
+    Eq,
+    Ord,
+    Hash,
+)]
+#[repr(u8)]
+pub enum UsbtmcStatus {
+    Success = 0x01,
+    Pending = 0x02,
+    Failed = 0x80,
+    TransferNotInProgress = 0x81,
+    SplitNotInProgress = 0x82,
+    SplitInProgress = 0x83,
+}
+
+/// Descriptor details of a USB session, read by [`Instrument::usb_info`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UsbInfo {
+    /// `idVendor` of the device descriptor
+    pub manufacturer_id: u16,
+    /// `idProduct` of the device descriptor
+    pub model_code: u16,
+    pub manufacturer_name: VisaString,
+    pub model_name: VisaString,
+    pub serial_number: VisaString,
+    /// `bInterfaceNumber` of the interface used by the session, the `wIndex` of interface requests
+    pub interface_number: u8,
+    /// `bInterfaceProtocol`, 1 for USB488 and 0 for plain USBTMC
+    pub protocol: u8,
+    /// Largest USB interrupt stored in a [`EventUsbIntr`](crate::enums::event::EventKind::EventUsbIntr) event, in bytes
+    pub max_interrupt_size: u16,
+    /// Whether the device is IEEE 488.2 compliant, `None` for USB RAW sessions
+    pub compliant_4882: Option<bool>,
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Performs a USB control transfer from the device and returns the bytes read, at most `len`.
+    ///
+    /// `request_type`, `request`, `value` and `index` are the `bmRequestType`, `bRequest`, `wValue` and `wIndex` fields of the setup packet,
+    /// the direction bit of `request_type` must be set.
+    pub fn usb_control_in(
+        &self,
+        request_type: u8,
+        request: u8,
+        value: u16,
+        index: u16,
+        len: u16,
+    ) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; len as _];
+        let mut ret_cnt: vs::ViUInt16 = 0;
+        wrap_raw_error_in_unsafe!(B::usb_control_in(
+            self.as_raw_ss(),
+            request_type as _,
+            request as _,
+            value,
+            index,
+            len,
+            buf.as_mut_ptr(),
+            &mut ret_cnt as _
+        ))?;
+        buf.truncate(ret_cnt as _);
+        Ok(buf)
+    }
+
+    /// Performs a USB control transfer sending `data` to the device, see [`usb_control_in`](Self::usb_control_in) for the other parameters.
+    ///
+    /// Fails with [`ErrorInvParameter`](ErrorCode::ErrorInvParameter) if `data` is longer than `u16::MAX` bytes.
+    pub fn usb_control_out(
+        &self,
+        request_type: u8,
+        request: u8,
+        value: u16,
+        index: u16,
+        data: &[u8],
+    ) -> Result<()> {
+        let len: u16 = data
+            .len()
+            .try_into()
+            .map_err(|_| Error(ErrorCode::ErrorInvParameter))?;
+        wrap_raw_error_in_unsafe!(B::usb_control_out(
+            self.as_raw_ss(),
+            request_type as _,
+            request as _,
+            value,
+            index,
+            len,
+            if data.is_empty() {
+                vs::VI_NULL as _
+            } else {
+                data.as_ptr()
+            }
+        ))?;
+        Ok(())
+    }
+
+    /// Reads the descriptor attributes of a USB session in one call
+    pub fn usb_info(&self) -> Result<UsbInfo> {
+        let compliant_4882 = match Attr4882Compliant::get_from(self) {
+            Ok(x) => Some(x.into_inner() != vs::VI_FALSE as vs::ViBoolean),
+            Err(Error(ErrorCode::ErrorNsupAttr)) => None,
+            Err(e) => return Err(e),
+        };
+        Ok(UsbInfo {
+            manufacturer_id: AttrManfId::get_from(self)?.into_inner() as _,
+            model_code: AttrModelCode::get_from(self)?.into_inner() as _,
+            manufacturer_name: AttrManfName::get_from(self)?.into_inner(),
+            model_name: AttrModelName::get_from(self)?.into_inner(),
+            serial_number: AttrUsbSerialNum::get_from(self)?.into_inner(),
+            interface_number: AttrUsbIntfcNum::get_from(self)?.into_inner() as _,
+            protocol: AttrUsbProtocol::get_from(self)?.into_inner() as _,
+            max_interrupt_size: AttrUsbMaxIntrSize::get_from(self)?.into_inner() as _,
+            compliant_4882,
+        })
+    }
+
+    /// Sets the largest USB interrupt stored in an event, only allowed while USB interrupt events are disabled
+    pub fn set_usb_max_intr_size(&self, size: u16) -> Result<()> {
+        // the range is the whole u16
+        self.set_attr(unsafe { AttrUsbMaxIntrSize::new_unchecked(size as _) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::{Mock, MockResource},
+        enums::{
+            attribute::{self},
+            status::ErrorCode,
+        },
+        DefaultRM, Error,
+    };
+
+    use anyhow::Result;
+
+    #[test]
+    fn usb_control() -> Result<()> {
+        use crate::{
+            backend::mock::ControlTransfer,
+            usb::{UsbtmcRequest, UsbtmcStatus},
+        };
+        use attribute::{
+            Attr4882Compliant, AttrKind, AttrManfId, AttrModelCode, AttrUsbIntfcNum,
+            AttrUsbMaxIntrSize, AttrUsbProtocol,
+        };
+        let pulse = UsbtmcRequest::IndicatorPulse;
+        let dev = MockResource::new("USB0::0x0957::0x1798::MY001::INSTR")
+            .with_attr(unsafe { AttrManfId::new_unchecked(0x0957) })
+            .with_attr(unsafe { AttrModelCode::new_unchecked(0x1798) })
+            .with_str_attr(AttrKind::AttrManfName, "ACME")
+            .with_str_attr(AttrKind::AttrModelName, "Scope 1")
+            .with_str_attr(AttrKind::AttrUsbSerialNum, "MY001")
+            .with_attr(unsafe { AttrUsbIntfcNum::new_unchecked(0) })
+            .with_attr(unsafe { AttrUsbProtocol::new_unchecked(1) })
+            .with_attr(AttrUsbMaxIntrSize::new_checked(2).unwrap())
+            .with_attr(Attr4882Compliant::VI_TRUE)
+            .with_control_in(pulse.request_type(), pulse as _, [0x01, 0xFF])
+            .register();
+        let gpib_dev = MockResource::new("GPIB0::24::INSTR").register();
+        let rm = DefaultRM::<Mock>::with_backend()?;
+        let instr = dev.open(&rm)?;
+
+        let info = instr.usb_info()?;
+        assert_eq!((info.manufacturer_id, info.model_code), (0x0957, 0x1798));
+        assert_eq!(info.serial_number.to_string(), "MY001");
+        assert_eq!((info.interface_number, info.protocol), (0, 1));
+        assert_eq!(info.max_interrupt_size, 2);
+        assert_eq!(info.compliant_4882, Some(true));
+        instr.set_usb_max_intr_size(64)?;
+        assert_eq!(instr.usb_info()?.max_interrupt_size, 64);
+
+        let reply = instr.usb_control_in(pulse.request_type(), pulse as _, 0, 0, 1)?;
+        assert_eq!(reply, [0x01]);
+        assert_eq!(UsbtmcStatus::try_from(reply[0]), Ok(UsbtmcStatus::Success));
+        // no canned reply, the device stalls
+        let clear = UsbtmcRequest::InitiateClear;
+        assert_eq!(
+            instr.usb_control_in(clear.request_type(), clear as _, 0, 0, 1),
+            Err(Error(ErrorCode::ErrorIo))
+        );
+        instr.usb_control_out(0x21, 0x0A, 0x1234, 0, b"abc")?;
+        assert_eq!(
+            dev.control_transfers(),
+            [
+                ControlTransfer {
+                    request_type: 0xA1,
+                    request: 64,
+                    value: 0,
+                    index: 0,
+                    data: vec![0x01],
+                },
+                ControlTransfer {
+                    request_type: 0x21,
+                    request: 0x0A,
+                    value: 0x1234,
+                    index: 0,
+                    data: b"abc".to_vec(),
+                },
+            ]
+        );
+
+        let gpib = gpib_dev.open(&rm)?;
+        assert_eq!(
+            gpib.usb_control_out(0x21, 0x0A, 0, 0, &[]),
+            Err(Error(ErrorCode::ErrorNsupOper))
+        );
+        Ok(())
+    }
+}
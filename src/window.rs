@@ -0,0 +1,194 @@
+use super::*;
+use enums::{memory::AddressSpace, status::ErrorCode};
+
+/// A window of bus memory mapped by [`Instrument::map_address`], unmapped on drop.
+///
+/// Accesses go through viPeekXX/viPokeXX, honouring `VI_ATTR_WIN_BYTE_ORDER`,
+/// and are checked against the window size, returning [`ErrorInvOffset`](ErrorCode::ErrorInvOffset) instead of touching memory out of it.
+///
+/// viPeekXX/viPokeXX return nothing, so the window cannot report failures of the access itself:
+/// a bus error reads an unspecified value and drops the write, or may be fatal depending on the VISA implementation.
+///
+/// The window is only valid while it is the one mapped in its session, which VISA guarantees by refusing to map another one
+/// with `VI_ERROR_WINDOW_MAPPED`. Unmapping it behind its back, e.g. calling viUnmapAddress on the raw session, leaves accesses to a stale address.
+#[derive(Debug)]
+pub struct MappedWindow<'a, B: Backend = Visa> {
+    instr: &'a Instrument<B>,
+    base: vs::ViAddr,
+    space: AddressSpace,
+    offset: u64,
+    size: usize,
+}
+
+macro_rules! impl_peek_poke {
+    ($($bits:literal $ty:ty: $peek:ident $poke:ident => $b_peek:ident $b_poke:ident;)*) => {
+        $(
+            #[doc = concat!("Reads a ", $bits, "-bit value at `offset` bytes from the start of the window.")]
+            pub fn $peek(&self, offset: usize) -> Result<$ty> {
+                let address = self.address(offset, std::mem::size_of::<$ty>())?;
+                let mut val: $ty = 0;
+                unsafe { B::$b_peek(self.instr.as_raw_ss(), address, &mut val as *mut $ty as *mut _) };
+                Ok(val)
+            }
+
+            #[doc = concat!("Writes a ", $bits, "-bit value at `offset` bytes from the start of the window.")]
+            pub fn $poke(&self, offset: usize, val: $ty) -> Result<()> {
+                let address = self.address(offset, std::mem::size_of::<$ty>())?;
+                unsafe { B::$b_poke(self.instr.as_raw_ss(), address, val as _) };
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl<B: Backend> MappedWindow<'_, B> {
+    /// Address of `len` bytes at `offset`, if they are all in the window
+    fn address(&self, offset: usize, len: usize) -> Result<vs::ViAddr> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.size => Ok(self.base.wrapping_byte_add(offset)),
+            _ => Err(Error(ErrorCode::ErrorInvOffset)),
+        }
+    }
+
+    impl_peek_poke! {
+        "8" u8: peek_u8 poke_u8 => peek8 poke8;
+        "16" u16: peek_u16 poke_u16 => peek16 poke16;
+        "32" u32: peek_u32 poke_u32 => peek32 poke32;
+        "64" u64: peek_u64 poke_u64 => peek64 poke64;
+    }
+
+    /// Address space the window is mapped in
+    pub fn space(&self) -> AddressSpace {
+        self.space
+    }
+
+    /// Offset of the window in its address space
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Size of the window in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Address returned by VISA, only dereferenceable if `VI_ATTR_WIN_ACCESS` is `VI_DEREF_ADDR`
+    pub fn base_address(&self) -> vs::ViAddr {
+        self.base
+    }
+
+    /// Unmaps the window, reporting the error dropping would ignore
+    pub fn unmap(self) -> Result<()> {
+        let vi = self.instr.as_raw_ss();
+        std::mem::forget(self);
+        wrap_raw_error_in_unsafe!(B::unmap_address(vi))?;
+        Ok(())
+    }
+}
+
+impl<B: Backend> Drop for MappedWindow<'_, B> {
+    fn drop(&mut self) {
+        unsafe {
+            B::unmap_address(self.instr.as_raw_ss());
+        }
+    }
+}
+
+impl<B: Backend> Instrument<B> {
+    /// Maps the specified memory space into the process's address space.
+    ///
+    /// This operation maps in a specified memory space. The memory space that is mapped is dictated via the `space` parameter. The `offset` parameter specifies the beginning of the region to map and the `size` parameter specifies the size of the region to be mapped. Only one window can be mapped per session, mapping again before the returned window is dropped fails with `VI_ERROR_WINDOW_MAPPED`.
+    ///
+    /// The `suggested` parameter is a hint for the address at which the window should be mapped, VISA is free to ignore it.
+    ///
+    /// The window is accessed with the bounds-checked peek and poke methods of [`MappedWindow`], and unmapped when it is dropped.
+    pub fn map_address(
+        &self,
+        space: AddressSpace,
+        offset: u64,
+        size: usize,
+        suggested: impl Into<Option<usize>>,
+    ) -> Result<MappedWindow<'_, B>> {
+        let mut base: vs::ViAddr = std::ptr::null_mut();
+        wrap_raw_error_in_unsafe!(B::map_address_ex(
+            self.as_raw_ss(),
+            space as _,
+            offset as _,
+            size as _,
+            vs::VI_FALSE as _,
+            suggested.into().unwrap_or(vs::VI_NULL as _) as _,
+            &mut base as _
+        ))?;
+        Ok(MappedWindow {
+            instr: self,
+            base,
+            space,
+            offset,
+            size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backend::mock::MockResource,
+        enums::{
+            attribute::{self, SpecAttr},
+            status::ErrorCode,
+        },
+        Error,
+    };
+
+    use anyhow::Result;
+
+    #[test]
+    fn mapped_window() -> Result<()> {
+        use crate::enums::memory::AddressSpace;
+        let (dev, _rm, instr) = MockResource::new("PXI0::7-0.0::INSTR")
+            .with_memory(
+                AddressSpace::PxiBar0Space,
+                0x100,
+                [0xAB, 0xCD, 0, 0, 0, 0, 0, 0],
+            )
+            .open()?;
+        assert_eq!(
+            instr
+                .map_address(AddressSpace::PxiBar0Space, 0x100, 16, None)
+                .map(|_| ()),
+            Err(Error(ErrorCode::ErrorInvOffset))
+        );
+
+        let window = instr.map_address(AddressSpace::PxiBar0Space, 0x100, 8, None)?;
+        assert_eq!(window.size(), 8);
+        assert_eq!(
+            instr
+                .map_address(AddressSpace::PxiBar0Space, 0x100, 8, None)
+                .map(|_| ()),
+            Err(Error(ErrorCode::ErrorWindowMapped))
+        );
+        assert_eq!(window.peek_u8(1)?, 0xCD);
+        assert_eq!(window.peek_u16(0)?, 0xABCD);
+        window.poke_u32(4, 0x01020304)?;
+        assert_eq!(window.peek_u64(0)?, 0xABCD_0000_0102_0304);
+        assert_eq!(window.peek_u16(7), Err(Error(ErrorCode::ErrorInvOffset)));
+        assert_eq!(
+            window.poke_u8(usize::MAX, 0),
+            Err(Error(ErrorCode::ErrorInvOffset))
+        );
+        assert_eq!(
+            dev.memory(AddressSpace::PxiBar0Space, 0x104, 4),
+            Some(vec![1, 2, 3, 4])
+        );
+        drop(window);
+
+        let window = instr.map_address(AddressSpace::PxiBar0Space, 0x104, 4, None)?;
+        assert_eq!(window.peek_u8(0)?, 1);
+        window.unmap()?;
+        assert_eq!(
+            attribute::AttrWinAccess::get_from(&instr)?,
+            attribute::AttrWinAccess::VI_NMAPPED
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    ffi::CString,
+    io::{BufRead, BufReader, Read, Write},
+};
+
+use anyhow::Result;
+use visa_rs::{
+    backend::mock::{Mock, MockResource},
+    enums::{
+        attribute::{self, HasAttribute, SpecAttr},
+        status::ErrorCode,
+    },
+    flags::AccessMode,
+    session::{AsRawSs, BorrowedSs},
+    AsResourceManager, DefaultRM, Error, TIMEOUT_IMMEDIATE,
+};
+
+fn expr(s: &str) -> visa_rs::VisaString {
+    CString::new(s).unwrap().into()
+}
+
+#[test]
+fn find_and_query() -> Result<()> {
+    let _a = MockResource::new("GPIB0::11::INSTR")
+        .respond("*IDN?", "MOCK,GPIB,0,1\n")
+        .register();
+    let _b = MockResource::new("GPIB0::12::INSTR").register();
+    let rm = DefaultRM::<Mock>::with_backend()?;
+    let found = rm
+        .find_res_list(&expr("GPIB0::1[12]::INSTR"))?
+        .collect::<visa_rs::Result<Vec<_>>>()?;
+    assert_eq!(found.len(), 2);
+    assert_eq!(
+        rm.find_res(&expr("GPIB0::13::INSTR")),
+        Err(Error(ErrorCode::ErrorRsrcNfound))
+    );
+    let (ty, num) = rm.parse_res(&found[0])?;
+    assert_eq!(ty.into_inner(), visa_rs::vs::VI_INTF_GPIB as _);
+    assert_eq!(num.into_inner(), 0);
+
+    let mut instr = rm.open(&found[0], AccessMode::NO_LOCK, TIMEOUT_IMMEDIATE)?;
+    instr.write_all(b"*idn? \n")?;
+    let mut line = String::new();
+    BufReader::new(&instr).read_line(&mut line)?;
+    assert_eq!(line, "MOCK,GPIB,0,1\n");
+    assert_eq!(
+        attribute::AttrRsrcName::get_from(&instr)?
+            .into_inner()
+            .to_string(),
+        "GPIB0::11::INSTR"
+    );
+    Ok(())
+}
+
+#[test]
+fn find_list_count_and_close() -> Result<()> {
+    let _a = MockResource::new("TCPIP0::10.0.0.1::INSTR").register();
+    let _b = MockResource::new("TCPIP0::10.0.0.2::INSTR").register();
+    let _c = MockResource::new("TCPIP0::10.0.0.3::INSTR").register();
+    let rm = DefaultRM::<Mock>::with_backend()?;
+    let mut list = rm.find_res_list(&expr("TCPIP0::10.0.0.?::INSTR"))?;
+    assert_eq!(list.total(), 3);
+    assert_eq!(list.len(), 3);
+    assert_eq!(
+        attribute::AttrRmSession::get_from(&list)?.into_inner() as visa_rs::session::RawSs,
+        rm.as_raw_ss()
+    );
+    list.next().transpose()?;
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.total(), 3);
+    let raw = list.as_raw_ss();
+    assert_eq!(list.collect::<visa_rs::Result<Vec<_>>>()?.len(), 2);
+    let closed = unsafe { BorrowedSs::<Mock>::borrow_raw(raw) };
+    assert_eq!(
+        attribute::AttrRmSession::get_from(&closed).map(|_| ()),
+        Err(Error(ErrorCode::ErrorInvObject))
+    );
+    Ok(())
+}
+
+#[test]
+fn read_termination() -> Result<()> {
+    let (dev, _rm, instr) = MockResource::new("ASRL7::INSTR").open()?;
+    let mut buf = [0u8; 4];
+
+    dev.push_response("a\nbcdef");
+    assert_eq!((&instr).read(&mut buf)?, 4);
+    assert_eq!(&buf, b"a\nbc");
+    instr.set_attr(attribute::AttrTermcharEn::VI_TRUE)?;
+    assert_eq!((&instr).read(&mut buf)?, 3);
+    assert_eq!(&buf[..3], b"def");
+
+    dev.push_response("a\nb");
+    assert_eq!((&instr).read(&mut buf)?, 2);
+    assert_eq!((&instr).read(&mut buf)?, 1);
+    let err = (&instr).read(&mut buf).unwrap_err();
+    assert_eq!(Error::try_from(err).unwrap(), Error(ErrorCode::ErrorTmo));
+    Ok(())
+}
+
+#[test]
+fn responder_and_written() -> Result<()> {
+    let (dev, _rm, mut instr) = MockResource::new("TCPIP0::mock-responder::INSTR")
+        .with_responder(|msg| msg.starts_with(b"MEAS").then(|| b"1.5\n".to_vec()))
+        .open()?;
+    instr.write_all(b"CONF\n")?;
+    instr.write_all(b"MEAS?\n")?;
+    assert_eq!(dev.take_written(), b"CONF\nMEAS?\n");
+    assert!(dev.written().is_empty());
+    let mut buf = [0u8; 16];
+    assert_eq!(instr.read(&mut buf)?, 4);
+    assert_eq!(&buf[..4], b"1.5\n");
+
+    instr.assert_trigger(visa_rs::enums::assert::AssertTrigPro::TrigProtDefault)?;
+    assert_eq!(dev.triggers(), 1);
+    dev.set_stb(0x50);
+    assert_eq!(instr.read_stb()?, 0x50);
+    assert_eq!(instr.read_stb()?, 0x10);
+    Ok(())
+}
+
+#[test]
+fn close_rm_closes_sessions() -> Result<()> {
+    let (_dev, rm, instr) = MockResource::new("VXI0::5::INSTR").open()?;
+    rm.close_all();
+    assert_eq!(
+        attribute::AttrTmoValue::get_from(&instr).map(|_| ()),
+        Err(Error(ErrorCode::ErrorInvObject))
+    );
+    Ok(())
+}
@@ -8,7 +8,10 @@ use syn::{
     Ident, LitInt, LitStr, Result, Token,
 };
 
-use crate::{attrs::range::RangeCore, match_tokens, subst_ident};
+use crate::{
+    attrs::range::{Checked, RangeCore},
+    match_tokens, subst_ident,
+};
 mod range;
 pub struct Attributes {
     _vis: Token![pub],
@@ -49,20 +52,44 @@ impl ToTokens for Attributes {
             attr.constructors(tokens);
             attr.default_impl(tokens);
             attr.kind_impl(tokens);
+            attr.value_enum(tokens);
+            attr.value_flags(tokens);
         }
         let fields = self.attrs.iter().map(|x| x.struct_name());
         let docs = self.attrs.iter().map(|x| &x.desc);
+        let serde_skip = self.attrs.iter().map(|x| {
+            x.is_pointer()
+                .then(|| quote!(#[cfg_attr(feature = "serde", serde(skip))]))
+        });
         let enum_name = &self.ident;
         quote!(
             #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+            #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
             pub enum #enum_name{
                 #(
                     #[doc=#docs]
+                    #serde_skip
                     #fields(#fields)
                 ),*
             }
         )
         .to_tokens(tokens);
+        let (value_enums, value_structs): (Vec<_>, Vec<_>) = self
+            .attrs
+            .iter()
+            .filter(|x| x.named_values().is_some() || x.bit_flags().is_some())
+            .map(|x| (x.value_enum_name(), x.struct_name()))
+            .unzip();
+        quote!(
+            #(
+                impl From<#value_enums> for #enum_name{
+                    fn from(s:#value_enums)->Self{
+                        Self::#value_structs(s.into())
+                    }
+                }
+            )*
+        )
+        .to_tokens(tokens);
         let fields = self.attrs.iter().map(|x| x.struct_name());
         quote!(
             #(
@@ -84,12 +111,51 @@ impl ToTokens for Attributes {
             t.into_iter().map(move |(ty, cfg)| {
                 quote!(
                         #cfg
-                        AttrKind::#ty => Self::from(<#f as super::SpecAttr>::zero())
+                        AttrKind::#ty => Some(Self::from(<#f as super::SpecAttr>::zero()))
                 )
             })
         });
+        let meta_arms = self.attrs.iter().flat_map(|x| x.meta_arms());
+        let (listed, listed_cfg): (Vec<_>, Vec<_>) = self
+            .attrs
+            .iter()
+            .flat_map(|x| struct_name_to_kind_name(&x.id))
+            .unzip();
+        quote!(
+            impl AttrKind{
+                /// Kinds of all the attributes listed in the NI-VISA document, i.e. those [`Attribute`] can hold
+                pub fn listed() -> Vec<AttrKind>{
+                    let mut kinds = Vec::new();
+                    #(
+                        #listed_cfg
+                        kinds.push(AttrKind::#listed);
+                    )*
+                    kinds
+                }
+
+                /// Access, scope, type, default and range listed in the NI-VISA document, `None` for attributes not listed
+                pub fn meta(&self) -> Option<super::AttrMeta>{
+                    #[allow(unreachable_patterns)]
+                    match self{
+                        #(#meta_arms,)*
+                        _=>None
+                    }
+                }
+            }
+        )
+        .to_tokens(tokens);
         let fields1 = self.attrs.iter().map(|x| x.struct_name());
         let fields2 = self.attrs.iter().map(|x| x.struct_name());
+        let fields3 = self.attrs.iter().map(|x| x.struct_name());
+        let visa_str = self.attrs.iter().map(|x| {
+            let field = x.struct_name();
+            if let TypeCore::UnArch(ref t) = x.ty.core {
+                if t == "ViString" || t == "ViRsrc" {
+                    return quote_spanned!(t.span()=> Self::#field(s)=>Some(&s.value[..]));
+                }
+            }
+            quote_spanned!(x.id.span()=> Self::#field(_)=>None)
+        });
         let as_attr_state=self.attrs.iter().map(|x| {
             let field=x.struct_name();
             if let TypeCore::UnArch(ref t)=x.ty.core{
@@ -109,11 +175,18 @@ impl ToTokens for Attributes {
         quote!(
             impl #enum_name{
                 pub(crate) unsafe fn from_kind(kind:AttrKind) -> Self{
+                    Self::try_from_kind(kind).unwrap_or_else(||
+                        unimplemented!("attribute '{:?}' not listed in NI-VISA document, so not supported yet",kind)
+                    )
+                }
+
+                pub(crate) unsafe fn try_from_kind(kind:AttrKind) -> Option<Self>{
+                    #[allow(unreachable_patterns)]
                     match kind{
                         #(
                             #match_field
                         ,)*
-                        s=>unimplemented!("attribute '{:?}' not listed in NI-VISA document, so not supported yet",s)
+                        _=>None
                     }
                 }
 
@@ -136,6 +209,18 @@ impl ToTokens for Attributes {
                         #(#as_attr_state),*
                     }
                 }
+
+                pub(crate) fn c_size(&self)-> usize{
+                    match self{
+                        #(Self::#fields3(s)=>::std::mem::size_of_val(&s.value)),*
+                    }
+                }
+
+                pub(crate) fn visa_str(&self)-> Option<&[u8]>{
+                    match self{
+                        #(#visa_str),*
+                    }
+                }
             }
         )
         .to_tokens(tokens);
@@ -173,9 +258,11 @@ impl Attr {
         let id = self.struct_name();
         match ty.core {
             TypeCore::UnArch(ref ty) => {
+                let (serde, serde_impls) = self.serde_def(ty, TokenStream2::new());
                 quote!(
                     #[doc= #desc]
                     #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+                    #serde
                     pub struct #id{
                         value:vs::#ty
                     }
@@ -184,6 +271,7 @@ impl Attr {
                             self.value
                         }
                     }
+                    #serde_impls
                 )
                 .to_tokens(tokens);
             }
@@ -197,12 +285,19 @@ impl Attr {
                         LitStr::new(&x.to_string(), x.span())
                     }
                 });
-                let ty = a.iter().map(|x| &x.core);
+                let arch: Vec<_> = arch.collect();
+                let ty: Vec<_> = a.iter().map(|x| &x.core).collect();
+                let (serde, serde_impls): (Vec<_>, Vec<_>) = ty
+                    .iter()
+                    .zip(&arch)
+                    .map(|(ty, arch)| self.serde_def(ty, quote!(#[cfg(target_pointer_width = #arch)])))
+                    .unzip();
                 quote!(
                     #(
                         #[cfg(target_pointer_width = #arch)]
                         #[doc= #desc]
                         #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+                        #serde
                         pub struct #id{
                             value:vs::#ty
                         }
@@ -212,12 +307,345 @@ impl Attr {
                                 self.value
                             }
                         }
+                        #serde_impls
                     )*
                 )
                 .to_tokens(tokens);
             }
         }
     }
+    /// Serde attributes of the struct and the conversions it is (de)serialized through,
+    /// so that deserialized values are checked by `new_checked` like constructed ones
+    fn serde_def(&self, ty: &Ident, cfg: TokenStream2) -> (TokenStream2, TokenStream2) {
+        if self.is_pointer() {
+            return (TokenStream2::new(), TokenStream2::new());
+        }
+        let derive = quote!(derive(::serde::Serialize, ::serde::Deserialize));
+        if self.named_values().is_some() {
+            // by the names of the values, converted in `value_enum`
+            let repr = LitStr::new(&format!("super::NamedValue<vs::{}>", ty), ty.span());
+            return (
+                quote!(#[cfg_attr(feature = "serde", #derive, serde(try_from = #repr, into = #repr))]),
+                TokenStream2::new(),
+            );
+        }
+        let id = self.struct_name();
+        let checked = self.checked_name();
+        let repr = LitStr::new(&format!("vs::{}", ty), ty.span());
+        (
+            quote!(#[cfg_attr(feature = "serde", #derive, serde(try_from = #repr, into = #repr))]),
+            quote!(
+                #cfg
+                #[cfg(feature = "serde")]
+                impl TryFrom<vs::#ty> for #id{
+                    type Error = crate::Error;
+                    /// Fails with [`ErrorNsupAttrState`](crate::enums::status::ErrorCode::ErrorNsupAttrState) for a value out of the range of the attribute
+                    fn try_from(value:vs::#ty)->::std::result::Result<Self, Self::Error>{
+                        Self::#checked(value)
+                            .ok_or_else(|| crate::enums::status::ErrorCode::ErrorNsupAttrState.into())
+                    }
+                }
+                #cfg
+                #[cfg(feature = "serde")]
+                impl From<#id> for vs::#ty{
+                    fn from(a:#id)->Self{
+                        a.value
+                    }
+                }
+            ),
+        )
+    }
+    /// Constructor checking the range, `new_checked` for writable attributes and `deserialize_checked` for read-only ones
+    fn checked_name(&self) -> Ident {
+        let name = if self.is_writeable() {
+            "new_checked"
+        } else {
+            "deserialize_checked"
+        };
+        Ident::new(name, self.id.span())
+    }
+    /// Addresses in this process, not worth (de)serializing
+    fn is_pointer(&self) -> bool {
+        matches!(self.ty.core, TypeCore::UnArch(ref t) if t == "ViBuf" || t == "ViAddr" || t == "ViAUInt8")
+    }
+    fn value_enum_name(&self) -> Ident {
+        let name = self.struct_name().to_string();
+        Ident::new(name.strip_prefix("Attr").unwrap_or(&name), self.id.span())
+    }
+    /// Values of an enumerated attribute, i.e. one whose range only lists named values
+    fn named_values(&self) -> Option<Vec<(Ident, LitInt)>> {
+        if !matches!(self.ty.core, TypeCore::UnArch(_)) || self.is_bitmask() {
+            return None;
+        }
+        let mut values: Vec<(Ident, LitInt)> = Vec::new();
+        let mut push = |n: &RangeCore| -> Option<()> {
+            for (id, v) in n.named_values()? {
+                if !values.iter().any(|(x, _)| x == &id) {
+                    values.push((id, v));
+                }
+            }
+            Some(())
+        };
+        match self.range {
+            Range::NoPort(ref n) => push(n)?,
+            Range::Port(ref p) => p.iter().try_for_each(|x| push(&x.core))?,
+        }
+        // booleans are left as they are
+        let boolean = values.iter().any(|(x, _)| x == "VI_TRUE" || x == "VI_FALSE");
+        (values.len() > 1 && !boolean).then_some(values)
+    }
+    fn value_enum(&self, tokens: &mut TokenStream2) {
+        let (Some(values), TypeCore::UnArch(ref ty)) = (self.named_values(), &self.ty.core) else {
+            return;
+        };
+        let struct_name = self.struct_name();
+        let enum_name = self.value_enum_name();
+        let checked = self.checked_name();
+        let doc = format!("Values listed for [`{}`]", struct_name);
+        let ids: Vec<_> = values.iter().map(|(id, _)| id).collect();
+        let variants = variant_names(&ids);
+        let visa_names: Vec<_> = ids.iter().map(|x| LitStr::new(&x.to_string(), x.span())).collect();
+        let values: Vec<_> = values.iter().map(|(_, v)| v).collect();
+        quote!(
+            #[doc=#doc]
+            #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+            pub enum #enum_name{
+                #(
+                    #[doc=#visa_names]
+                    #variants
+                ),*
+            }
+            impl #enum_name{
+                /// Name of the value in VISA
+                pub fn visa_name(&self)->&'static str{
+                    match self{
+                        #(Self::#variants=>#visa_names),*
+                    }
+                }
+            }
+            impl ::std::fmt::Display for #enum_name{
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.write_str(self.visa_name())
+                }
+            }
+            impl From<#enum_name> for #struct_name{
+                fn from(v:#enum_name)->Self{
+                    match v{
+                        #(#enum_name::#variants=>Self{value:#values as _}),*
+                    }
+                }
+            }
+            impl TryFrom<#struct_name> for #enum_name{
+                type Error = crate::Error;
+                /// Fails with [`ErrorNsupAttrState`](crate::enums::status::ErrorCode::ErrorNsupAttrState) for a value not listed in VISA
+                fn try_from(a:#struct_name)->::std::result::Result<Self, Self::Error>{
+                    #(
+                        if a.value == #values as vs::#ty{
+                            return Ok(Self::#variants);
+                        }
+                    )*
+                    Err(crate::enums::status::ErrorCode::ErrorNsupAttrState.into())
+                }
+            }
+            #[cfg(feature = "serde")]
+            impl TryFrom<super::NamedValue<vs::#ty>> for #struct_name{
+                type Error = crate::Error;
+                /// Fails with [`ErrorNsupAttrState`](crate::enums::status::ErrorCode::ErrorNsupAttrState) for an unknown name or a value out of the range of the attribute
+                fn try_from(v:super::NamedValue<vs::#ty>)->::std::result::Result<Self, Self::Error>{
+                    match v{
+                        super::NamedValue::Name(name)=>match name.as_str(){
+                            #(#visa_names=>Ok(#enum_name::#variants.into()),)*
+                            _=>Err(crate::enums::status::ErrorCode::ErrorNsupAttrState.into()),
+                        },
+                        super::NamedValue::Value(value)=>Self::#checked(value)
+                            .ok_or_else(|| crate::enums::status::ErrorCode::ErrorNsupAttrState.into()),
+                    }
+                }
+            }
+            #[cfg(feature = "serde")]
+            impl From<#struct_name> for super::NamedValue<vs::#ty>{
+                fn from(a:#struct_name)->Self{
+                    match #enum_name::try_from(a.clone()){
+                        Ok(v)=>Self::Name(v.visa_name().to_owned()),
+                        Err(_)=>Self::Value(a.value),
+                    }
+                }
+            }
+        )
+        .to_tokens(tokens);
+    }
+    /// Match arms from the kinds of the attribute to its `AttrMeta`
+    fn meta_arms(&self) -> Vec<TokenStream2> {
+        let vis = self.vis.to_string();
+        // "Read/Write", "Read Only" or "Read-Only"
+        let readable = vis.contains("Read");
+        let writable = vis.contains("Write");
+        let scope = if vis.contains("Global") {
+            quote!(Some(super::AttrScope::Global))
+        } else if vis.contains("Local") {
+            quote!(Some(super::AttrScope::Local))
+        } else {
+            quote!(None)
+        };
+        let struct_name = self.struct_name();
+        let (default, classes) = match self.range {
+            Range::NoPort(ref n) => (n.default.meta_expr(&struct_name), Vec::new()),
+            Range::Port(ref p) => (
+                p[0].core.default.meta_expr(&struct_name),
+                p.iter().map(|x| x.port.meta_expr()).collect(),
+            ),
+        };
+        struct_name_to_kind_name(&self.id)
+            .map(|(kind, cfg)| {
+                let arch = if cfg.is_empty() {
+                    None
+                } else if cfg.to_string().contains("64") {
+                    Some("64")
+                } else {
+                    Some("32")
+                };
+                let c_type = match self.ty.core {
+                    TypeCore::UnArch(ref t) => t.to_string(),
+                    TypeCore::Arch(ref a) => a
+                        .iter()
+                        .find(|x| Some(x.arch.base10_digits()) == arch)
+                        .unwrap_or(&a[0])
+                        .core
+                        .to_string(),
+                };
+                let mut range = Vec::new();
+                let mut push = |items: Vec<TokenStream2>| {
+                    for item in items {
+                        if !range.iter().any(|x: &TokenStream2| x.to_string() == item.to_string()) {
+                            range.push(item);
+                        }
+                    }
+                };
+                match self.range {
+                    Range::NoPort(ref n) => push(n.meta_items(arch)),
+                    Range::Port(ref p) => p.iter().for_each(|x| push(x.core.meta_items(arch))),
+                }
+                // the default of a string attribute names no constant
+                let default = if c_type == "ViString" || c_type == "ViRsrc" {
+                    quote!(None)
+                } else {
+                    default.clone()
+                };
+                quote!(
+                    #cfg
+                    AttrKind::#kind => Some(super::AttrMeta{
+                        readable: #readable,
+                        writable: #writable,
+                        scope: #scope,
+                        c_type: #c_type,
+                        default: #default,
+                        range: &[#(#range),*],
+                        applicable_resource_classes: &[#(#classes),*],
+                    })
+                )
+            })
+            .collect()
+    }
+    /// Whether the description tells the value is a bit mask, e.g. "by bit-ORing multiple values together" or "a bit vector with bits 0-9"
+    fn is_bitmask(&self) -> bool {
+        let desc = self.desc.value();
+        BITMASK_PHRASES.iter().any(|x| desc.contains(x))
+    }
+    /// Names and values of the bits of a bit mask attribute.
+    ///
+    /// Taken from the values listed in the range, or from the description, e.g.
+    /// "bits 0-9 corresponding to VI_TRIG_TTL0 through VI_TRIG_ECL1" or "bits 0-6 corresponding to interrupt lines 1-7"
+    fn bit_flags(&self) -> Option<Vec<(Ident, TokenStream2)>> {
+        if !self.is_bitmask() {
+            return None;
+        }
+        if let Range::NoPort(ref n) = self.range {
+            if let Some(values) = n.named_values() {
+                // no bit set is the empty set of flags, not a flag
+                return Some(
+                    values
+                        .into_iter()
+                        .filter(|(_, v)| v.base10_digits() != "0")
+                        .map(|(id, v)| (id, v.into_token_stream()))
+                        .collect(),
+                );
+            }
+        }
+        let desc = self.desc.value();
+        let (_, rest) = desc
+            .split_once("bits ")
+            .or_else(|| desc.split_once("Bits "))?;
+        let (bits, rest) = rest.split_once(' ')?;
+        let (first, last) = parse_span(bits)?;
+        let names = bit_names(rest, last - first + 1)?;
+        Some(
+            names
+                .into_iter()
+                .zip(first..)
+                .map(|(x, i)| {
+                    let i = proc_macro2::Literal::usize_unsuffixed(i);
+                    (Ident::new(&x, self.id.span()), quote!((1 << #i)))
+                })
+                .collect(),
+        )
+    }
+    fn value_flags(&self, tokens: &mut TokenStream2) {
+        let (Some(bits), TypeCore::UnArch(ref ty)) = (self.bit_flags(), &self.ty.core) else {
+            return;
+        };
+        let struct_name = self.struct_name();
+        let flags_name = self.value_enum_name();
+        let doc = format!("Bits of [`{}`]", struct_name);
+        let ids: Vec<_> = bits.iter().map(|(id, _)| id).collect();
+        let names = ids
+            .iter()
+            .zip(unprefixed_names(&ids))
+            .map(|(id, x)| Ident::new(&x, id.span()));
+        let checked_doc = format!("Accepts any combination of the bits of [`{}`]", flags_name);
+        let visa_names = ids.iter().map(|x| LitStr::new(&x.to_string(), x.span()));
+        let values = bits.iter().map(|(_, v)| v);
+        quote!(
+            ::bitflags::bitflags! {
+                #[doc=#doc]
+                #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+                pub struct #flags_name: vs::#ty {
+                    #(
+                        #[doc=#visa_names]
+                        const #names = #values as _;
+                    )*
+                }
+            }
+            impl From<#flags_name> for #struct_name{
+                fn from(v:#flags_name)->Self{
+                    Self{value:v.bits()}
+                }
+            }
+            impl TryFrom<#struct_name> for #flags_name{
+                type Error = crate::Error;
+                /// Fails with [`ErrorNsupAttrState`](crate::enums::status::ErrorCode::ErrorNsupAttrState) if a bit not listed in VISA is set
+                fn try_from(a:#struct_name)->::std::result::Result<Self, Self::Error>{
+                    Self::from_bits(a.value)
+                        .ok_or_else(|| crate::enums::status::ErrorCode::ErrorNsupAttrState.into())
+                }
+            }
+        )
+        .to_tokens(tokens);
+        let checked = self.checked_name();
+        let vis = if self.is_writeable() {
+            quote!(pub)
+        } else {
+            quote!(#[cfg(feature = "serde")] pub(crate))
+        };
+        quote!(
+            impl #struct_name{
+                #[doc=#checked_doc]
+                #vis fn #checked(value:vs::#ty)->Option<Self>{
+                    #flags_name::from_bits(value).map(Self::from)
+                }
+            }
+        )
+        .to_tokens(tokens);
+    }
     fn is_writeable(&self) -> bool {
         self.vis.to_string().contains("Write")
     }
@@ -225,7 +653,17 @@ impl Attr {
         let mut c = |n: &RangeCore| {
             n.check_attr_name(&self.id);
             let mut constructors = TokenStream2::new();
-            n.to_constructor(&self.ty, &mut constructors, self.is_writeable());
+            // checked against the bits in `value_flags` instead
+            let checked = if self.is_bitmask() {
+                Checked::Skip
+            } else if self.is_writeable() {
+                Checked::Pub
+            } else if self.is_pointer() {
+                Checked::Skip
+            } else {
+                Checked::Serde
+            };
+            n.to_constructor(&self.ty, &mut constructors, checked);
             let struct_name = self.struct_name();
             quote!(
                 impl #struct_name{
@@ -286,6 +724,90 @@ impl Attr {
     }
 }
 
+/// Phrases of the descriptions of bit mask attributes
+const BITMASK_PHRASES: [&str; 3] = ["bit-ORing", "bit vector", "bit map"];
+
+/// VXI trigger lines in the order of their bits, as named by "VI_TRIG_TTL0 through VI_TRIG_ECL1"
+const TRIG_LINES: [&str; 10] = [
+    "VI_TRIG_TTL0",
+    "VI_TRIG_TTL1",
+    "VI_TRIG_TTL2",
+    "VI_TRIG_TTL3",
+    "VI_TRIG_TTL4",
+    "VI_TRIG_TTL5",
+    "VI_TRIG_TTL6",
+    "VI_TRIG_TTL7",
+    "VI_TRIG_ECL0",
+    "VI_TRIG_ECL1",
+];
+
+/// First and last number of e.g. `0-9`
+fn parse_span(s: &str) -> Option<(usize, usize)> {
+    let (first, last) = s.split_once('-')?;
+    let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+    (first <= last).then_some((first, last))
+}
+
+/// Names of `count` bits described by e.g. "corresponding to VI_TRIG_TTL0 through VI_TRIG_ECL1",
+/// "corresponding to interrupt lines 1-7" or "represent TTL triggers 0-7"
+fn bit_names(desc: &str, count: usize) -> Option<Vec<String>> {
+    let desc = desc
+        .strip_prefix("corresponding to ")
+        .or_else(|| desc.strip_prefix("represent "))?;
+    let sentence = desc.split('.').next()?;
+    let words: Vec<_> = sentence.split_whitespace().collect();
+    let names: Vec<String> = match words[..] {
+        [first, "through", last, ..] => {
+            let first = TRIG_LINES.iter().position(|x| *x == first)?;
+            let last = TRIG_LINES.iter().position(|x| *x == last)?;
+            TRIG_LINES.get(first..=last)?.iter().map(|x| x.to_string()).collect()
+        }
+        _ => {
+            let at = words.iter().position(|x| parse_span(x).is_some())?;
+            let (first, last) = parse_span(words[at])?;
+            // "interrupt lines 1-7" to INTERRUPT_LINE1 ... INTERRUPT_LINE7
+            let mut prefix: Vec<_> = words[..at].iter().map(|x| x.to_uppercase()).collect();
+            let noun = prefix.pop()?;
+            prefix.push(noun.strip_suffix('S').unwrap_or(&noun).to_string());
+            let prefix = prefix.join("_");
+            (first..=last).map(|i| format!("{prefix}{i}")).collect()
+        }
+    };
+    (names.len() == count).then_some(names)
+}
+
+/// Names of values with their common prefix removed, e.g. `VI_ASRL_PAR_NONE` to `NONE`
+fn unprefixed_names(ids: &[&Ident]) -> Vec<String> {
+    let names: Vec<String> = ids.iter().map(|x| x.to_string()).collect();
+    let segments: Vec<Vec<&str>> = names.iter().map(|x| x.split('_').collect()).collect();
+    let mut common = 0;
+    while segments
+        .iter()
+        .all(|x| x.len() > common + 1 && x[common] == segments[0][common])
+    {
+        common += 1;
+    }
+    segments
+        .iter()
+        .map(|seg| {
+            let mut start = common;
+            // an identifier can't start with a digit, keep the segment before it
+            while start > 0 && seg[start].starts_with(|c: char| c.is_ascii_digit()) {
+                start -= 1;
+            }
+            seg[start..].join("_")
+        })
+        .collect()
+}
+
+/// Pascal case names of values with their common prefix removed, e.g. `VI_ASRL_PAR_NONE` to `None`
+fn variant_names(ids: &[&Ident]) -> Vec<Ident> {
+    ids.iter()
+        .zip(unprefixed_names(ids))
+        .map(|(id, x)| Ident::new(&crate::screaming_snake_case_to_pascal_case(&x), id.span()))
+        .collect()
+}
+
 const PLATFORM_DEPENDENT_TYPE: [&str; 8] = [
     "VI_ATTR_USER_DATA",
     "VI_ATTR_RET_COUNT",
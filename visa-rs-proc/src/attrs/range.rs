@@ -55,6 +55,36 @@ impl DefaultValue {
     }
 }
 
+impl DefaultValue {
+    /// `Option<i128>` expression of the default, for `AttrMeta`
+    pub fn meta_expr(&self, struct_name: &Ident) -> TokenStream2 {
+        match self {
+            DefaultValue::Num(n) => {
+                let n = int_lit(n);
+                quote!(Some(#n))
+            }
+            DefaultValue::Ident(i) => quote!(Some(#struct_name::#i.value as i128)),
+            DefaultValue::Key { char, .. } => {
+                let char = int_lit(char);
+                quote!(Some(#char))
+            }
+            DefaultValue::NumDesc { num, .. } => {
+                let num = int_lit(num);
+                quote!(Some(#num))
+            }
+            DefaultValue::NA(_) => quote!(None),
+        }
+    }
+}
+
+/// `i128` literal of `n`, so values of any VISA type fit
+fn int_lit(n: &LitInt) -> TokenStream2 {
+    match n.base10_parse::<i128>() {
+        Ok(v) => proc_macro2::Literal::i128_suffixed(v).to_token_stream(),
+        Err(e) => e.to_compile_error(),
+    }
+}
+
 impl PartialEq for DefaultValue {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -143,6 +173,22 @@ pub enum Port {
     USBInstr,
 }
 
+impl Port {
+    /// The `ResourceClass` expression
+    pub fn meta_expr(&self) -> TokenStream2 {
+        use Port::*;
+        match self {
+            PXI => quote!(super::ResourceClass::Pxi),
+            Serial => quote!(super::ResourceClass::Serial),
+            GPIB => quote!(super::ResourceClass::Gpib),
+            VXI => quote!(super::ResourceClass::Vxi),
+            TCPIP => quote!(super::ResourceClass::Tcpip),
+            USBRaw => quote!(super::ResourceClass::UsbRaw),
+            USBInstr => quote!(super::ResourceClass::UsbInstr),
+        }
+    }
+}
+
 impl FromStr for Port {
     type Err = String;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -204,7 +250,7 @@ impl Parse for Range {
 
 pub struct PortRange {
     pub(crate) core: RangeCore,
-    pub(crate) _port: Port,
+    pub(crate) port: Port,
 }
 
 impl Parse for PortRange {
@@ -214,7 +260,7 @@ impl Parse for PortRange {
         let c;
         braced!(c in input);
         Ok(Self {
-            _port: port,
+            port,
             core: c.parse()?,
         })
     }
@@ -259,6 +305,36 @@ impl RangeCore {
             bound: Bound::NoArch(BoundCore::Stream(ranges)),
         }
     }
+    /// Items of the range as `RangeItem` expressions, `arch` selects the bound of platform dependent attributes
+    pub fn meta_items(&self, arch: Option<&str>) -> Vec<TokenStream2> {
+        let core = match self.bound {
+            Bound::NoArch(ref n) => n,
+            Bound::Arch(ref a) => match a.iter().find(|x| Some(x.arch.base10_digits()) == arch) {
+                Some(x) => &x.core,
+                None => return Vec::new(),
+            },
+        };
+        let BoundCore::Stream(ref items) = core else {
+            return Vec::new();
+        };
+        items.iter().map(BoundItem::meta_item).collect()
+    }
+    /// Named values listed as the range, `None` unless every item is a single named value
+    pub fn named_values(&self) -> Option<Vec<(Ident, LitInt)>> {
+        match self.bound {
+            Bound::NoArch(BoundCore::Stream(ref items)) if !items.is_empty() => items
+                .iter()
+                .map(|x| match x {
+                    BoundItem::Single(BoundToken::Ident {
+                        id,
+                        value: Some(value),
+                    }) => Some((id.clone(), value.clone())),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
     pub fn check_attr_name(&self, tar: &Ident) {
         if let Some(n) = self.attr_name.as_ref() {
             super::match_ident(tar, n);
@@ -266,12 +342,23 @@ impl RangeCore {
     }
 }
 
+/// How the checked constructor is generated for an attribute
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Checked {
+    /// public `new_checked`, for writable attributes
+    Pub,
+    /// crate-private `deserialize_checked`, only to deserialize read-only attributes
+    Serde,
+    /// generated elsewhere, i.e. from the bits of a bit mask
+    Skip,
+}
+
 impl RangeCore {
     pub fn to_constructor(
         &self,
         ty: &Type,
         tokens: &mut proc_macro2::TokenStream,
-        writeable: bool,
+        checked: Checked,
     ) {
         match self.bound {
             Bound::Arch(ref arch_bound) => match ty.core {
@@ -290,7 +377,7 @@ impl RangeCore {
                                     &tya.core,
                                     &cfg.into(),
                                     tokens,
-                                    writeable,
+                                    checked,
                                 );
                             } else if let Ok(32) = bound.arch.base10_parse() {
                                 let cfg = quote_spanned!(bound.arch.span()=> #[cfg(target_pointer_width = "32")]);
@@ -298,7 +385,7 @@ impl RangeCore {
                                     &tya.core,
                                     &cfg.into(),
                                     tokens,
-                                    writeable,
+                                    checked,
                                 );
                             }
                         });
@@ -309,13 +396,13 @@ impl RangeCore {
                             quote_spanned!(bound.arch.span()=> #[cfg(target_pointer_width = "64")]);
                         bound
                             .core
-                            .to_constructor(tyu, &cfg.into(), tokens, writeable);
+                            .to_constructor(tyu, &cfg.into(), tokens, checked);
                     } else if let Ok(32) = bound.arch.base10_parse() {
                         let cfg =
                             quote_spanned!(bound.arch.span()=> #[cfg(target_pointer_width = "32")]);
                         bound
                             .core
-                            .to_constructor(tyu, &cfg.into(), tokens, writeable);
+                            .to_constructor(tyu, &cfg.into(), tokens, checked);
                     }
                 }),
             },
@@ -324,14 +411,14 @@ impl RangeCore {
                     if let Ok(64) = tya.arch.base10_parse() {
                         let cfg =
                             quote_spanned!(tya.arch.span()=> #[cfg(target_pointer_width = "64")]);
-                        n.to_constructor(&tya.core, &cfg.into(), tokens, writeable);
+                        n.to_constructor(&tya.core, &cfg.into(), tokens, checked);
                     } else if let Ok(32) = tya.arch.base10_parse() {
                         let cfg =
                             quote_spanned!(tya.arch.span()=> #[cfg(target_pointer_width = "32")]);
-                        n.to_constructor(&tya.core, &cfg.into(), tokens, writeable);
+                        n.to_constructor(&tya.core, &cfg.into(), tokens, checked);
                     }
                 }),
-                super::TypeCore::UnArch(ref u) => n.to_constructor(u, &None, tokens, writeable),
+                super::TypeCore::UnArch(ref u) => n.to_constructor(u, &None, tokens, checked),
             },
         }
     }
@@ -409,7 +496,7 @@ impl BoundCore {
         ty: &Ident,
         cfg: &Option<TokenStream2>,
         tokens: &mut TokenStream2,
-        is_writeable: bool,
+        checked: Checked,
     ) {
         let new_uncheck = quote_spanned!( ty.span()=>
             #cfg
@@ -418,10 +505,10 @@ impl BoundCore {
             }
         );
         let mut new = None;
-        let mut new_check = None;
-        match self {
+        // every value is accepted if the range is not listed
+        let check = match self {
             BoundCore::NA(_) => {
-                if is_writeable {
+                if checked == Checked::Pub {
                     new = quote_spanned!(ty.span()=>
                         #cfg
                         pub fn new(value:vs::#ty)->Self{
@@ -429,36 +516,45 @@ impl BoundCore {
                         }
                     )
                     .into();
-                    new_check = quote_spanned!(ty.span()=>
-                        #cfg
-                        pub fn new_checked(value:vs::#ty)->Option<Self>{
-                            Some(Self{value})
-                        }
-                    )
-                    .into();
                 }
+                quote!(true)
             }
-            BoundCore::Unreachable(_) => (),
+            BoundCore::Unreachable(_) => quote!(true),
             BoundCore::Stream(s) => {
                 s.iter()
                     .for_each(|x| x.sub_constructor(ty, cfg).to_tokens(tokens));
-                if is_writeable {
-                    let checks = s.iter().map(|x| x.check_range(ty));
-                    new_check = quote_spanned!(ty.span()=>
-                        #cfg
-                        #[allow(unused_parens)]
-                        pub fn new_checked(value:vs::#ty)->Option<Self>{
-                            if #(#checks)||*{
-                                Some(Self{value})
-                            }else{
-                                None
-                            }
-                        }
-                    )
-                    .into();
-                }
+                let checks = s.iter().map(|x| x.check_range(ty));
+                quote!(#(#checks)||*)
             }
-        }
+        };
+        let new_check = match (checked, self) {
+            (Checked::Skip, _) | (Checked::Pub, BoundCore::Unreachable(_)) => None,
+            (Checked::Pub, _) => quote_spanned!(ty.span()=>
+                #cfg
+                #[allow(unused_parens)]
+                pub fn new_checked(value:vs::#ty)->Option<Self>{
+                    if #check{
+                        Some(Self{value})
+                    }else{
+                        None
+                    }
+                }
+            )
+            .into(),
+            (Checked::Serde, _) => quote_spanned!(ty.span()=>
+                #cfg
+                #[cfg(feature = "serde")]
+                #[allow(unused_parens)]
+                pub(crate) fn deserialize_checked(value:vs::#ty)->Option<Self>{
+                    if #check{
+                        Some(Self{value})
+                    }else{
+                        None
+                    }
+                }
+            )
+            .into(),
+        };
 
         quote!(
             #new
@@ -500,6 +596,27 @@ pub enum BoundItem {
 }
 
 impl BoundItem {
+    fn meta_item(&self) -> TokenStream2 {
+        match self {
+            BoundItem::Single(s) => {
+                let name = s.meta_name();
+                let value = s.meta_value();
+                quote!(super::RangeItem::Value{name:#name, value:#value})
+            }
+            BoundItem::Range((l, h)) => {
+                let (min, max) = (l.meta_value(), h.meta_value());
+                quote!(super::RangeItem::Range{name:None, min:#min, max:#max})
+            }
+            BoundItem::NamedRange {
+                name,
+                range: (l, h),
+            } => {
+                let name = name.to_string();
+                let (min, max) = (l.meta_value(), h.meta_value());
+                quote!(super::RangeItem::Range{name:Some(#name), min:#min, max:#max})
+            }
+        }
+    }
     fn check_range(&self, ty: &Ident) -> TokenStream2 {
         match self {
             BoundItem::Single(s) => quote_spanned!(s.span()=>#s as vs::#ty == value),
@@ -669,6 +786,24 @@ pub enum BoundToken {
     Num(LitInt),
 }
 
+impl BoundToken {
+    fn meta_name(&self) -> TokenStream2 {
+        match self {
+            BoundToken::Ident { id, .. } => {
+                let id = id.to_string();
+                quote!(Some(#id))
+            }
+            BoundToken::Num(_) => quote!(None),
+        }
+    }
+    fn meta_value(&self) -> TokenStream2 {
+        match self {
+            BoundToken::Ident { value: Some(v), .. } | BoundToken::Num(v) => int_lit(v),
+            BoundToken::Ident { id, value: None } => quote!((vs::#id as i128)),
+        }
+    }
+}
+
 impl std::fmt::Debug for BoundToken {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {